@@ -0,0 +1,144 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Administrative access to an existing database, in the spirit of
+//! RocksDB's `ldb` tool.
+//!
+//! `Admin` opens every column family of a database with default options,
+//! so it cannot open databases that rely on a custom comparator. Databases
+//! using a merge operator can be opened, but merged keys cannot be read.
+//!
+//! With the `cli` feature, the same operations are available from the
+//! `rocksdb-admin` binary.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::DB;
+//! use rocksdb::admin::Admin;
+//!
+//! let path = "path/for/rocksdb/storage_admin";
+//! DB::open_default(path).unwrap().put(b"k", b"v").unwrap();
+//!
+//! let admin = Admin::open(path).unwrap();
+//! assert_eq!(admin.column_families(), vec!["default".to_owned()]);
+//! assert_eq!(admin.get(None, b"k").unwrap(), Some(b"v".to_vec()));
+//! ```
+
+use std::path::Path;
+
+use {ColumnFamily, DB, Direction, Error, ErrorKind, IteratorMode, Options};
+
+/// A key-value pair returned by `Admin::scan`.
+pub type KeyValue = (Box<[u8]>, Box<[u8]>);
+
+/// An existing database opened with all of its column families.
+pub struct Admin {
+    db: DB,
+    cfs: Vec<String>,
+}
+
+impl Admin {
+    /// Opens the database at `path`, failing if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Admin, Error> {
+        let path = path.as_ref();
+        let cfs = try!(DB::list_cf(&Options::default(), path));
+        let names: Vec<&str> = cfs.iter()
+            .map(|cf| cf.as_str())
+            .filter(|cf| *cf != "default")
+            .collect();
+        let db = try!(DB::open_cf(&Options::default(), path, &names));
+        Ok(Admin { db: db, cfs: cfs })
+    }
+
+    /// Returns the underlying database.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// Returns the names of all column families, including `default`.
+    pub fn column_families(&self) -> Vec<String> {
+        self.cfs.clone()
+    }
+
+    /// Resolves an optional column family name, where `None` is the default
+    /// column family.
+    fn cf(&self, name: Option<&str>) -> Result<Option<&ColumnFamily>, Error> {
+        match name {
+            None | Some("default") => Ok(None),
+            Some(name) => match self.db.cf_handle(name) {
+                Some(cf) => Ok(Some(cf)),
+                None => Err(Error::with_kind(ErrorKind::InvalidArgument,
+                                             format!("no column family {}", name))),
+            },
+        }
+    }
+
+    pub fn get(&self, cf: Option<&str>, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let value = match try!(self.cf(cf)) {
+            Some(cf) => try!(self.db.get_cf(cf, key)),
+            None => try!(self.db.get(key)),
+        };
+        Ok(value.map(|v| v.to_vec()))
+    }
+
+    pub fn put(&self, cf: Option<&str>, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        match try!(self.cf(cf)) {
+            Some(cf) => self.db.put_cf(cf, key, value),
+            None => self.db.put(key, value),
+        }
+    }
+
+    pub fn delete(&self, cf: Option<&str>, key: &[u8]) -> Result<(), Error> {
+        match try!(self.cf(cf)) {
+            Some(cf) => self.db.delete_cf(cf, key),
+            None => self.db.delete(key),
+        }
+    }
+
+    /// Returns up to `limit` key-value pairs in key order, starting at
+    /// `from` or at the first key.
+    pub fn scan(
+        &self,
+        cf: Option<&str>,
+        from: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<KeyValue>, Error> {
+        let mode = match from {
+            Some(key) => IteratorMode::From(key, Direction::Forward),
+            None => IteratorMode::Start,
+        };
+        let iter = match try!(self.cf(cf)) {
+            Some(cf) => try!(self.db.iterator_cf(cf, mode)),
+            None => self.db.iterator(mode),
+        };
+        Ok(iter.take(limit).collect())
+    }
+
+    /// Returns the value of a property such as `rocksdb.stats`; see
+    /// `DB::property_value`.
+    pub fn property(&self, cf: Option<&str>, name: &str) -> Result<Option<String>, Error> {
+        match try!(self.cf(cf)) {
+            Some(cf) => self.db.property_value_cf(cf, name),
+            None => self.db.property_value(name),
+        }
+    }
+
+    /// Returns the SST files of a column family in the current manifest,
+    /// as listed by the `rocksdb.sstables` property: a line for every level,
+    /// followed by the number, size and key range of each of its files.
+    pub fn manifest(&self, cf: Option<&str>) -> Result<String, Error> {
+        Ok(try!(self.property(cf, "rocksdb.sstables")).unwrap_or_default())
+    }
+}
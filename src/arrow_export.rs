@@ -0,0 +1,194 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scanning key ranges into Arrow `RecordBatch`es, available with the
+//! `arrow` feature, e.g. to feed DataFusion or Polars.
+//!
+//! By default every batch has a binary `key` and a binary `value` column. A
+//! `Decoder` can instead turn every entry into a row of its own schema.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::DB;
+//! use rocksdb::arrow_export::{BinaryDecoder, RecordBatches};
+//!
+//! let db = DB::open_default("path/for/rocksdb/storage_arrow").unwrap();
+//! for i in 0..10u8 {
+//!     db.put(&[i], b"value").unwrap();
+//! }
+//!
+//! let batches = RecordBatches::new(&db, None, Some(&[2]), None, 4, BinaryDecoder::new()).unwrap();
+//! let rows: Vec<usize> = batches.map(|batch| batch.unwrap().num_rows()).collect();
+//! assert_eq!(rows, vec![4, 4]);
+//! ```
+
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BinaryBuilder};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+
+use {ColumnFamily, DB, DBIterator, Direction, Error, ErrorKind, IteratorMode, ReadOptions};
+
+fn arrow_error(e: ArrowError) -> Error {
+    Error::with_kind(ErrorKind::InvalidArgument, e.to_string())
+}
+
+/// Turns entries into the rows of record batches.
+pub trait Decoder {
+    /// Returns the schema of the batches.
+    fn schema(&self) -> SchemaRef;
+
+    /// Appends the row of an entry to the columns being built. Errors are
+    /// returned from the scan as `ErrorKind::Corruption`.
+    fn append(&mut self, key: &[u8], value: &[u8]) -> Result<(), ArrowError>;
+
+    /// Returns the columns built since the last call, in the order of the
+    /// schema, and starts new ones.
+    fn finish(&mut self) -> Result<Vec<ArrayRef>, ArrowError>;
+}
+
+/// A `Decoder` producing non-nullable binary `key` and `value` columns.
+pub struct BinaryDecoder {
+    schema: SchemaRef,
+    keys: BinaryBuilder,
+    values: BinaryBuilder,
+}
+
+impl BinaryDecoder {
+    pub fn new() -> BinaryDecoder {
+        BinaryDecoder {
+            schema: Arc::new(Schema::new(vec![
+                Field::new("key", DataType::Binary, false),
+                Field::new("value", DataType::Binary, false),
+            ])),
+            keys: BinaryBuilder::new(),
+            values: BinaryBuilder::new(),
+        }
+    }
+}
+
+impl Default for BinaryDecoder {
+    fn default() -> BinaryDecoder {
+        BinaryDecoder::new()
+    }
+}
+
+impl Decoder for BinaryDecoder {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn append(&mut self, key: &[u8], value: &[u8]) -> Result<(), ArrowError> {
+        self.keys.append_value(key);
+        self.values.append_value(value);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<Vec<ArrayRef>, ArrowError> {
+        Ok(vec![Arc::new(self.keys.finish()), Arc::new(self.values.finish())])
+    }
+}
+
+/// An iterator over the entries of a key range as record batches of up to
+/// `batch_size` rows.
+pub struct RecordBatches<D> {
+    iter: DBIterator,
+    batch_size: usize,
+    decoder: D,
+    done: bool,
+}
+
+impl<D: Decoder> RecordBatches<D> {
+    /// Scans `cf`, or the default column family, from `start` up to but
+    /// excluding `end`.
+    pub fn new(
+        db: &DB,
+        cf: Option<&ColumnFamily>,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        batch_size: usize,
+        decoder: D,
+    ) -> Result<RecordBatches<D>, Error> {
+        if batch_size == 0 {
+            return Err(Error::with_kind(ErrorKind::InvalidArgument,
+                                        "batch_size must not be 0".to_owned()));
+        }
+        let mode = match start {
+            Some(start) => IteratorMode::From(start, Direction::Forward),
+            None => IteratorMode::Start,
+        };
+        let mut readopts = ReadOptions::default();
+        if let Some(end) = end {
+            readopts.set_iterate_upper_bound(end);
+        }
+        let iter = match cf {
+            Some(cf) => try!(db.iterator_cf_opt(cf, mode, readopts)),
+            None => db.iterator_opt(mode, readopts),
+        };
+        Ok(RecordBatches {
+            iter: iter,
+            batch_size: batch_size,
+            decoder: decoder,
+            done: false,
+        })
+    }
+
+    /// Returns the schema of the batches.
+    pub fn schema(&self) -> SchemaRef {
+        self.decoder.schema()
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, Error> {
+        let mut rows = 0;
+        while rows < self.batch_size {
+            let (key, value) = match self.iter.next() {
+                Some(entry) => entry,
+                None => break,
+            };
+            try!(self.decoder.append(&key, &value).map_err(|e| {
+                Error::with_kind(ErrorKind::Corruption, format!("cannot decode entry: {}", e))
+            }));
+            rows += 1;
+        }
+        if rows < self.batch_size {
+            self.done = true;
+        }
+        if rows == 0 {
+            return Ok(None);
+        }
+        let columns = try!(self.decoder.finish().map_err(arrow_error));
+        let batch = try!(RecordBatch::try_new(self.decoder.schema(), columns).map_err(arrow_error));
+        Ok(Some(batch))
+    }
+}
+
+impl<D: Decoder> Iterator for RecordBatches<D> {
+    type Item = Result<RecordBatch, Error>;
+
+    fn next(&mut self) -> Option<Result<RecordBatch, Error>> {
+        if self.done {
+            return None;
+        }
+        match self.next_batch() {
+            Ok(batch) => batch.map(Ok),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
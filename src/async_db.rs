@@ -0,0 +1,180 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Futures-based access to a `DB`, available with the `tokio` feature.
+//!
+//! RocksDB calls block the calling thread on disk I/O, which stalls every
+//! task scheduled on an async executor. `AsyncDB` runs each operation on
+//! tokio's blocking thread pool instead and returns a future resolving to
+//! its result. An operation starts when its future is first polled, which
+//! must happen within a tokio runtime; the future itself can be created
+//! anywhere.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate rocksdb;
+//! extern crate tokio;
+//!
+//! use rocksdb::DB;
+//! use rocksdb::async_db::AsyncDB;
+//!
+//! # fn main() {
+//! let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+//! let db = AsyncDB::new(DB::open_default("path/for/rocksdb/storage_async").unwrap());
+//!
+//! runtime.block_on(db.put(b"my key", b"my value")).unwrap();
+//! let value = runtime.block_on(db.get(b"my key")).unwrap();
+//! assert_eq!(value, Some(b"my value".to_vec()));
+//! # }
+//! ```
+
+use std::future::Future;
+use std::panic;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::task::{self, JoinHandle};
+
+use {DB, Direction, Error, IteratorMode, WriteBatch};
+
+/// A key-value pair returned by `AsyncDB::scan`.
+pub type KeyValue = (Box<[u8]>, Box<[u8]>);
+
+/// A cheaply clonable handle running `DB` operations on tokio's blocking
+/// thread pool.
+#[derive(Clone)]
+pub struct AsyncDB {
+    db: Arc<DB>,
+}
+
+/// A future resolving to the result of an operation run on the blocking
+/// thread pool. The operation is spawned on the first poll, so dropping the
+/// future before then skips it. A panic in the operation is propagated to
+/// the task polling this future.
+pub struct Blocking<T> {
+    state: State<T>,
+}
+
+enum State<T> {
+    Idle(Option<Box<dyn FnOnce() -> T + Send>>),
+    Running(JoinHandle<T>),
+}
+
+impl<T: Send + 'static> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+        if let State::Idle(ref mut f) = self.state {
+            let f = f.take().unwrap();
+            self.state = State::Running(task::spawn_blocking(f));
+        }
+        let handle = match self.state {
+            State::Running(ref mut handle) => handle,
+            State::Idle(_) => unreachable!(),
+        };
+        match Pin::new(handle).poll(cx) {
+            Poll::Ready(Ok(v)) => Poll::Ready(v),
+            Poll::Ready(Err(e)) => {
+                if e.is_panic() {
+                    panic::resume_unwind(e.into_panic());
+                }
+                panic!("rocksdb blocking task was cancelled");
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl AsyncDB {
+    pub fn new(db: DB) -> AsyncDB {
+        AsyncDB::from_arc(Arc::new(db))
+    }
+
+    /// Wraps a database that is also used synchronously elsewhere.
+    pub fn from_arc(db: Arc<DB>) -> AsyncDB {
+        AsyncDB { db: db }
+    }
+
+    /// Returns the underlying database, for synchronous use.
+    pub fn db(&self) -> &Arc<DB> {
+        &self.db
+    }
+
+    fn spawn<T, F>(&self, f: F) -> Blocking<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&DB) -> T + Send + 'static,
+    {
+        let db = self.db.clone();
+        Blocking { state: State::Idle(Some(Box::new(move || f(&db)))) }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Blocking<Result<Option<Vec<u8>>, Error>> {
+        let key = key.to_vec();
+        self.spawn(move |db| db.get(&key).map(|v| v.map(|v| v.to_vec())))
+    }
+
+    /// Looks up several keys in a single blocking task, returning the
+    /// values in the same order as `keys`.
+    pub fn multi_get<I, K>(&self, keys: I) -> Blocking<Result<Vec<Option<Vec<u8>>>, Error>>
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<[u8]>,
+    {
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(|k| k.as_ref().to_vec()).collect();
+        self.spawn(move |db| {
+            db.multi_get(&keys)
+                .into_iter()
+                .map(|v| v.map(|v| v.map(|v| v.to_vec())))
+                .collect()
+        })
+    }
+
+    pub fn put(&self, key: &[u8], value: &[u8]) -> Blocking<Result<(), Error>> {
+        let key = key.to_vec();
+        let value = value.to_vec();
+        self.spawn(move |db| db.put(&key, &value))
+    }
+
+    pub fn delete(&self, key: &[u8]) -> Blocking<Result<(), Error>> {
+        let key = key.to_vec();
+        self.spawn(move |db| db.delete(&key))
+    }
+
+    pub fn write(&self, batch: WriteBatch) -> Blocking<Result<(), Error>> {
+        self.spawn(move |db| db.write(batch))
+    }
+
+    /// Reads up to `limit` key-value pairs in one blocking task, starting at
+    /// `from` (or at the first or last key if `None`) and moving in
+    /// `direction`.
+    pub fn scan(
+        &self,
+        from: Option<&[u8]>,
+        direction: Direction,
+        limit: usize,
+    ) -> Blocking<Vec<KeyValue>> {
+        let from = from.map(|k| k.to_vec());
+        self.spawn(move |db| {
+            let mode = match (&from, direction) {
+                (&Some(ref key), direction) => IteratorMode::From(key, direction),
+                (&None, Direction::Forward) => IteratorMode::Start,
+                (&None, Direction::Reverse) => IteratorMode::End,
+            };
+            db.iterator(mode).take(limit).collect()
+        })
+    }
+}
@@ -80,6 +80,56 @@ impl BackupEngine {
             Ok(())
         }
     }
+
+    /// Returns the ids of the backups held by the engine, oldest first.
+    /// Backups the engine found to be corrupted are left out.
+    pub fn backup_ids(&self) -> Vec<u32> {
+        unsafe {
+            let info = ffi::rocksdb_backup_engine_get_backup_info(self.inner);
+            let count = ffi::rocksdb_backup_engine_info_count(info);
+            let mut ids: Vec<u32> = (0..count)
+                .map(|i| ffi::rocksdb_backup_engine_info_backup_id(info, i))
+                .collect();
+            ffi::rocksdb_backup_engine_info_destroy(info);
+            ids.sort();
+            ids
+        }
+    }
+
+    /// Restores the most recent backup into `db_dir`, and its log files into
+    /// `wal_dir`, which is usually the same directory.
+    pub fn restore_from_latest_backup<D: AsRef<Path>, W: AsRef<Path>>(
+        &mut self,
+        db_dir: D,
+        wal_dir: W,
+        opts: &RestoreOptions,
+    ) -> Result<(), Error> {
+        let c_db_dir = match CString::new(db_dir.as_ref().to_string_lossy().as_bytes()) {
+            Ok(c) => c,
+            Err(_) => {
+                return Err(Error::new(
+                    "Failed to convert db_dir to CString when restoring backup".to_owned(),
+                ))
+            }
+        };
+        let c_wal_dir = match CString::new(wal_dir.as_ref().to_string_lossy().as_bytes()) {
+            Ok(c) => c,
+            Err(_) => {
+                return Err(Error::new(
+                    "Failed to convert wal_dir to CString when restoring backup".to_owned(),
+                ))
+            }
+        };
+        unsafe {
+            ffi_try!(ffi::rocksdb_backup_engine_restore_db_from_latest_backup(
+                self.inner,
+                c_db_dir.as_ptr(),
+                c_wal_dir.as_ptr(),
+                opts.inner,
+            ));
+            Ok(())
+        }
+    }
 }
 
 impl BackupEngineOptions {
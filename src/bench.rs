@@ -0,0 +1,375 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Workloads in the style of RocksDB's `db_bench`, available with the
+//! `bench` feature, to measure the effect of option changes.
+//!
+//! Keys are the big-endian index of the key, padded with zeroes to the key
+//! size, so `FillSeq` writes them in order. Values are random bytes.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use rocksdb::DB;
+//! use rocksdb::bench::{Bench, Workload};
+//!
+//! let db = Arc::new(DB::open_default("path/for/rocksdb/storage_bench").unwrap());
+//! let fill = Bench::new(Workload::FillSeq).num(1000).run(&db).unwrap();
+//! let read = Bench::new(Workload::ReadRandom).num(1000).threads(2).run(&db).unwrap();
+//! assert_eq!(read.found, read.ops);
+//! println!("{}\n{}", fill, read);
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use {DB, Error, ErrorKind, WriteOptions};
+
+// The size of the random data values are taken from.
+const VALUE_DATA_SIZE: usize = 1 << 20;
+
+/// The operations performed by a benchmark.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Workload {
+    /// Writes every key in order.
+    FillSeq,
+    /// Writes keys in random order.
+    FillRandom,
+    /// Reads keys in random order.
+    ReadRandom,
+    /// Reads keys in random order while one more thread keeps writing
+    /// random keys. Only the reads are counted.
+    ReadWhileWriting,
+}
+
+impl Workload {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Workload::FillSeq => "fillseq",
+            Workload::FillRandom => "fillrandom",
+            Workload::ReadRandom => "readrandom",
+            Workload::ReadWhileWriting => "readwhilewriting",
+        }
+    }
+}
+
+/// The results of a benchmark.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchReport {
+    pub workload: Workload,
+    /// The number of operations, over all threads.
+    pub ops: u64,
+    /// The number of reads that found their key.
+    pub found: u64,
+    /// The size of the keys and values written or read.
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl BenchReport {
+    fn secs(&self) -> f64 {
+        self.elapsed.as_secs() as f64 + self.elapsed.subsec_nanos() as f64 / 1e9
+    }
+
+    pub fn ops_per_sec(&self) -> f64 {
+        self.ops as f64 / self.secs()
+    }
+
+    pub fn micros_per_op(&self) -> f64 {
+        self.secs() * 1e6 / self.ops as f64
+    }
+
+    pub fn mb_per_sec(&self) -> f64 {
+        self.bytes as f64 / 1048576.0 / self.secs()
+    }
+}
+
+impl fmt::Display for BenchReport {
+    /// Formats the report like `db_bench` does.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(
+            f,
+            "{:<16} : {:>11.3} micros/op {} ops/sec;",
+            self.workload.name(),
+            self.micros_per_op(),
+            self.ops_per_sec() as u64
+        ));
+        if self.bytes > 0 {
+            try!(write!(f, " {:>6.1} MB/s", self.mb_per_sec()));
+        }
+        match self.workload {
+            Workload::ReadRandom | Workload::ReadWhileWriting => {
+                write!(f, " ({} of {} found)", self.found, self.ops)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// A xorshift64* generator, so that runs with the same seed are comparable.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // The state must not be zero.
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+/// A benchmark run against a database.
+#[derive(Debug, Clone)]
+pub struct Bench {
+    workload: Workload,
+    num: u64,
+    key_size: usize,
+    value_size: usize,
+    threads: usize,
+    seed: u64,
+    disable_wal: bool,
+}
+
+impl Bench {
+    pub fn new(workload: Workload) -> Bench {
+        Bench {
+            workload: workload,
+            num: 1000000,
+            key_size: 16,
+            value_size: 100,
+            threads: 1,
+            seed: 301,
+            disable_wal: false,
+        }
+    }
+
+    /// Sets the number of keys, which is also the total number of
+    /// operations, rounded down to a multiple of the number of threads.
+    ///
+    /// Default: `1000000`
+    pub fn num(mut self, num: u64) -> Bench {
+        self.num = num;
+        self
+    }
+
+    /// Sets the size of the keys, which is at least 8.
+    ///
+    /// Default: `16`
+    pub fn key_size(mut self, key_size: usize) -> Bench {
+        self.key_size = key_size;
+        self
+    }
+
+    /// Default: `100`
+    pub fn value_size(mut self, value_size: usize) -> Bench {
+        self.value_size = value_size;
+        self
+    }
+
+    /// Sets the number of threads the operations are spread over.
+    ///
+    /// Default: `1`
+    pub fn threads(mut self, threads: usize) -> Bench {
+        self.threads = threads;
+        self
+    }
+
+    /// Seeds the random key order and values.
+    ///
+    /// Default: `301`
+    pub fn seed(mut self, seed: u64) -> Bench {
+        self.seed = seed;
+        self
+    }
+
+    /// Default: `false`
+    pub fn disable_wal(mut self, disable_wal: bool) -> Bench {
+        self.disable_wal = disable_wal;
+        self
+    }
+
+    fn key(&self, index: u64, key: &mut Vec<u8>) {
+        key.clear();
+        key.extend_from_slice(&[
+            (index >> 56) as u8,
+            (index >> 48) as u8,
+            (index >> 40) as u8,
+            (index >> 32) as u8,
+            (index >> 24) as u8,
+            (index >> 16) as u8,
+            (index >> 8) as u8,
+            index as u8,
+        ]);
+        key.resize(self.key_size, 0);
+    }
+
+    // Runs `ops` operations of one thread, returning the number of reads
+    // that found their key and the bytes written or read.
+    fn run_thread(
+        &self,
+        db: &DB,
+        workload: Workload,
+        thread: u64,
+        ops: u64,
+        stop: &AtomicBool,
+    ) -> Result<(u64, u64), Error> {
+        let mut rng = Rng::new(self.seed ^ thread);
+        let mut wo = WriteOptions::default();
+        wo.disable_wal(self.disable_wal);
+        let data: Vec<u8> = (0..VALUE_DATA_SIZE / 8)
+            .flat_map(|_| {
+                let n = rng.next();
+                (0..8).map(move |i| (n >> (8 * i)) as u8)
+            })
+            .collect();
+        let mut key = Vec::with_capacity(self.key_size);
+        let (mut found, mut bytes, mut offset) = (0, 0, 0);
+        let mut i = 0;
+        while i < ops && !stop.load(Ordering::Relaxed) {
+            match workload {
+                Workload::FillSeq | Workload::FillRandom => {
+                    let index = match workload {
+                        Workload::FillSeq => thread * ops + i,
+                        _ => rng.next() % self.num,
+                    };
+                    self.key(index, &mut key);
+                    if offset + self.value_size > data.len() {
+                        offset = 0;
+                    }
+                    let value = &data[offset..offset + self.value_size];
+                    offset += self.value_size;
+                    try!(db.put_opt(&key, value, &wo));
+                    bytes += (key.len() + value.len()) as u64;
+                }
+                Workload::ReadRandom | Workload::ReadWhileWriting => {
+                    self.key(rng.next() % self.num, &mut key);
+                    if let Some(value) = try!(db.get(&key)) {
+                        found += 1;
+                        bytes += (key.len() + value.len()) as u64;
+                    }
+                }
+            }
+            i += 1;
+        }
+        Ok((found, bytes))
+    }
+
+    /// Runs the benchmark. Reads only find keys written before, e.g. by a
+    /// `FillSeq` run with the same number of keys and key size.
+    pub fn run(&self, db: &Arc<DB>) -> Result<BenchReport, Error> {
+        if self.num == 0 || self.key_size < 8 || self.threads == 0 ||
+            self.value_size > VALUE_DATA_SIZE
+        {
+            return Err(Error::with_kind(
+                ErrorKind::InvalidArgument,
+                "there must be at least one key and one thread, keys must \
+                 have at least 8 bytes, and values at most 1MB"
+                    .to_owned(),
+            ));
+        }
+        let per_thread = self.num / self.threads as u64;
+        let stop = Arc::new(AtomicBool::new(false));
+        let start = Instant::now();
+
+        let writer = if self.workload == Workload::ReadWhileWriting {
+            let (bench, db, stop) = (self.clone(), db.clone(), stop.clone());
+            Some(thread::spawn(move || {
+                let thread = bench.threads as u64;
+                bench.run_thread(&db, Workload::FillRandom, thread, ::std::u64::MAX, &stop)
+            }))
+        } else {
+            None
+        };
+        let workers: Vec<_> = (0..self.threads as u64)
+            .map(|thread| {
+                let (bench, db, stop) = (self.clone(), db.clone(), stop.clone());
+                let workload = match self.workload {
+                    Workload::ReadWhileWriting => Workload::ReadRandom,
+                    workload => workload,
+                };
+                thread::spawn(move || bench.run_thread(&db, workload, thread, per_thread, &stop))
+            })
+            .collect();
+
+        let mut report = BenchReport {
+            workload: self.workload,
+            ops: per_thread * self.threads as u64,
+            found: 0,
+            bytes: 0,
+            elapsed: Duration::default(),
+        };
+        let mut result = Ok(());
+        for worker in workers {
+            match worker.join().expect("benchmark thread panicked") {
+                Ok((found, bytes)) => {
+                    report.found += found;
+                    report.bytes += bytes;
+                }
+                Err(e) => {
+                    stop.store(true, Ordering::Relaxed);
+                    result = Err(e);
+                }
+            }
+        }
+        report.elapsed = start.elapsed();
+        stop.store(true, Ordering::Relaxed);
+        if let Some(writer) = writer {
+            try!(writer.join().expect("benchmark thread panicked"));
+        }
+        result.map(|_| report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{Bench, Workload};
+    use {DB, Options};
+
+    #[test]
+    fn test_bench() {
+        let path = "_rust_rocksdb_bench_test";
+        {
+            let db = Arc::new(DB::open_default(path).unwrap());
+            let fill = Bench::new(Workload::FillSeq).num(1000).threads(2).run(&db).unwrap();
+            assert_eq!(fill.ops, 1000);
+            assert_eq!(fill.bytes, 1000 * 116);
+            assert!(fill.to_string().starts_with("fillseq"));
+
+            let read = Bench::new(Workload::ReadRandom).num(1000).run(&db).unwrap();
+            assert_eq!(read.found, 1000);
+
+            let fill = Bench::new(Workload::FillRandom).num(1000).value_size(10).run(&db).unwrap();
+            assert_eq!(fill.bytes, 1000 * 26);
+
+            let bench = Bench::new(Workload::ReadWhileWriting).num(1000).threads(2);
+            let read = bench.run(&db).unwrap();
+            assert_eq!((read.ops, read.found), (1000, 1000));
+
+            assert!(Bench::new(Workload::FillSeq).key_size(4).run(&db).is_err());
+            assert!(Bench::new(Workload::ReadWhileWriting).num(0).run(&db).is_err());
+        }
+        assert!(DB::destroy(&Options::default(), path).is_ok());
+    }
+}
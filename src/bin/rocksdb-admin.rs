@@ -0,0 +1,129 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A small `ldb`-like tool built on `rocksdb::admin`.
+//!
+//! Keys and values are taken and printed as UTF-8 text; arguments starting
+//! with `0x` are decoded as hex.
+
+extern crate rocksdb;
+
+use std::env;
+use std::process;
+
+use rocksdb::Error;
+use rocksdb::admin::Admin;
+
+const USAGE: &'static str = "usage: rocksdb-admin <db-path> [--cf=<name>] <command> [args]
+
+commands:
+    list_cf                  list column families
+    get <key>                print the value of a key
+    put <key> <value>        store a value
+    delete <key>             delete a key
+    scan [<from>] [<limit>]  print up to <limit> (default 100) entries
+    property <name>          print a property, e.g. rocksdb.stats
+    manifest                 print the SST files of every column family, or of --cf";
+
+fn decode(arg: &str) -> Result<Vec<u8>, String> {
+    if !arg.starts_with("0x") {
+        return Ok(arg.as_bytes().to_vec());
+    }
+    let hex = &arg[2..];
+    if hex.len() % 2 != 0 {
+        return Err(format!("odd number of hex digits in {}", arg));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| format!("{}: {}", arg, e)))
+        .collect()
+}
+
+fn display(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn run(args: Vec<String>) -> Result<(), String> {
+    let mut args = args.into_iter();
+    let path = try!(args.next().ok_or(USAGE.to_owned()));
+    let mut rest: Vec<String> = args.collect();
+    let mut cf = None;
+    if rest.first().map_or(false, |a| a.starts_with("--cf=")) {
+        cf = Some(rest.remove(0)["--cf=".len()..].to_owned());
+    }
+    let cf = cf.as_ref().map(|cf| cf.as_str());
+    let command = try!(rest.first().cloned().ok_or(USAGE.to_owned()));
+    let args = &rest[1..];
+
+    let admin = try!(Admin::open(&path).map_err(|e: Error| e.to_string()));
+    let err = |e: Error| e.to_string();
+    match (command.as_str(), args.len()) {
+        ("list_cf", 0) => {
+            for name in admin.column_families() {
+                println!("{}", name);
+            }
+        }
+        ("get", 1) => {
+            match try!(admin.get(cf, &try!(decode(&args[0]))).map_err(err)) {
+                Some(value) => println!("{}", display(&value)),
+                None => return Err("not found".to_owned()),
+            }
+        }
+        ("put", 2) => {
+            try!(admin.put(cf, &try!(decode(&args[0])), &try!(decode(&args[1]))).map_err(err));
+        }
+        ("delete", 1) => {
+            try!(admin.delete(cf, &try!(decode(&args[0]))).map_err(err));
+        }
+        ("scan", n) if n <= 2 => {
+            let from = match args.get(0) {
+                Some(from) => Some(try!(decode(from))),
+                None => None,
+            };
+            let limit = match args.get(1) {
+                Some(limit) => try!(limit.parse().map_err(|_| format!("invalid limit: {}", limit))),
+                None => 100,
+            };
+            let entries = try!(admin.scan(cf, from.as_ref().map(|f| &f[..]), limit).map_err(err));
+            for (key, value) in entries {
+                println!("{} ==> {}", display(&key), display(&value));
+            }
+        }
+        ("property", 1) => {
+            match try!(admin.property(cf, &args[0]).map_err(err)) {
+                Some(value) => println!("{}", value),
+                None => return Err(format!("unknown property: {}", args[0])),
+            }
+        }
+        ("manifest", 0) => {
+            let names = match cf {
+                Some(cf) => vec![cf.to_owned()],
+                None => admin.column_families(),
+            };
+            for name in names {
+                println!("column family {}:", name);
+                print!("{}", try!(admin.manifest(Some(&name)).map_err(err)));
+            }
+        }
+        _ => return Err(USAGE.to_owned()),
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run(env::args().skip(1).collect()) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversions to the buffer types of the `bytes` crate, available with the
+//! `bytes` feature.
+//!
+//! Values are handed over without copying: a `Bytes` made from a `DBVector`
+//! owns the buffer allocated by RocksDB, and the entries of an iterator are
+//! moved into `Bytes`. `Bytes` and `BytesMut` dereference to `[u8]`, so they
+//! can be passed to `put` and the other writes as they are.
+
+use bytes::{Bytes, BytesMut};
+
+use {ColumnFamily, DB, DBIterator, DBVector, Error};
+
+impl From<DBVector> for Bytes {
+    fn from(value: DBVector) -> Bytes {
+        Bytes::from_owner(value)
+    }
+}
+
+impl DB {
+    /// Like `get`, but returns the value as `Bytes`, without copying it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate bytes;
+    /// # extern crate rocksdb;
+    ///
+    /// use bytes::Bytes;
+    /// use rocksdb::DB;
+    ///
+    /// # fn main() {
+    /// let db = DB::open_default("path/for/rocksdb/storage_bytes").unwrap();
+    /// let value = Bytes::from_static(b"v1");
+    /// db.put(b"k1", &value).unwrap();
+    /// assert_eq!(db.get_bytes(b"k1").unwrap(), Some(value));
+    /// # }
+    /// ```
+    pub fn get_bytes(&self, key: &[u8]) -> Result<Option<Bytes>, Error> {
+        Ok(try!(self.get(key)).map(Bytes::from))
+    }
+
+    pub fn get_bytes_cf(&self, cf: &ColumnFamily, key: &[u8]) -> Result<Option<Bytes>, Error> {
+        Ok(try!(self.get_cf(cf, key)).map(Bytes::from))
+    }
+
+    /// Like `get_into`, but reads into a `BytesMut`, which can then be
+    /// split or frozen without copying.
+    pub fn get_into_bytes(&self, key: &[u8], buf: &mut BytesMut) -> Result<Option<usize>, Error> {
+        buf.clear();
+        Ok(try!(self.get(key)).map(|value| {
+            buf.extend_from_slice(&value);
+            value.len()
+        }))
+    }
+}
+
+impl DBIterator {
+    /// Returns the entries as `Bytes`, without copying them.
+    pub fn into_bytes(self) -> BytesIterator {
+        BytesIterator { inner: self }
+    }
+}
+
+/// An iterator returning the entries of a `DBIterator` as `Bytes`.
+pub struct BytesIterator {
+    inner: DBIterator,
+}
+
+impl Iterator for BytesIterator {
+    type Item = (Bytes, Bytes);
+
+    fn next(&mut self) -> Option<(Bytes, Bytes)> {
+        self.inner.next().map(|(key, value)| (Bytes::from(key), Bytes::from(value)))
+    }
+}
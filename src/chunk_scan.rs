@@ -0,0 +1,210 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scans that copy entries into a reusable buffer a chunk at a time.
+//!
+//! Every entry of `DBIterator` is copied into two boxed slices of its own.
+//! `ChunkScan` instead appends the keys and values of up to `chunk_size`
+//! entries to a single buffer, which is reused for the next chunk, so full
+//! range scans such as exports allocate nothing per entry once the buffer
+//! has grown to the size of a chunk.
+
+use std::cmp;
+
+use {ColumnFamily, DB, DBRawIterator, Direction, Error, IteratorMode, ReadOptions};
+
+/// Consecutive entries of a `ChunkScan`, stored in one buffer.
+#[derive(Debug, Default, Clone)]
+pub struct Chunk {
+    data: Vec<u8>,
+    // The offset of the key in `data`, and the lengths of the key and of
+    // the value following it.
+    entries: Vec<(usize, usize, usize)>,
+}
+
+impl Chunk {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the key and value of the `index`th entry of the chunk.
+    pub fn get(&self, index: usize) -> Option<(&[u8], &[u8])> {
+        self.entries.get(index).map(|&(offset, key_len, value_len)| {
+            let value = offset + key_len;
+            (&self.data[offset..value], &self.data[value..value + value_len])
+        })
+    }
+
+    /// Returns the size of the keys and values of the chunk.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn iter(&self) -> ChunkIter {
+        ChunkIter {
+            chunk: self,
+            index: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.entries.clear();
+    }
+
+    fn push(&mut self, key: &[u8], value: &[u8]) {
+        self.entries.push((self.data.len(), key.len(), value.len()));
+        self.data.extend_from_slice(key);
+        self.data.extend_from_slice(value);
+    }
+}
+
+/// An iterator over the entries of a `Chunk`.
+pub struct ChunkIter<'a> {
+    chunk: &'a Chunk,
+    index: usize,
+}
+
+impl<'a> Iterator for ChunkIter<'a> {
+    type Item = (&'a [u8], &'a [u8]);
+
+    fn next(&mut self) -> Option<(&'a [u8], &'a [u8])> {
+        let entry = self.chunk.get(self.index);
+        if entry.is_some() {
+            self.index += 1;
+        }
+        entry
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.chunk.len() - self.index;
+        (left, Some(left))
+    }
+}
+
+impl<'a> IntoIterator for &'a Chunk {
+    type Item = (&'a [u8], &'a [u8]);
+    type IntoIter = ChunkIter<'a>;
+
+    fn into_iter(self) -> ChunkIter<'a> {
+        self.iter()
+    }
+}
+
+/// A forward scan of a key range returning its entries a `Chunk` at a
+/// time, created by `DB::scan_chunks`.
+///
+/// # Examples
+///
+/// ```
+/// use rocksdb::DB;
+///
+/// let db = DB::open_default("path/for/rocksdb/storage_scan_chunks").unwrap();
+/// for i in 0..10u8 {
+///     db.put(&[i], b"value").unwrap();
+/// }
+///
+/// let mut scan = db.scan_chunks(Some(&[2]), None, 4);
+/// let mut sizes = Vec::new();
+/// while let Some(chunk) = scan.next_chunk().unwrap() {
+///     for (key, value) in chunk {
+///         assert_eq!(value, b"value");
+///         assert!(key >= &[2][..]);
+///     }
+///     sizes.push(chunk.len());
+/// }
+/// assert_eq!(sizes, vec![4, 4]);
+/// ```
+pub struct ChunkScan {
+    iter: DBRawIterator,
+    chunk_size: usize,
+    chunk: Chunk,
+}
+
+impl ChunkScan {
+    fn new(iter: DBRawIterator, chunk_size: usize) -> ChunkScan {
+        ChunkScan {
+            iter: iter,
+            chunk_size: cmp::max(chunk_size, 1),
+            chunk: Chunk::default(),
+        }
+    }
+
+    /// Returns the next chunk, overwriting the previous one, or `None` once
+    /// the range has been scanned. Every chunk but the last holds
+    /// `chunk_size` entries.
+    pub fn next_chunk(&mut self) -> Result<Option<&Chunk>, Error> {
+        self.chunk.clear();
+        while self.chunk.len() < self.chunk_size {
+            match (self.iter.key_pinned(), self.iter.value_pinned()) {
+                (Some(key), Some(value)) => self.chunk.push(key, value),
+                _ => break,
+            }
+            self.iter.next();
+        }
+        if self.chunk.len() < self.chunk_size {
+            try!(self.iter.status());
+        }
+        if self.chunk.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(&self.chunk))
+        }
+    }
+}
+
+fn read_options(end: Option<&[u8]>) -> ReadOptions {
+    let mut readopts = ReadOptions::default();
+    if let Some(end) = end {
+        readopts.set_iterate_upper_bound(end);
+    }
+    readopts
+}
+
+fn start_mode(start: Option<&[u8]>) -> IteratorMode {
+    match start {
+        Some(start) => IteratorMode::From(start, Direction::Forward),
+        None => IteratorMode::Start,
+    }
+}
+
+impl DB {
+    /// Scans the keys from `start` up to but excluding `end` in chunks of up
+    /// to `chunk_size` entries, or at least one. `None` leaves that side of
+    /// the range unbounded.
+    pub fn scan_chunks(
+        &self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        chunk_size: usize,
+    ) -> ChunkScan {
+        let iter = self.iterator_opt(start_mode(start), read_options(end));
+        ChunkScan::new(iter.into(), chunk_size)
+    }
+
+    pub fn scan_chunks_cf(
+        &self,
+        cf: &ColumnFamily,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        chunk_size: usize,
+    ) -> Result<ChunkScan, Error> {
+        let iter = try!(self.iterator_cf_opt(cf, start_mode(start), read_options(end)));
+        Ok(ChunkScan::new(iter.into(), chunk_size))
+    }
+}
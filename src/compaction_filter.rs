@@ -18,6 +18,8 @@ use std::ffi::CString;
 use std::mem;
 use std::slice;
 
+use ffi_util::catch_callback_panic;
+
 /// Decision about how to handle compacting an object
 ///
 /// This is returned by a compaction filter callback. Depending
@@ -61,7 +63,8 @@ pub unsafe extern "C" fn destructor_callback<F>(raw_cb: *mut c_void)
 where
     F: CompactionFilterFn,
 {
-    let _: Box<CompactionFilterCallback<F>> = mem::transmute(raw_cb);
+    let cb: Box<CompactionFilterCallback<F>> = mem::transmute(raw_cb);
+    catch_callback_panic("compaction filter destructor", || drop(cb));
 }
 
 pub unsafe extern "C" fn name_callback<F>(raw_cb: *mut c_void) -> *const c_char
@@ -91,8 +94,11 @@ where
     let cb = &mut *(raw_cb as *mut CompactionFilterCallback<F>);
     let key = slice::from_raw_parts(raw_key as *const u8, key_length as usize);
     let oldval = slice::from_raw_parts(existing_value as *const u8, value_length as usize);
-    let result = (cb.filter_fn)(level as u32, key, oldval);
-    match result {
+    // A panic keeps the entry, leaving the database as it was.
+    let result = catch_callback_panic("compaction filter", || {
+        (cb.filter_fn)(level as u32, key, oldval)
+    });
+    match result.unwrap_or(Keep) {
         Keep => 0,
         Remove => 1,
         Change(newval) => {
@@ -135,3 +141,27 @@ fn compaction_filter_test() {
     }
 
 }
+
+#[test]
+fn panicking_compaction_filter_test() {
+    use {DB, Options};
+
+    let path = "_rust_rocksdb_panicking_filtertest";
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_compaction_filter("panicking", |_, key: &[u8], _: &[u8]| {
+        if key == b"k2" {
+            panic!("filter failed");
+        }
+        Decision::Remove
+    });
+    {
+        let db = DB::open(&opts, path).unwrap();
+        let _ = db.put(b"k1", b"a");
+        let _ = db.put(b"k2", b"b");
+        db.compact_range(None, None);
+        assert!(db.get(b"k1").unwrap().is_none());
+        assert_eq!(&*db.get(b"k2").unwrap().unwrap(), b"b");
+    }
+    assert!(DB::destroy(&opts, path).is_ok());
+}
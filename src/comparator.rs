@@ -20,34 +20,46 @@ use std::ffi::CString;
 use std::mem;
 use std::slice;
 
+use ffi_util::abort_on_callback_panic;
+
 pub type CompareFn = fn(&[u8], &[u8]) -> Ordering;
 
-pub struct ComparatorCallback {
+pub trait ComparatorFn: Fn(&[u8], &[u8]) -> Ordering + Send + Sync + 'static {}
+impl<F> ComparatorFn for F
+where
+    F: Fn(&[u8], &[u8]) -> Ordering,
+    F: Send + Sync + 'static,
+{
+}
+
+pub struct ComparatorCallback<F: ComparatorFn> {
     pub name: CString,
-    pub f: CompareFn,
+    pub f: F,
 }
 
-pub unsafe extern "C" fn destructor_callback(raw_cb: *mut c_void) {
-    let _: Box<ComparatorCallback> = mem::transmute(raw_cb);
+pub unsafe extern "C" fn destructor_callback<F: ComparatorFn>(raw_cb: *mut c_void) {
+    let cb: Box<ComparatorCallback<F>> = mem::transmute(raw_cb);
+    abort_on_callback_panic("comparator destructor", move || drop(cb));
 }
 
-pub unsafe extern "C" fn name_callback(raw_cb: *mut c_void) -> *const c_char {
-    let cb: &mut ComparatorCallback = &mut *(raw_cb as *mut ComparatorCallback);
+pub unsafe extern "C" fn name_callback<F: ComparatorFn>(raw_cb: *mut c_void) -> *const c_char {
+    let cb = &*(raw_cb as *const ComparatorCallback<F>);
     let ptr = cb.name.as_ptr();
     ptr as *const c_char
 }
 
-pub unsafe extern "C" fn compare_callback(
+pub unsafe extern "C" fn compare_callback<F: ComparatorFn>(
     raw_cb: *mut c_void,
     a_raw: *const c_char,
     a_len: size_t,
     b_raw: *const c_char,
     b_len: size_t,
 ) -> c_int {
-    let cb: &mut ComparatorCallback = &mut *(raw_cb as *mut ComparatorCallback);
+    let cb = &*(raw_cb as *const ComparatorCallback<F>);
     let a: &[u8] = slice::from_raw_parts(a_raw as *const u8, a_len as usize);
     let b: &[u8] = slice::from_raw_parts(b_raw as *const u8, b_len as usize);
-    match (cb.f)(a, b) {
+    // No ordering can be made up without corrupting the database.
+    match abort_on_callback_panic("comparator", || (cb.f)(a, b)) {
         Ordering::Less => -1,
         Ordering::Equal => 0,
         Ordering::Greater => 1,
@@ -0,0 +1,353 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Database tuning loaded from TOML or YAML documents, available with the
+//! `config` feature.
+//!
+//! Option names and values are those of RocksDB's OPTIONS files, as
+//! accepted by `Options::from_string`:
+//!
+//! - top-level keys are database options and options of the default column
+//!   family;
+//! - the `table` section holds block-based table options;
+//! - every entry of `column_families` holds the options of one column
+//!   family, with its own optional `table` section. Column families start
+//!   from the top-level options and override them.
+//!
+//! Lists, such as `compression_per_level`, are joined with `:`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::config::Config;
+//!
+//! let config = Config::from_toml(r#"
+//!     create_if_missing = true
+//!     create_missing_column_families = true
+//!     max_background_jobs = 4
+//!     write_buffer_size = "64m"
+//!
+//!     [table]
+//!     block_size = "16k"
+//!
+//!     [column_families.users]
+//!     write_buffer_size = "16m"
+//! "#).unwrap();
+//!
+//! let db = config.open_options().unwrap().open("path/for/rocksdb/storage_config").unwrap();
+//! assert!(db.cf_handle("users").is_some());
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde_yaml;
+use toml;
+
+use {ColumnFamilyDescriptor, Error, ErrorKind, OpenOptions, Options};
+
+/// A value of the document, independent of its format.
+enum Node {
+    Scalar(String),
+    Map(Vec<(String, Node)>),
+}
+
+fn invalid(msg: String) -> Error {
+    Error::with_kind(ErrorKind::InvalidArgument, msg)
+}
+
+fn toml_node(value: &toml::Value) -> Result<Node, Error> {
+    match *value {
+        toml::Value::String(ref s) => Ok(Node::Scalar(s.clone())),
+        toml::Value::Integer(i) => Ok(Node::Scalar(i.to_string())),
+        toml::Value::Float(f) => Ok(Node::Scalar(f.to_string())),
+        toml::Value::Boolean(b) => Ok(Node::Scalar(b.to_string())),
+        toml::Value::Array(ref items) => {
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                match try!(toml_node(item)) {
+                    Node::Scalar(s) => values.push(s),
+                    Node::Map(_) => return Err(invalid("lists may only hold values".to_owned())),
+                }
+            }
+            Ok(Node::Scalar(values.join(":")))
+        }
+        toml::Value::Table(ref table) => {
+            let mut entries = Vec::with_capacity(table.len());
+            for (key, value) in table {
+                entries.push((key.clone(), try!(toml_node(value))));
+            }
+            Ok(Node::Map(entries))
+        }
+        toml::Value::Datetime(ref d) => Err(invalid(format!("unexpected date `{}`", d))),
+    }
+}
+
+fn yaml_node(value: &serde_yaml::Value) -> Result<Node, Error> {
+    match *value {
+        serde_yaml::Value::String(ref s) => Ok(Node::Scalar(s.clone())),
+        serde_yaml::Value::Number(ref n) => Ok(Node::Scalar(n.to_string())),
+        serde_yaml::Value::Bool(b) => Ok(Node::Scalar(b.to_string())),
+        serde_yaml::Value::Sequence(ref items) => {
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                match try!(yaml_node(item)) {
+                    Node::Scalar(s) => values.push(s),
+                    Node::Map(_) => return Err(invalid("lists may only hold values".to_owned())),
+                }
+            }
+            Ok(Node::Scalar(values.join(":")))
+        }
+        serde_yaml::Value::Mapping(ref mapping) => {
+            let mut entries = Vec::new();
+            for (key, value) in mapping {
+                let key = match *key {
+                    serde_yaml::Value::String(ref key) => key.clone(),
+                    _ => return Err(invalid("keys must be strings".to_owned())),
+                };
+                entries.push((key, try!(yaml_node(value))));
+            }
+            Ok(Node::Map(entries))
+        }
+        _ => Err(invalid("unsupported YAML value".to_owned())),
+    }
+}
+
+/// The options of one column family, or of the database and its default
+/// column family.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Section {
+    options: BTreeMap<String, String>,
+    table: BTreeMap<String, String>,
+}
+
+impl Section {
+    fn scalars(name: &str, node: Node) -> Result<BTreeMap<String, String>, Error> {
+        let mut values = BTreeMap::new();
+        match node {
+            Node::Map(entries) => {
+                for (key, value) in entries {
+                    match value {
+                        Node::Scalar(value) => {
+                            values.insert(key, value);
+                        }
+                        Node::Map(_) => {
+                            return Err(invalid(format!("unexpected section `{}.{}`", name, key)))
+                        }
+                    }
+                }
+                Ok(values)
+            }
+            Node::Scalar(_) => Err(invalid(format!("`{}` must be a section", name))),
+        }
+    }
+
+    /// Parses the options and the `table` section of a column family;
+    /// `name` prefixes the names of unexpected sections in errors.
+    fn parse(name: &str, entries: Vec<(String, Node)>) -> Result<Section, Error> {
+        let mut parsed = Section::default();
+        for (key, value) in entries {
+            match value {
+                Node::Scalar(value) => {
+                    parsed.options.insert(key, value);
+                }
+                Node::Map(_) if key == "table" => {
+                    parsed.table = try!(Section::scalars("table", value));
+                }
+                Node::Map(_) => {
+                    return Err(invalid(format!("unexpected section `{}{}`", name, key)))
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Renders the section in the `name=value;` syntax of
+    /// `Options::from_string`.
+    fn to_options_string(&self) -> String {
+        let mut s = String::new();
+        for (key, value) in &self.options {
+            s.push_str(&format!("{}={};", key, value));
+        }
+        if !self.table.is_empty() {
+            s.push_str("block_based_table_factory={");
+            for (key, value) in &self.table {
+                s.push_str(&format!("{}={};", key, value));
+            }
+            s.push_str("};");
+        }
+        s
+    }
+}
+
+/// A parsed configuration document.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    db: Section,
+    column_families: BTreeMap<String, Section>,
+}
+
+impl Config {
+    pub fn from_toml(s: &str) -> Result<Config, Error> {
+        let table: toml::Table = match s.parse() {
+            Ok(table) => table,
+            Err(e) => return Err(invalid(format!("invalid TOML: {}", e))),
+        };
+        Config::from_node(try!(toml_node(&toml::Value::Table(table))))
+    }
+
+    pub fn from_yaml(s: &str) -> Result<Config, Error> {
+        let value: serde_yaml::Value = match serde_yaml::from_str(s) {
+            Ok(value) => value,
+            Err(e) => return Err(invalid(format!("invalid YAML: {}", e))),
+        };
+        Config::from_node(try!(yaml_node(&value)))
+    }
+
+    fn from_node(node: Node) -> Result<Config, Error> {
+        let entries = match node {
+            Node::Map(entries) => entries,
+            Node::Scalar(_) => return Err(invalid("the document must be a map".to_owned())),
+        };
+        let mut db = Vec::with_capacity(entries.len());
+        let mut column_families = BTreeMap::new();
+        for (key, value) in entries {
+            if key != "column_families" {
+                db.push((key, value));
+                continue;
+            }
+            let cfs = match value {
+                Node::Map(cfs) => cfs,
+                Node::Scalar(_) => {
+                    return Err(invalid("`column_families` must be a section".to_owned()))
+                }
+            };
+            for (name, cf) in cfs {
+                let entries = match cf {
+                    Node::Map(entries) => entries,
+                    Node::Scalar(_) => {
+                        return Err(invalid(format!("column family `{}` must be a section", name)))
+                    }
+                };
+                let prefix = format!("column_families.{}.", name);
+                column_families.insert(name, try!(Section::parse(&prefix, entries)));
+            }
+        }
+        let db = try!(Section::parse("", db));
+        Ok(Config {
+            db: db,
+            column_families: column_families,
+        })
+    }
+
+    /// Returns the database options, which also apply to the default column
+    /// family.
+    pub fn options(&self) -> Result<Options, Error> {
+        Options::from_string(&Options::default(), &self.db.to_options_string())
+    }
+
+    /// Returns the names of the configured column families.
+    pub fn column_family_names(&self) -> Vec<&str> {
+        self.column_families.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// Returns the configured column families, each starting from the
+    /// database options.
+    pub fn column_families(&self) -> Result<Vec<ColumnFamilyDescriptor>, Error> {
+        let base = try!(self.options());
+        let mut cfs = Vec::with_capacity(self.column_families.len());
+        for (name, cf) in &self.column_families {
+            let opts = try!(Options::from_string(&base, &cf.to_options_string()));
+            cfs.push(ColumnFamilyDescriptor::new(name.as_str(), opts));
+        }
+        Ok(cfs)
+    }
+
+    /// Returns a builder opening the database with all configured column
+    /// families.
+    pub fn open_options(&self) -> Result<OpenOptions, Error> {
+        let cfs = try!(self.column_families());
+        Ok(OpenOptions::with_options(try!(self.options())).column_families(cfs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use {DB, Options};
+
+    const TOML: &'static str = r#"
+        create_if_missing = true
+        create_missing_column_families = true
+        compression_per_level = ["kNoCompression", "kSnappyCompression"]
+
+        [table]
+        block_size = "16k"
+
+        [column_families.cf1]
+        write_buffer_size = "8m"
+
+        [column_families.cf1.table]
+        cache_index_and_filter_blocks = true
+    "#;
+
+    const YAML: &'static str = "
+create_if_missing: true
+create_missing_column_families: true
+compression_per_level: [kNoCompression, kSnappyCompression]
+table:
+  block_size: 16k
+column_families:
+  cf1:
+    write_buffer_size: 8m
+    table:
+      cache_index_and_filter_blocks: true
+";
+
+    #[test]
+    fn test_parse() {
+        let config = Config::from_toml(TOML).unwrap();
+        assert_eq!(config, Config::from_yaml(YAML).unwrap());
+        assert_eq!(config.column_family_names(), vec!["cf1"]);
+        assert_eq!(
+            config.db.to_options_string(),
+            "compression_per_level=kNoCompression:kSnappyCompression;\
+             create_if_missing=true;create_missing_column_families=true;\
+             block_based_table_factory={block_size=16k;};"
+        );
+        assert_eq!(
+            config.column_families["cf1"].to_options_string(),
+            "write_buffer_size=8m;block_based_table_factory={cache_index_and_filter_blocks=true;};"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Config::from_toml("[unknown]\nkey = 1").is_err());
+        assert!(Config::from_toml("[column_families.cf1.other]\nkey = 1").is_err());
+        assert!(Config::from_toml("column_families = 1").is_err());
+        assert!(Config::from_toml("no_such_option = 1").unwrap().options().is_err());
+    }
+
+    #[test]
+    fn test_open() {
+        let path = "_rust_rocksdb_config_test";
+        {
+            let db = Config::from_toml(TOML).unwrap().open_options().unwrap().open(path).unwrap();
+            let cf1 = db.cf_handle("cf1").unwrap();
+            db.put_cf(cf1, b"k1", b"v1").unwrap();
+            assert_eq!(&*db.get_cf(cf1, b"k1").unwrap().unwrap(), b"v1");
+        }
+        assert!(DB::destroy(&Options::default(), path).is_ok());
+    }
+}
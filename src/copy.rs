@@ -0,0 +1,194 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Copying a consistent snapshot of a database into a new one, e.g. to
+//! move a shard or to clone an environment.
+//!
+//! The copy reads every selected column family from one snapshot of the
+//! source, so it sees the writes made before it started and none of the
+//! ones made during it, and writes the entries to the new database in
+//! batches. The source stays available for reads and writes throughout.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::{DB, Options};
+//! use rocksdb::copy::SnapshotCopy;
+//!
+//! let mut opts = Options::default();
+//! opts.create_if_missing(true);
+//! opts.create_missing_column_families(true);
+//! let db = DB::open_cf(&opts, "path/for/rocksdb/storage_copy_src", &["users", "logs"]).unwrap();
+//! db.put_cf(db.cf_handle("users").unwrap(), b"ada", b"1815").unwrap();
+//!
+//! let copied = SnapshotCopy::new(&db)
+//!     .column_family("users")
+//!     .on_progress(|progress| println!("{} keys copied", progress.keys))
+//!     .run(&Options::default(), "path/for/rocksdb/storage_copy_dst")
+//!     .unwrap();
+//! assert_eq!(copied.keys, 1);
+//! ```
+
+use std::path::Path;
+
+use {ColumnFamily, DB, Error, ErrorKind, IteratorMode, Options, WriteBatch};
+
+/// How far a copy has got.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CopyProgress {
+    /// The column family being copied.
+    pub column_family: String,
+    /// The number of entries copied so far, over all column families.
+    pub keys: u64,
+    /// The size of the keys and values copied so far.
+    pub bytes: u64,
+}
+
+/// A copy of a snapshot of `db` into a new database.
+pub struct SnapshotCopy<'a> {
+    db: &'a DB,
+    cfs: Vec<String>,
+    batch_bytes: usize,
+    progress: Option<Box<dyn FnMut(&CopyProgress) + 'a>>,
+}
+
+impl<'a> SnapshotCopy<'a> {
+    pub fn new(db: &'a DB) -> SnapshotCopy<'a> {
+        SnapshotCopy {
+            db: db,
+            cfs: Vec::new(),
+            batch_bytes: 4 << 20,
+            progress: None,
+        }
+    }
+
+    /// Copies the column family `name`, which may be `default`. Every column
+    /// family is copied if none are selected.
+    pub fn column_family(mut self, name: &str) -> SnapshotCopy<'a> {
+        self.cfs.push(name.to_owned());
+        self
+    }
+
+    /// Sets the size of the batches written to the new database.
+    ///
+    /// Default: 4MB
+    pub fn batch_bytes(mut self, batch_bytes: usize) -> SnapshotCopy<'a> {
+        self.batch_bytes = batch_bytes;
+        self
+    }
+
+    /// Calls `f` after every batch written, and once at the end of every
+    /// column family.
+    pub fn on_progress<F>(mut self, f: F) -> SnapshotCopy<'a>
+    where
+        F: FnMut(&CopyProgress) + 'a,
+    {
+        self.progress = Some(Box::new(f));
+        self
+    }
+
+    fn report(&mut self, progress: &CopyProgress) {
+        if let Some(ref mut f) = self.progress {
+            f(progress);
+        }
+    }
+
+    /// Creates the database at `path` with `opts`, which are used for every
+    /// column family, and copies the selected column families into it.
+    /// Fails if the database already exists.
+    ///
+    /// The entries are written without the write-ahead log and flushed at
+    /// the end, so a database left behind by a failed copy is incomplete
+    /// and must be destroyed.
+    pub fn run<P: AsRef<Path>>(mut self, opts: &Options, path: P) -> Result<CopyProgress, Error> {
+        let cfs = if self.cfs.is_empty() {
+            try!(DB::list_cf(&Options::default(), self.db.path()))
+        } else {
+            self.cfs.clone()
+        };
+        for name in &cfs {
+            if name != "default" && self.db.cf_handle(name).is_none() {
+                return Err(Error::with_kind(ErrorKind::InvalidArgument,
+                                            format!("no column family {}", name)));
+            }
+        }
+
+        let mut opts = try!(opts.try_clone());
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        opts.set_error_if_exists(true);
+        let names: Vec<&str> = cfs.iter()
+            .map(|name| name.as_str())
+            .filter(|name| *name != "default")
+            .collect();
+        let dst = try!(DB::open_cf(&opts, path, &names));
+
+        let snapshot = self.db.snapshot();
+        let mut progress = CopyProgress::default();
+        for name in &cfs {
+            progress.column_family = name.clone();
+            let iter = match self.db.cf_handle(name) {
+                Some(cf) => try!(snapshot.iterator_cf(cf, IteratorMode::Start)),
+                None => snapshot.iterator(IteratorMode::Start),
+            };
+            let dst_cf = dst.cf_handle(name);
+            let mut batch = WriteBatch::default();
+            let mut batch_size = 0;
+            for (key, value) in iter {
+                match dst_cf {
+                    Some(cf) => try!(batch.put_cf(cf, &key, &value)),
+                    None => try!(batch.put(&key, &value)),
+                }
+                batch_size += key.len() + value.len();
+                progress.keys += 1;
+                progress.bytes += (key.len() + value.len()) as u64;
+                if batch_size >= self.batch_bytes {
+                    try!(dst.write_without_wal(batch));
+                    batch = WriteBatch::default();
+                    batch_size = 0;
+                    self.report(&progress);
+                }
+            }
+            try!(dst.write_without_wal(batch));
+            self.report(&progress);
+        }
+        // The entries skipped the write-ahead log, so the copy is only
+        // durable once every memtable is flushed. Compacting flushes them but
+        // reports no errors, hence the explicit flush and the check that
+        // nothing is left in memory.
+        dst.finish_bulk_load();
+        try!(dst.flush());
+        for name in &cfs {
+            if try!(unflushed_entries(&dst, dst.cf_handle(name))) > 0 {
+                let msg = format!("column family {} of the copy was not flushed", name);
+                return Err(Error::with_kind(ErrorKind::IOError, msg));
+            }
+        }
+        Ok(progress)
+    }
+}
+
+// Returns the number of entries still in the memtables of `cf`, or of the
+// default column family if `cf` is `None`.
+fn unflushed_entries(db: &DB, cf: Option<&ColumnFamily>) -> Result<u64, Error> {
+    let mut entries = 0;
+    for name in &["rocksdb.num-entries-active-mem-table", "rocksdb.num-entries-imm-mem-tables"] {
+        let value = match cf {
+            Some(cf) => try!(db.property_int_value_cf(cf, name)),
+            None => try!(db.property_int_value(name)),
+        };
+        entries += value.unwrap_or(0);
+    }
+    Ok(entries)
+}
@@ -14,28 +14,60 @@
 //
 
 
-use {DB, Error, Options, WriteOptions, ColumnFamily, ColumnFamilyDescriptor};
+use {DB, Error, ErrorKind, Options, WriteOptions, ColumnFamily, ColumnFamilyDescriptor};
 use ffi;
-use ffi_util::opt_bytes_to_ptr;
+use ffi_util::{error_message, opt_bytes_to_ptr};
+use keys;
 
 use libc::{self, c_char, c_int, c_uchar, c_void, size_t};
 use std::collections::BTreeMap;
-use std::ffi::CString;
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::{CStr, CString};
 use std::fmt;
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::marker::PhantomData;
 use std::ops::Deref;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::ptr;
 use std::slice;
 use std::str;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// The number of locks `DB::update` spreads keys over.
+const UPDATE_LOCK_STRIPES: usize = 64;
 
 pub fn new_bloom_filter(bits: c_int) -> *mut ffi::rocksdb_filterpolicy_t {
     unsafe { ffi::rocksdb_filterpolicy_create_bloom(bits) }
 }
 
+/// Returns the version of RocksDB this crate was built against, e.g.
+/// `"5.11.3"`.
+pub fn version() -> String {
+    format!("{}.{}.{}", ffi::ROCKSDB_MAJOR, ffi::ROCKSDB_MINOR, ffi::ROCKSDB_PATCH)
+}
+
 unsafe impl Send for DB {}
 unsafe impl Sync for DB {}
 
+// Column family handles are owned by their DB, which outlives them for as
+// long as the column family isn't dropped, and RocksDB allows them to be
+// used from any thread.
+unsafe impl Send for ColumnFamily {}
+unsafe impl Sync for ColumnFamily {}
+
+// A WriteBatch is a plain buffer of operations and can be built on one
+// thread and written from another.
+unsafe impl Send for WriteBatch {}
+
+/// A compression algorithm for SST file blocks.
+///
+/// The bundled `librocksdb-sys` build only links snappy; the other libraries
+/// must be available to RocksDB or opening a database that uses them fails.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DBCompressionType {
     None = ffi::rocksdb_no_compression as isize,
@@ -44,6 +76,7 @@ pub enum DBCompressionType {
     Bz2 = ffi::rocksdb_bz2_compression as isize,
     Lz4 = ffi::rocksdb_lz4_compression as isize,
     Lz4hc = ffi::rocksdb_lz4hc_compression as isize,
+    Zstd = ffi::rocksdb_zstd_compression as isize,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -53,6 +86,14 @@ pub enum DBCompactionStyle {
     Fifo = ffi::rocksdb_fifo_compaction as isize,
 }
 
+/// The algorithm used by universal compaction to stop picking files
+/// into a single compaction run.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UniversalCompactionStopStyle {
+    Similar = ffi::rocksdb_similar_size_compaction_stop_style as isize,
+    Total = ffi::rocksdb_total_size_compaction_stop_style as isize,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum DBRecoveryMode {
     TolerateCorruptedTailRecords = ffi::rocksdb_tolerate_corrupted_tail_records_recovery as isize,
@@ -61,6 +102,38 @@ pub enum DBRecoveryMode {
     SkipAnyCorruptedRecord = ffi::rocksdb_skip_any_corrupted_records_recovery as isize,
 }
 
+/// The kind of device a database is stored on, used by `Options::auto_tuned`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StorageClass {
+    /// Flash storage, where random reads are cheap.
+    Ssd,
+    /// Spinning disks, where seeks dominate and large sequential I/O pays off.
+    Hdd,
+}
+
+/// An alternative memtable representation, used with `Options::set_memtable_factory`.
+///
+/// The hash based representations bucket entries by the configured prefix
+/// extractor and only support efficient iteration within a single prefix.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MemtableFactory {
+    Vector,
+    HashSkipList {
+        bucket_count: usize,
+        height: i32,
+        branching_factor: i32,
+    },
+    HashLinkList { bucket_count: usize },
+}
+
+/// The index structure written into each block-based table file.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlockBasedIndexType {
+    BinarySearch = ffi::rocksdb_block_based_table_index_type_binary_search as isize,
+    HashSearch = ffi::rocksdb_block_based_table_index_type_hash_search as isize,
+    TwoLevelIndexSearch = ffi::rocksdb_block_based_table_index_type_two_level_index_search as isize,
+}
+
 /// An atomic batch of write operations.
 ///
 /// Making an atomic commit of several writes:
@@ -81,8 +154,48 @@ pub struct WriteBatch {
     inner: *mut ffi::rocksdb_writebatch_t,
 }
 
+/// A write batch that also indexes its updates, so they can be read back
+/// before the batch is written, either on their own or merged over the
+/// database or a snapshot of it.
+///
+/// ```
+/// use rocksdb::{DB, IteratorMode, WriteBatchWithIndex};
+///
+/// let db = DB::open_default("path/for/rocksdb/storage_wbwi").unwrap();
+/// db.put(b"k1", b"v1").unwrap();
+/// db.put(b"k2", b"v2").unwrap();
+///
+/// let mut batch = WriteBatchWithIndex::default();
+/// batch.put(b"k2", b"pending").unwrap();
+/// batch.delete(b"k1").unwrap();
+/// assert!(batch.get_from_batch_and_db(&db, b"k1").unwrap().is_none());
+/// {
+///     let mut iter = batch.iterator_with_base(&db, IteratorMode::Start);
+///     let (key, value) = iter.next().unwrap();
+///     assert_eq!((&*key, &*value), (&b"k2"[..], &b"pending"[..]));
+///     assert!(iter.next().is_none());
+/// }
+/// db.write_with_index(batch).unwrap(); // Atomically commits the batch
+/// ```
+pub struct WriteBatchWithIndex {
+    inner: *mut ffi::rocksdb_writebatch_wi_t,
+}
+
+unsafe impl Send for WriteBatchWithIndex {}
+
+/// An iterator over the updates of a `WriteBatchWithIndex` merged over the
+/// database, created by `WriteBatchWithIndex::iterator_with_base` or
+/// `Snapshot::iterator_with_batch`. The batch cannot be modified while the
+/// iterator is alive.
+pub struct BatchIterator<'a> {
+    inner: DBIterator,
+    _batch: PhantomData<&'a WriteBatchWithIndex>,
+}
+
 pub struct ReadOptions {
     inner: *mut ffi::rocksdb_readoptions_t,
+    // rocksdb only keeps a pointer to the upper bound, so the options own it.
+    _iterate_upper_bound: Option<Vec<u8>>,
 }
 
 /// A consistent view of the database at the point of creation.
@@ -100,6 +213,10 @@ pub struct Snapshot<'a> {
     inner: *const ffi::rocksdb_snapshot_t,
 }
 
+// A snapshot is immutable, and reads through it may come from any thread.
+unsafe impl<'a> Send for Snapshot<'a> {}
+unsafe impl<'a> Sync for Snapshot<'a> {}
+
 /// An iterator over a database or column family, with specifiable
 /// ranges and direction.
 ///
@@ -146,6 +263,77 @@ pub struct Snapshot<'a> {
 /// ```
 pub struct DBRawIterator {
     inner: *mut ffi::rocksdb_iterator_t,
+    // Read options the iterator was created with, if it owns them. rocksdb
+    // keeps pointing into them (e.g. for the upper bound) while iterating.
+    readopts: Option<ReadOptions>,
+    _live: LiveIterator,
+}
+
+// Counts an iterator in `DB::live_iterators` until dropped.
+struct LiveIterator {
+    count: Arc<AtomicUsize>,
+    // Spans the lifetime of the iterator.
+    #[cfg(feature = "tracing")]
+    _span: ::tracing::Span,
+}
+
+impl LiveIterator {
+    fn new(db: &DB) -> LiveIterator {
+        db.live_iterators.fetch_add(1, Ordering::SeqCst);
+        LiveIterator {
+            count: db.live_iterators.clone(),
+            #[cfg(feature = "tracing")]
+            _span: debug_span!("rocksdb.iterator", path = ?db.path),
+        }
+    }
+}
+
+impl Drop for LiveIterator {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// An iterator over the write batches recorded in the write-ahead log,
+/// created by `DB::get_updates_since`. Each item is the sequence number of
+/// the first update of a batch, and the batch itself.
+///
+/// The iterator stops at the end of the log as it was when it was created
+/// or last advanced; a new one must be created to see later writes.
+pub struct WalIterator {
+    inner: *mut ffi::rocksdb_wal_iterator_t,
+    _live: LiveIterator,
+}
+
+unsafe impl Send for WalIterator {}
+
+impl Iterator for WalIterator {
+    type Item = Result<(u64, WriteBatch), Error>;
+
+    fn next(&mut self) -> Option<Result<(u64, WriteBatch), Error>> {
+        unsafe {
+            if ffi::rocksdb_wal_iter_valid(self.inner) == 0 {
+                let mut err: *mut c_char = ptr::null_mut();
+                ffi::rocksdb_wal_iter_status(self.inner, &mut err);
+                if !err.is_null() {
+                    return Some(Err(Error::new(error_message(err))));
+                }
+                return None;
+            }
+            let mut seq = 0;
+            let batch = ffi::rocksdb_wal_iter_get_batch(self.inner, &mut seq);
+            ffi::rocksdb_wal_iter_next(self.inner);
+            Some(Ok((seq, WriteBatch { inner: batch })))
+        }
+    }
+}
+
+impl Drop for WalIterator {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_wal_iter_destroy(self.inner);
+        }
+    }
 }
 
 
@@ -197,17 +385,25 @@ pub enum IteratorMode<'a> {
 
 impl DBRawIterator {
     fn new(db: &DB, readopts: &ReadOptions) -> DBRawIterator {
-        unsafe { DBRawIterator { inner: ffi::rocksdb_create_iterator(db.inner, readopts.inner) } }
+        unsafe {
+            DBRawIterator {
+                inner: ffi::rocksdb_create_iterator(db.inner, readopts.inner),
+                readopts: None,
+                _live: LiveIterator::new(db),
+            }
+        }
     }
 
     fn new_cf(
         db: &DB,
-        cf_handle: ColumnFamily,
+        cf_handle: &ColumnFamily,
         readopts: &ReadOptions,
     ) -> Result<DBRawIterator, Error> {
         unsafe {
             Ok(DBRawIterator {
                 inner: ffi::rocksdb_create_iterator_cf(db.inner, readopts.inner, cf_handle.inner),
+                readopts: None,
+                _live: LiveIterator::new(db),
             })
         }
     }
@@ -217,6 +413,17 @@ impl DBRawIterator {
         unsafe { ffi::rocksdb_iter_valid(self.inner) != 0 }
     }
 
+    /// Returns the error the iterator stopped on, if any. An iterator that
+    /// is no longer valid has either reached the end of its range or
+    /// failed, e.g. with `ErrorKind::Incomplete` once it skipped more than
+    /// `ReadOptions::set_max_skippable_internal_keys` entries.
+    pub fn status(&self) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(ffi::rocksdb_iter_get_error(self.inner,));
+        }
+        Ok(())
+    }
+
     /// Seeks to the first key in the database.
     ///
     /// # Examples
@@ -397,6 +604,45 @@ impl DBRawIterator {
         unsafe { self.key_inner().map(|key| key.to_vec()) }
     }
 
+    /// Returns the current key without copying it.
+    ///
+    /// The slice borrows the iterator, so it is valid until the iterator
+    /// is moved to another entry, which takes `&mut self`, or dropped. The
+    /// borrow checker refuses code that keeps it any longer:
+    ///
+    /// ```compile_fail
+    /// use rocksdb::DB;
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_pinned_fail").unwrap();
+    /// let mut iter = db.raw_iterator();
+    /// iter.seek_to_first();
+    /// let key = iter.key_pinned();
+    /// iter.next(); // `iter` is still borrowed by `key`
+    /// println!("{:?}", key);
+    /// ```
+    ///
+    /// Copy the slice, e.g. with `key`, to keep it past the next move.
+    ///
+    /// ```
+    /// use rocksdb::DB;
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_pinned").unwrap();
+    /// db.put(b"k1", b"v1").unwrap();
+    /// db.put(b"k2", b"v22").unwrap();
+    ///
+    /// let mut iter = db.raw_iterator();
+    /// iter.seek_to_first();
+    /// let mut sizes = 0;
+    /// while let (Some(key), Some(value)) = (iter.key_pinned(), iter.value_pinned()) {
+    ///     sizes += key.len() + value.len();
+    ///     iter.next();
+    /// }
+    /// assert_eq!(sizes, 9);
+    /// ```
+    pub fn key_pinned(&self) -> Option<&[u8]> {
+        unsafe { self.key_inner() }
+    }
+
     /// Returns a slice to the internal buffer storing the current value.
     ///
     /// This may be slightly more performant to use than the standard ``.value()`` method
@@ -420,6 +666,12 @@ impl DBRawIterator {
     pub fn value(&self) -> Option<Vec<u8>> {
         unsafe { self.value_inner().map(|value| value.to_vec()) }
     }
+
+    /// Returns the current value without copying it. Like the slice of
+    /// `key_pinned`, it is valid until the iterator is moved or dropped.
+    pub fn value_pinned(&self) -> Option<&[u8]> {
+        unsafe { self.value_inner() }
+    }
 }
 
 impl Drop for DBRawIterator {
@@ -443,7 +695,7 @@ impl DBIterator {
 
     fn new_cf(
         db: &DB,
-        cf_handle: ColumnFamily,
+        cf_handle: &ColumnFamily,
         readopts: &ReadOptions,
         mode: IteratorMode,
     ) -> Result<DBIterator, Error> {
@@ -482,6 +734,12 @@ impl DBIterator {
     pub fn valid(&self) -> bool {
         self.raw.valid()
     }
+
+    /// Returns the error the iteration stopped on, if any. See
+    /// `DBRawIterator::status`.
+    pub fn status(&self) -> Result<(), Error> {
+        self.raw.status()
+    }
 }
 
 impl Iterator for DBIterator {
@@ -534,7 +792,7 @@ impl<'a> Snapshot<'a> {
 
     pub fn iterator_cf(
         &self,
-        cf_handle: ColumnFamily,
+        cf_handle: &ColumnFamily,
         mode: IteratorMode,
     ) -> Result<DBIterator, Error> {
         let mut readopts = ReadOptions::default();
@@ -542,13 +800,36 @@ impl<'a> Snapshot<'a> {
         DBIterator::new_cf(self.db, cf_handle, &readopts, mode)
     }
 
+    /// Like `iterator`, but reads with `readopts`, whose snapshot is set to
+    /// this one.
+    pub fn iterator_opt(&self, mode: IteratorMode, mut readopts: ReadOptions) -> DBIterator {
+        readopts.set_snapshot(self);
+        let mut iter = DBIterator::new(self.db, &readopts, mode);
+        iter.raw.readopts = Some(readopts);
+        iter
+    }
+
+    /// Like `iterator_cf`, but reads with `readopts`, whose snapshot is set
+    /// to this one.
+    pub fn iterator_cf_opt(
+        &self,
+        cf_handle: &ColumnFamily,
+        mode: IteratorMode,
+        mut readopts: ReadOptions,
+    ) -> Result<DBIterator, Error> {
+        readopts.set_snapshot(self);
+        let mut iter = try!(DBIterator::new_cf(self.db, cf_handle, &readopts, mode));
+        iter.raw.readopts = Some(readopts);
+        Ok(iter)
+    }
+
     pub fn raw_iterator(&self) -> DBRawIterator {
         let mut readopts = ReadOptions::default();
         readopts.set_snapshot(self);
         DBRawIterator::new(self.db, &readopts)
     }
 
-    pub fn raw_iterator_cf(&self, cf_handle: ColumnFamily) -> Result<DBRawIterator, Error> {
+    pub fn raw_iterator_cf(&self, cf_handle: &ColumnFamily) -> Result<DBRawIterator, Error> {
         let mut readopts = ReadOptions::default();
         readopts.set_snapshot(self);
         DBRawIterator::new_cf(self.db, cf_handle, &readopts)
@@ -560,11 +841,57 @@ impl<'a> Snapshot<'a> {
         self.db.get_opt(key, &readopts)
     }
 
-    pub fn get_cf(&self, cf: ColumnFamily, key: &[u8]) -> Result<Option<DBVector>, Error> {
+    pub fn get_cf(&self, cf: &ColumnFamily, key: &[u8]) -> Result<Option<DBVector>, Error> {
         let mut readopts = ReadOptions::default();
         readopts.set_snapshot(self);
         self.db.get_cf_opt(cf, key, &readopts)
     }
+
+    /// Reads `key` from `batch` merged over this snapshot, as if the batch
+    /// had been written on top of it.
+    pub fn get_with_batch(
+        &self,
+        batch: &WriteBatchWithIndex,
+        key: &[u8],
+    ) -> Result<Option<DBVector>, Error> {
+        let mut readopts = ReadOptions::default();
+        readopts.set_snapshot(self);
+        batch.get_merged(self.db, None, key, &readopts)
+    }
+
+    pub fn get_cf_with_batch(
+        &self,
+        batch: &WriteBatchWithIndex,
+        cf: &ColumnFamily,
+        key: &[u8],
+    ) -> Result<Option<DBVector>, Error> {
+        let mut readopts = ReadOptions::default();
+        readopts.set_snapshot(self);
+        batch.get_merged(self.db, Some(cf), key, &readopts)
+    }
+
+    /// Iterates over `batch` merged over this snapshot. See
+    /// `WriteBatchWithIndex::iterator_with_base`.
+    pub fn iterator_with_batch<'b>(
+        &self,
+        batch: &'b WriteBatchWithIndex,
+        mode: IteratorMode,
+    ) -> BatchIterator<'b> {
+        let mut readopts = ReadOptions::default();
+        readopts.set_snapshot(self);
+        batch.iterator_merged(self.db, None, &readopts, mode)
+    }
+
+    pub fn iterator_cf_with_batch<'b>(
+        &self,
+        batch: &'b WriteBatchWithIndex,
+        cf_handle: &ColumnFamily,
+        mode: IteratorMode,
+    ) -> Result<BatchIterator<'b>, Error> {
+        let mut readopts = ReadOptions::default();
+        readopts.set_snapshot(self);
+        Ok(batch.iterator_merged(self.db, Some(cf_handle), &readopts, mode))
+    }
 }
 
 impl<'a> Drop for Snapshot<'a> {
@@ -585,6 +912,121 @@ impl ColumnFamilyDescriptor {
     }
 }
 
+enum OpenMode {
+    ReadWrite,
+    ReadOnly { error_if_log_file_exist: bool },
+    Ttl(Duration),
+}
+
+/// A builder for opening a `DB`, combining the database `Options`, the
+/// column families to open and how to open the database.
+///
+/// # Examples
+///
+/// ```
+/// use rocksdb::{ColumnFamilyDescriptor, OpenOptions, Options};
+///
+/// let path = "path/for/rocksdb/storage_open_options";
+/// let db = OpenOptions::new()
+///     .create_if_missing(true)
+///     .create_missing_column_families(true)
+///     .column_family(ColumnFamilyDescriptor::new("cf1", Options::default()))
+///     .open(path)
+///     .unwrap();
+/// assert!(db.cf_handle("cf1").is_some());
+/// drop(db);
+///
+/// let db = OpenOptions::new()
+///     .column_family(ColumnFamilyDescriptor::new("cf1", Options::default()))
+///     .read_only(false)
+///     .open(path)
+///     .unwrap();
+/// assert!(db.put(b"k", b"v").is_err());
+/// ```
+pub struct OpenOptions {
+    opts: Options,
+    cfs: Vec<ColumnFamilyDescriptor>,
+    mode: OpenMode,
+}
+
+impl OpenOptions {
+    /// Starts from default `Options`.
+    pub fn new() -> OpenOptions {
+        OpenOptions::with_options(Options::default())
+    }
+
+    /// Starts from the given database options.
+    pub fn with_options(opts: Options) -> OpenOptions {
+        OpenOptions {
+            opts: opts,
+            cfs: Vec::new(),
+            mode: OpenMode::ReadWrite,
+        }
+    }
+
+    /// See `Options::create_if_missing`.
+    pub fn create_if_missing(mut self, create_if_missing: bool) -> OpenOptions {
+        self.opts.create_if_missing(create_if_missing);
+        self
+    }
+
+    /// See `Options::set_error_if_exists`.
+    pub fn error_if_exists(mut self, error_if_exists: bool) -> OpenOptions {
+        self.opts.set_error_if_exists(error_if_exists);
+        self
+    }
+
+    /// See `Options::create_missing_column_families`.
+    pub fn create_missing_column_families(mut self, create_missing_cfs: bool) -> OpenOptions {
+        self.opts.create_missing_column_families(create_missing_cfs);
+        self
+    }
+
+    /// Adds a column family to open. The default column family is always
+    /// opened, with default options unless it is added here.
+    pub fn column_family(mut self, cf: ColumnFamilyDescriptor) -> OpenOptions {
+        self.cfs.push(cf);
+        self
+    }
+
+    pub fn column_families<I>(mut self, cfs: I) -> OpenOptions
+    where
+        I: IntoIterator<Item = ColumnFamilyDescriptor>,
+    {
+        self.cfs.extend(cfs);
+        self
+    }
+
+    /// Opens the database read-only: writes fail and no background work is
+    /// done. If `error_if_log_file_exist` is true, opening fails when the
+    /// database has unflushed WAL files, which the read-only instance would
+    /// not see.
+    pub fn read_only(mut self, error_if_log_file_exist: bool) -> OpenOptions {
+        self.mode = OpenMode::ReadOnly { error_if_log_file_exist: error_if_log_file_exist };
+        self
+    }
+
+    /// Opens the database with a time to live: keys older than `ttl` are
+    /// removed by compactions, though reads may still return them until
+    /// then. The TTL has second granularity and must be used every time the
+    /// database is opened. Only the default column family can be opened with
+    /// a TTL.
+    pub fn ttl(mut self, ttl: Duration) -> OpenOptions {
+        self.mode = OpenMode::Ttl(ttl);
+        self
+    }
+
+    pub fn open<P: AsRef<Path>>(self, path: P) -> Result<DB, Error> {
+        DB::open_with_mode(&self.opts, path.as_ref(), self.cfs, &self.mode)
+    }
+}
+
+impl Default for OpenOptions {
+    fn default() -> OpenOptions {
+        OpenOptions::new()
+    }
+}
+
 impl DB {
     /// Open a database with default options.
     pub fn open_default<P: AsRef<Path>>(path: P) -> Result<DB, Error> {
@@ -609,7 +1051,16 @@ impl DB {
 
     /// Open a database with the given database options and column family names/options.
     pub fn open_cf_descriptors<P: AsRef<Path>>(opts: &Options, path: P, cfs: Vec<ColumnFamilyDescriptor>) -> Result<DB, Error> {
-        let path = path.as_ref();
+        DB::open_with_mode(opts, path.as_ref(), cfs, &OpenMode::ReadWrite)
+    }
+
+    fn open_with_mode(
+        opts: &Options,
+        path: &Path,
+        cfs: Vec<ColumnFamilyDescriptor>,
+        mode: &OpenMode,
+    ) -> Result<DB, Error> {
+        enter_span!("rocksdb.open", path = ?path);
         let cpath = match CString::new(path.to_string_lossy().as_bytes()) {
             Ok(c) => c,
             Err(_) => {
@@ -621,12 +1072,18 @@ impl DB {
             }
         };
 
-        if let Err(e) = fs::create_dir_all(&path) {
-            return Err(Error::new(format!(
-                "Failed to create RocksDB\
-                                           directory: `{:?}`.",
-                e
-            )));
+        let read_only = match *mode {
+            OpenMode::ReadOnly { .. } => true,
+            _ => false,
+        };
+        if !read_only {
+            if let Err(e) = fs::create_dir_all(&path) {
+                return Err(Error::new(format!(
+                    "Failed to create RocksDB\
+                                               directory: `{:?}`.",
+                    e
+                )));
+            }
         }
 
         let db: *mut ffi::rocksdb_t;
@@ -634,8 +1091,33 @@ impl DB {
 
         if cfs.len() == 0 {
             unsafe {
-                db = ffi_try!(ffi::rocksdb_open(opts.inner, cpath.as_ptr() as *const _,));
+                db = match *mode {
+                    OpenMode::ReadWrite => {
+                        ffi_try!(ffi::rocksdb_open(opts.inner, cpath.as_ptr() as *const _,))
+                    }
+                    OpenMode::ReadOnly { error_if_log_file_exist } => {
+                        ffi_try!(ffi::rocksdb_open_for_read_only(
+                            opts.inner,
+                            cpath.as_ptr() as *const _,
+                            error_if_log_file_exist as c_uchar,
+                        ))
+                    }
+                    OpenMode::Ttl(ttl) => {
+                        ffi_try!(ffi::rocksdb_open_with_ttl(
+                            opts.inner,
+                            cpath.as_ptr() as *const _,
+                            ttl.as_secs() as c_int,
+                        ))
+                    }
+                };
             }
+        } else if let OpenMode::Ttl(_) = *mode {
+            return Err(Error::with_kind(
+                ErrorKind::NotSupported,
+                "the bundled RocksDB cannot open column families \
+                 with a TTL."
+                    .to_owned(),
+            ));
         } else {
             let mut cfs_v = cfs;
             // Always open the default column family.
@@ -662,13 +1144,27 @@ impl DB {
                 .collect();
 
             unsafe {
-                db = ffi_try!(ffi::rocksdb_open_column_families(
-                    opts.inner,
-                    cpath.as_ptr(),
-                    cfs_v.len() as c_int,
-                    cfnames.as_mut_ptr(),
-                    cfopts.as_mut_ptr(),
-                    cfhandles.as_mut_ptr(),));
+                db = match *mode {
+                    OpenMode::ReadOnly { error_if_log_file_exist } => {
+                        ffi_try!(ffi::rocksdb_open_for_read_only_column_families(
+                            opts.inner,
+                            cpath.as_ptr(),
+                            cfs_v.len() as c_int,
+                            cfnames.as_mut_ptr(),
+                            cfopts.as_mut_ptr(),
+                            cfhandles.as_mut_ptr(),
+                            error_if_log_file_exist as c_uchar,))
+                    }
+                    _ => {
+                        ffi_try!(ffi::rocksdb_open_column_families(
+                            opts.inner,
+                            cpath.as_ptr(),
+                            cfs_v.len() as c_int,
+                            cfnames.as_mut_ptr(),
+                            cfopts.as_mut_ptr(),
+                            cfhandles.as_mut_ptr(),))
+                    }
+                };
             }
 
             for handle in &cfhandles {
@@ -694,9 +1190,45 @@ impl DB {
             inner: db,
             cfs: cf_map,
             path: path.to_path_buf(),
+            _env: opts.env.clone(),
+            live_iterators: Arc::new(AtomicUsize::new(0)),
+            default_readopts: ReadOptions::default(),
+            default_writeopts: WriteOptions::default(),
+            update_locks: (0..UPDATE_LOCK_STRIPES).map(|_| Mutex::new(())).collect(),
         })
     }
 
+    /// Closes the database, failing if iterators created from it are still
+    /// alive, as using them after the database is closed is undefined
+    /// behavior. On failure the database is returned along with an
+    /// `ErrorKind::Busy` error, so it can be closed again once the iterators
+    /// are dropped.
+    ///
+    /// Snapshots and column family handles borrow the database, so those
+    /// cannot outlive it in the first place. Dropping a `DB` closes it
+    /// without this check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::{DB, IteratorMode};
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_close").unwrap();
+    /// let iter = db.iterator(IteratorMode::Start);
+    /// let (db, _) = db.close().unwrap_err();
+    /// drop(iter);
+    /// assert!(db.close().is_ok());
+    /// ```
+    pub fn close(self) -> Result<(), (DB, Error)> {
+        let iterators = self.live_iterators.load(Ordering::SeqCst);
+        if iterators > 0 {
+            let err = Error::with_kind(ErrorKind::Busy,
+                                       format!("{} iterators are still alive", iterators));
+            return Err((self, err));
+        }
+        Ok(())
+    }
+
     pub fn list_cf<P: AsRef<Path>>(opts: &Options, path: P) -> Result<Vec<String>, Error> {
         let path = path.as_ref();
         let cpath = match CString::new(path.to_string_lossy().as_bytes()) {
@@ -748,7 +1280,23 @@ impl DB {
         &self.path.as_path()
     }
 
+    /// Returns the unique identity RocksDB generated for this database when
+    /// it was created, as stored in its `IDENTITY` file. The identity
+    /// survives restarts, so it can be used to tell data directories apart.
+    ///
+    /// The file is read directly from disk, so this fails for databases
+    /// running on an in-memory `Env`.
+    pub fn get_db_identity(&self) -> Result<String, Error> {
+        let mut identity = String::new();
+        match fs::File::open(self.path.join("IDENTITY"))
+            .and_then(|mut f| f.read_to_string(&mut identity)) {
+            Ok(_) => Ok(identity.trim().to_owned()),
+            Err(e) => Err(Error::with_kind(ErrorKind::IOError, format!("reading IDENTITY: {}", e))),
+        }
+    }
+
     pub fn write_opt(&self, batch: WriteBatch, writeopts: &WriteOptions) -> Result<(), Error> {
+        enter_span!("rocksdb.write", updates = batch.len());
         unsafe {
             ffi_try!(ffi::rocksdb_write(self.inner, writeopts.inner, batch.inner,));
         }
@@ -756,7 +1304,61 @@ impl DB {
     }
 
     pub fn write(&self, batch: WriteBatch) -> Result<(), Error> {
-        self.write_opt(batch, &WriteOptions::default())
+        self.write_opt(batch, &self.default_writeopts)
+    }
+
+    pub fn write_with_index_opt(
+        &self,
+        batch: WriteBatchWithIndex,
+        writeopts: &WriteOptions,
+    ) -> Result<(), Error> {
+        enter_span!("rocksdb.write", updates = batch.len());
+        unsafe {
+            ffi_try!(ffi::rocksdb_write_writebatch_wi(self.inner, writeopts.inner, batch.inner,));
+        }
+        Ok(())
+    }
+
+    /// Writes the updates of a `WriteBatchWithIndex` atomically, like
+    /// `write`.
+    pub fn write_with_index(&self, batch: WriteBatchWithIndex) -> Result<(), Error> {
+        self.write_with_index_opt(batch, &self.default_writeopts)
+    }
+
+    /// Returns the sequence number of the most recent update.
+    pub fn latest_sequence_number(&self) -> u64 {
+        unsafe { ffi::rocksdb_get_latest_sequence_number(self.inner) }
+    }
+
+    /// Returns an iterator over the write batches in the write-ahead log,
+    /// starting with the one containing the update numbered `seq`.
+    ///
+    /// Only updates whose log files have not been deleted can be read: see
+    /// `Options::set_wal_ttl_seconds` and `Options::set_wal_size_limit_mb`
+    /// to keep them around. The iterator counts as a live iterator for
+    /// `DB::close`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::DB;
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_updates_since").unwrap();
+    /// db.put(b"k1", b"v1").unwrap();
+    /// db.put(b"k2", b"v2").unwrap();
+    ///
+    /// let seq = db.latest_sequence_number();
+    /// let (first, batch) = db.get_updates_since(seq).unwrap().next().unwrap().unwrap();
+    /// assert_eq!((first, batch.len()), (seq, 1));
+    /// ```
+    pub fn get_updates_since(&self, seq: u64) -> Result<WalIterator, Error> {
+        unsafe {
+            let iter = ffi_try!(ffi::rocksdb_get_updates_since(self.inner, seq, ptr::null(),));
+            Ok(WalIterator {
+                inner: iter,
+                _live: LiveIterator::new(self),
+            })
+        }
     }
 
     pub fn write_without_wal(&self, batch: WriteBatch) -> Result<(), Error> {
@@ -766,6 +1368,7 @@ impl DB {
     }
 
     pub fn get_opt(&self, key: &[u8], readopts: &ReadOptions) -> Result<Option<DBVector>, Error> {
+        enter_span!("rocksdb.get", key_len = key.len());
         if readopts.inner.is_null() {
             return Err(Error::new(
                 "Unable to create RocksDB read options. \
@@ -786,6 +1389,7 @@ impl DB {
                 key.len() as size_t,
                 &mut val_len,
             )) as *mut u8;
+            trace_event!(found = !val.is_null());
             if val.is_null() {
                 Ok(None)
             } else {
@@ -796,15 +1400,16 @@ impl DB {
 
     /// Return the bytes associated with a key value
     pub fn get(&self, key: &[u8]) -> Result<Option<DBVector>, Error> {
-        self.get_opt(key, &ReadOptions::default())
+        self.get_opt(key, &self.default_readopts)
     }
 
     pub fn get_cf_opt(
         &self,
-        cf: ColumnFamily,
+        cf: &ColumnFamily,
         key: &[u8],
         readopts: &ReadOptions,
     ) -> Result<Option<DBVector>, Error> {
+        enter_span!("rocksdb.get", key_len = key.len());
         if readopts.inner.is_null() {
             return Err(Error::new(
                 "Unable to create RocksDB read options. \
@@ -826,6 +1431,7 @@ impl DB {
                 key.len() as size_t,
                 &mut val_len,
             )) as *mut u8;
+            trace_event!(found = !val.is_null());
             if val.is_null() {
                 Ok(None)
             } else {
@@ -834,11 +1440,151 @@ impl DB {
         }
     }
 
-    pub fn get_cf(&self, cf: ColumnFamily, key: &[u8]) -> Result<Option<DBVector>, Error> {
-        self.get_cf_opt(cf, key, &ReadOptions::default())
+    pub fn get_cf(&self, cf: &ColumnFamily, key: &[u8]) -> Result<Option<DBVector>, Error> {
+        self.get_cf_opt(cf, key, &self.default_readopts)
+    }
+
+    /// Looks up several keys in one call from a single consistent view of
+    /// the database, returning a result per key in the same order as
+    /// `keys`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::DB;
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_multi_get").unwrap();
+    /// db.put(b"k1", b"v1").unwrap();
+    ///
+    /// let mut values = db.multi_get(&[b"k1", b"k2"]).into_iter();
+    /// assert_eq!(&*values.next().unwrap().unwrap().unwrap(), b"v1");
+    /// assert!(values.next().unwrap().unwrap().is_none());
+    /// ```
+    pub fn multi_get<I, K>(&self, keys: I) -> Vec<Result<Option<DBVector>, Error>>
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<[u8]>,
+    {
+        let keys: Vec<K> = keys.into_iter().collect();
+        self.multi_get_raw(None, &keys)
+    }
+
+    /// Like `multi_get`, with each key read from the given column family.
+    pub fn multi_get_cf<'a, I, K>(&self, keys: I) -> Vec<Result<Option<DBVector>, Error>>
+    where
+        I: IntoIterator<Item = (&'a ColumnFamily, K)>,
+        K: AsRef<[u8]>,
+    {
+        let (cfs, keys): (Vec<_>, Vec<K>) = keys.into_iter()
+            .map(|(cf, key)| (cf.inner as *const _, key))
+            .unzip();
+        self.multi_get_raw(Some(&cfs), &keys)
+    }
+
+    fn multi_get_raw<K: AsRef<[u8]>>(
+        &self,
+        cfs: Option<&[*const ffi::rocksdb_column_family_handle_t]>,
+        keys: &[K],
+    ) -> Vec<Result<Option<DBVector>, Error>> {
+        enter_span!("rocksdb.multi_get", keys = keys.len());
+        let readopts = &self.default_readopts;
+        let key_ptrs: Vec<*const c_char> = keys.iter()
+            .map(|k| k.as_ref().as_ptr() as *const c_char)
+            .collect();
+        let key_sizes: Vec<size_t> = keys.iter().map(|k| k.as_ref().len() as size_t).collect();
+        let mut values: Vec<*mut c_char> = vec![ptr::null_mut(); keys.len()];
+        let mut value_sizes: Vec<size_t> = vec![0; keys.len()];
+        let mut errs: Vec<*mut c_char> = vec![ptr::null_mut(); keys.len()];
+
+        unsafe {
+            match cfs {
+                Some(cfs) => ffi::rocksdb_multi_get_cf(
+                    self.inner,
+                    readopts.inner,
+                    cfs.as_ptr(),
+                    keys.len(),
+                    key_ptrs.as_ptr(),
+                    key_sizes.as_ptr(),
+                    values.as_mut_ptr(),
+                    value_sizes.as_mut_ptr(),
+                    errs.as_mut_ptr(),
+                ),
+                None => ffi::rocksdb_multi_get(
+                    self.inner,
+                    readopts.inner,
+                    keys.len(),
+                    key_ptrs.as_ptr(),
+                    key_sizes.as_ptr(),
+                    values.as_mut_ptr(),
+                    value_sizes.as_mut_ptr(),
+                    errs.as_mut_ptr(),
+                ),
+            }
+
+            values.into_iter()
+                .zip(value_sizes)
+                .zip(errs)
+                .map(|((value, size), err)| if !err.is_null() {
+                    Err(Error::new(error_message(err)))
+                } else if value.is_null() {
+                    Ok(None)
+                } else {
+                    Ok(Some(DBVector::from_c(value as *mut u8, size)))
+                })
+                .collect()
+        }
+    }
+
+    /// Reads the value of `key` into `buf`, replacing its contents, and
+    /// returns the length of the value, or `None` (leaving `buf` empty) if
+    /// the key does not exist.
+    ///
+    /// Reusing `buf` across lookups avoids allocating an owned copy of each
+    /// value once the buffer is large enough. The bundled RocksDB has no
+    /// pinned reads in its C API, so the value is still copied out of a
+    /// temporary buffer allocated by RocksDB.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::DB;
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_get_into").unwrap();
+    /// db.put(b"k1", b"v1").unwrap();
+    ///
+    /// let mut buf = Vec::new();
+    /// for key in &[b"k1", b"k2"] {
+    ///     if let Some(len) = db.get_into(*key, &mut buf).unwrap() {
+    ///         assert_eq!(&buf[..len], b"v1");
+    ///     }
+    /// }
+    /// ```
+    pub fn get_into(&self, key: &[u8], buf: &mut Vec<u8>) -> Result<Option<usize>, Error> {
+        buf.clear();
+        let value = try!(self.get(key));
+        Ok(value.map(|value| {
+            buf.extend_from_slice(&value);
+            value.len()
+        }))
     }
 
-    pub fn create_cf(&mut self, name: &str, opts: &Options) -> Result<ColumnFamily, Error> {
+    pub fn get_cf_into(
+        &self,
+        cf: &ColumnFamily,
+        key: &[u8],
+        buf: &mut Vec<u8>,
+    ) -> Result<Option<usize>, Error> {
+        buf.clear();
+        let value = try!(self.get_cf(cf, key));
+        Ok(value.map(|value| {
+            buf.extend_from_slice(&value);
+            value.len()
+        }))
+    }
+
+    /// Creates a column family, whose handle can then be borrowed with
+    /// `cf_handle`.
+    pub fn create_cf(&mut self, name: &str, opts: &Options) -> Result<(), Error> {
         let cname = match CString::new(name.as_bytes()) {
             Ok(c) => c,
             Err(_) => {
@@ -849,43 +1595,105 @@ impl DB {
                 ))
             }
         };
-        let cf = unsafe {
+        unsafe {
             let cf_handler = ffi_try!(ffi::rocksdb_create_column_family(
                 self.inner,
                 opts.inner,
                 cname.as_ptr(),
             ));
-            let cf = ColumnFamily { inner: cf_handler };
-            self.cfs.insert(name.to_string(), cf);
-            cf
-        };
-        Ok(cf)
+            self.cfs.insert(name.to_string(), ColumnFamily { inner: cf_handler });
+        }
+        Ok(())
     }
 
+    /// Drops a column family and destroys its handle. RocksDB deletes the
+    /// column family's files once no iterator reads from it any more.
     pub fn drop_cf(&mut self, name: &str) -> Result<(), Error> {
-        let cf = self.cfs.get(name);
-        if cf.is_none() {
-            return Err(Error::new(
-                format!("Invalid column family: {}", name).to_owned(),
-            ));
-        }
-        unsafe {
-            ffi_try!(ffi::rocksdb_drop_column_family(
-                self.inner,
-                cf.unwrap().inner,
-            ));
+        let cf = match self.cfs.get(name) {
+            Some(cf) => cf.inner,
+            None => {
+                return Err(Error::new(
+                    format!("Invalid column family: {}", name).to_owned(),
+                ))
+            }
+        };
+        unsafe {
+            ffi_try!(ffi::rocksdb_drop_column_family(self.inner, cf,));
+            ffi::rocksdb_column_family_handle_destroy(cf);
         }
+        self.cfs.remove(name);
         Ok(())
     }
 
-    /// Return the underlying column family handle.
-    pub fn cf_handle(&self, name: &str) -> Option<ColumnFamily> {
-        self.cfs.get(name).cloned()
+    /// Drops a column family like `drop_cf`, then waits up to `timeout` for
+    /// its SST files to be deleted from disk, so that the space is reclaimed
+    /// when this returns. Fails with `ErrorKind::TimedOut` if files remain,
+    /// e.g. because an iterator over the column family is still alive or
+    /// file deletions are disabled.
+    pub fn drop_cf_and_wait(&mut self, name: &str, timeout: Duration) -> Result<(), Error> {
+        let before = try!(self.get_live_files(false));
+        try!(self.drop_cf(name));
+        let after: Vec<String> = try!(self.get_live_files(false))
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+        let dropped: Vec<PathBuf> = before
+            .into_iter()
+            .filter(|f| !after.contains(&f.name))
+            .map(|f| self.path.join(f.name.trim_start_matches('/')))
+            .collect();
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = dropped.iter().filter(|p| p.exists()).count();
+            if remaining == 0 {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::with_kind(ErrorKind::TimedOut, format!(
+                    "{} files of column family {} were not deleted",
+                    remaining,
+                    name
+                )));
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Return the underlying column family handle. The handle borrows the
+    /// database, so it cannot outlive it or be used across `drop_cf`.
+    pub fn cf_handle(&self, name: &str) -> Option<&ColumnFamily> {
+        self.cfs.get(name)
     }
 
     pub fn iterator(&self, mode: IteratorMode) -> DBIterator {
-        let opts = ReadOptions::default();
-        DBIterator::new(self, &opts, mode)
+        DBIterator::new(self, &self.default_readopts, mode)
+    }
+
+    /// Creates an iterator using the given read options, e.g. to scan across
+    /// prefixes with `ReadOptions::set_total_order_seek` when a prefix
+    /// extractor is configured. The iterator keeps the options alive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::{DB, IteratorMode, Options, ReadOptions, SliceTransform};
+    ///
+    /// let mut opts = Options::default();
+    /// opts.create_if_missing(true);
+    /// opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(3));
+    /// let db = DB::open(&opts, "path/for/rocksdb/storage_iterator_opt").unwrap();
+    ///
+    /// let mut readopts = ReadOptions::default();
+    /// readopts.set_total_order_seek(true);
+    /// for (key, value) in db.iterator_opt(IteratorMode::Start, readopts) {
+    ///     println!("Saw {:?} {:?}", key, value);
+    /// }
+    /// ```
+    pub fn iterator_opt(&self, mode: IteratorMode, readopts: ReadOptions) -> DBIterator {
+        let mut iter = DBIterator::new(self, &readopts, mode);
+        iter.raw.readopts = Some(readopts);
+        iter
     }
 
     pub fn prefix_iterator<'a>(&self, prefix: &'a [u8]) -> DBIterator {
@@ -896,16 +1704,26 @@ impl DB {
 
     pub fn iterator_cf(
         &self,
-        cf_handle: ColumnFamily,
+        cf_handle: &ColumnFamily,
+        mode: IteratorMode,
+    ) -> Result<DBIterator, Error> {
+        DBIterator::new_cf(self, cf_handle, &self.default_readopts, mode)
+    }
+
+    pub fn iterator_cf_opt(
+        &self,
+        cf_handle: &ColumnFamily,
         mode: IteratorMode,
+        readopts: ReadOptions,
     ) -> Result<DBIterator, Error> {
-        let opts = ReadOptions::default();
-        DBIterator::new_cf(self, cf_handle, &opts, mode)
+        let mut iter = try!(DBIterator::new_cf(self, cf_handle, &readopts, mode));
+        iter.raw.readopts = Some(readopts);
+        Ok(iter)
     }
 
     pub fn prefix_iterator_cf<'a>(
         &self,
-        cf_handle: ColumnFamily,
+        cf_handle: &ColumnFamily,
         prefix: &'a [u8]
     ) -> Result<DBIterator, Error> {
         let mut opts = ReadOptions::default();
@@ -914,13 +1732,11 @@ impl DB {
     }
 
     pub fn raw_iterator(&self) -> DBRawIterator {
-        let opts = ReadOptions::default();
-        DBRawIterator::new(self, &opts)
+        DBRawIterator::new(self, &self.default_readopts)
     }
 
-    pub fn raw_iterator_cf(&self, cf_handle: ColumnFamily) -> Result<DBRawIterator, Error> {
-        let opts = ReadOptions::default();
-        DBRawIterator::new_cf(self, cf_handle, &opts)
+    pub fn raw_iterator_cf(&self, cf_handle: &ColumnFamily) -> Result<DBRawIterator, Error> {
+        DBRawIterator::new_cf(self, cf_handle, &self.default_readopts)
     }
 
     pub fn snapshot(&self) -> Snapshot {
@@ -928,6 +1744,7 @@ impl DB {
     }
 
     pub fn put_opt(&self, key: &[u8], value: &[u8], writeopts: &WriteOptions) -> Result<(), Error> {
+        enter_span!("rocksdb.put", key_len = key.len());
         unsafe {
             ffi_try!(ffi::rocksdb_put(
                 self.inner,
@@ -943,11 +1760,12 @@ impl DB {
 
     pub fn put_cf_opt(
         &self,
-        cf: ColumnFamily,
+        cf: &ColumnFamily,
         key: &[u8],
         value: &[u8],
         writeopts: &WriteOptions,
     ) -> Result<(), Error> {
+        enter_span!("rocksdb.put", key_len = key.len());
         unsafe {
             ffi_try!(ffi::rocksdb_put_cf(
                 self.inner,
@@ -968,6 +1786,7 @@ impl DB {
         value: &[u8],
         writeopts: &WriteOptions,
     ) -> Result<(), Error> {
+        enter_span!("rocksdb.merge", key_len = key.len());
         unsafe {
             ffi_try!(ffi::rocksdb_merge(
                 self.inner,
@@ -983,11 +1802,12 @@ impl DB {
 
     pub fn merge_cf_opt(
         &self,
-        cf: ColumnFamily,
+        cf: &ColumnFamily,
         key: &[u8],
         value: &[u8],
         writeopts: &WriteOptions,
     ) -> Result<(), Error> {
+        enter_span!("rocksdb.merge", key_len = key.len());
         unsafe {
             ffi_try!(ffi::rocksdb_merge_cf(
                 self.inner,
@@ -1003,6 +1823,7 @@ impl DB {
     }
 
     pub fn delete_opt(&self, key: &[u8], writeopts: &WriteOptions) -> Result<(), Error> {
+        enter_span!("rocksdb.delete", key_len = key.len());
         unsafe {
             ffi_try!(ffi::rocksdb_delete(
                 self.inner,
@@ -1016,10 +1837,11 @@ impl DB {
 
     pub fn delete_cf_opt(
         &self,
-        cf: ColumnFamily,
+        cf: &ColumnFamily,
         key: &[u8],
         writeopts: &WriteOptions,
     ) -> Result<(), Error> {
+        enter_span!("rocksdb.delete", key_len = key.len());
         unsafe {
             ffi_try!(ffi::rocksdb_delete_cf(
                 self.inner,
@@ -1033,30 +1855,133 @@ impl DB {
     }
 
     pub fn put(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
-        self.put_opt(key, value, &WriteOptions::default())
+        self.put_opt(key, value, &self.default_writeopts)
     }
 
-    pub fn put_cf(&self, cf: ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
-        self.put_cf_opt(cf, key, value, &WriteOptions::default())
+    pub fn put_cf(&self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.put_cf_opt(cf, key, value, &self.default_writeopts)
     }
 
     pub fn merge(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
-        self.merge_opt(key, value, &WriteOptions::default())
+        self.merge_opt(key, value, &self.default_writeopts)
+    }
+
+    pub fn merge_cf(&self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.merge_cf_opt(cf, key, value, &self.default_writeopts)
     }
 
-    pub fn merge_cf(&self, cf: ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
-        self.merge_cf_opt(cf, key, value, &WriteOptions::default())
+    /// Appends `value` to the value of `key`. The database must have been
+    /// opened with `Options::set_append_merge_operator`; this is a `merge`
+    /// otherwise.
+    pub fn append(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.merge(key, value)
+    }
+
+    pub fn append_cf(&self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        self.merge_cf(cf, key, value)
     }
 
     pub fn delete(&self, key: &[u8]) -> Result<(), Error> {
-        self.delete_opt(key, &WriteOptions::default())
+        self.delete_opt(key, &self.default_writeopts)
     }
 
-    pub fn delete_cf(&self, cf: ColumnFamily, key: &[u8]) -> Result<(), Error> {
-        self.delete_cf_opt(cf, key, &WriteOptions::default())
+    pub fn delete_cf(&self, cf: &ColumnFamily, key: &[u8]) -> Result<(), Error> {
+        self.delete_cf_opt(cf, key, &self.default_writeopts)
+    }
+
+    /// Replaces the value of `key` with the result of `f`, which is given
+    /// the current value, or deletes the key if `f` returns `None`. Returns
+    /// the new value.
+    ///
+    /// This is a process-local helper rather than a transaction: the write
+    /// only waits for other `update` and `compare_and_swap` calls on the
+    /// same key of this `DB` handle. It is not atomic with respect to
+    /// `put`, `delete`, `merge` or `write`, other handles on the database,
+    /// or other processes.
+    ///
+    /// `f` runs without the lock held, so it may call `update` itself. If
+    /// the value changed by the time the lock is taken, `f` is called again
+    /// with the new value, so it should have no side effects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::DB;
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_update").unwrap();
+    /// let increment = |v: Option<&[u8]>| Some(vec![v.map_or(0, |v| v[0]) + 1]);
+    /// db.update(b"counter", increment).unwrap();
+    /// assert_eq!(db.update(b"counter", increment).unwrap(), Some(vec![2]));
+    /// ```
+    pub fn update<F>(&self, key: &[u8], mut f: F) -> Result<Option<Vec<u8>>, Error>
+    where
+        F: FnMut(Option<&[u8]>) -> Option<Vec<u8>>,
+    {
+        loop {
+            let current = try!(self.get(key));
+            let new = f(current.as_ref().map(|v| &**v));
+            let _lock = self.update_lock(key);
+            let now = try!(self.get(key));
+            if now.as_ref().map(|v| &**v) != current.as_ref().map(|v| &**v) {
+                continue;
+            }
+            match new {
+                Some(ref value) => try!(self.put(key, value)),
+                None => try!(self.delete(key)),
+            }
+            return Ok(new);
+        }
+    }
+
+    /// Sets `key` to `new` if its current value is `expected`, where `None`
+    /// means the key is absent, and returns whether it did. Deletes the key
+    /// if `new` is `None`. Like `update`, this is only atomic with respect
+    /// to the other `update` and `compare_and_swap` calls on this `DB`
+    /// handle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::DB;
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_cas").unwrap();
+    /// assert!(db.compare_and_swap(b"k", None, Some(b"v1")).unwrap());
+    /// assert!(!db.compare_and_swap(b"k", None, Some(b"v2")).unwrap());
+    /// assert!(db.compare_and_swap(b"k", Some(b"v1"), None).unwrap());
+    /// ```
+    pub fn compare_and_swap(
+        &self,
+        key: &[u8],
+        expected: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> Result<bool, Error> {
+        let _lock = self.update_lock(key);
+        let matches = {
+            let current = try!(self.get(key));
+            current.as_ref().map(|v| &**v) == expected
+        };
+        if matches {
+            match new {
+                Some(value) => try!(self.put(key, value)),
+                None => try!(self.delete(key)),
+            }
+        }
+        Ok(matches)
+    }
+
+    fn update_lock(&self, key: &[u8]) -> MutexGuard<()> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let stripe = hasher.finish() as usize % self.update_locks.len();
+        match self.update_locks[stripe].lock() {
+            Ok(guard) => guard,
+            // A panic in another update leaves nothing half-written.
+            Err(poisoned) => poisoned.into_inner(),
+        }
     }
 
     pub fn compact_range(&self, start: Option<&[u8]>, end: Option<&[u8]>) {
+        enter_span!("rocksdb.compact_range");
         unsafe {
             ffi::rocksdb_compact_range(
                 self.inner,
@@ -1068,7 +1993,8 @@ impl DB {
         }
     }
 
-    pub fn compact_range_cf(&self, cf: ColumnFamily, start: Option<&[u8]>, end: Option<&[u8]>) {
+    pub fn compact_range_cf(&self, cf: &ColumnFamily, start: Option<&[u8]>, end: Option<&[u8]>) {
+        enter_span!("rocksdb.compact_range");
         unsafe {
             ffi::rocksdb_compact_range_cf(
                 self.inner,
@@ -1080,9 +2006,281 @@ impl DB {
             );
         }
     }
+
+    /// Returns the approximate size on disk of the keys in each of the
+    /// `[start, end)` ranges. Data still in the memtables is not counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::DB;
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_approximate_sizes").unwrap();
+    /// let sizes = db.approximate_sizes(&[(b"a", b"m"), (b"m", b"z")]);
+    /// assert_eq!(sizes.len(), 2);
+    /// ```
+    pub fn approximate_sizes(&self, ranges: &[(&[u8], &[u8])]) -> Vec<u64> {
+        self.approximate_sizes_impl(None, ranges)
+    }
+
+    pub fn approximate_sizes_cf(&self, cf: &ColumnFamily, ranges: &[(&[u8], &[u8])]) -> Vec<u64> {
+        self.approximate_sizes_impl(Some(cf), ranges)
+    }
+
+    fn approximate_sizes_impl(
+        &self,
+        cf: Option<&ColumnFamily>,
+        ranges: &[(&[u8], &[u8])],
+    ) -> Vec<u64> {
+        let starts: Vec<*const c_char> =
+            ranges.iter().map(|r| r.0.as_ptr() as *const c_char).collect();
+        let start_lens: Vec<size_t> = ranges.iter().map(|r| r.0.len() as size_t).collect();
+        let limits: Vec<*const c_char> =
+            ranges.iter().map(|r| r.1.as_ptr() as *const c_char).collect();
+        let limit_lens: Vec<size_t> = ranges.iter().map(|r| r.1.len() as size_t).collect();
+        let mut sizes = vec![0; ranges.len()];
+        unsafe {
+            match cf {
+                Some(cf) => ffi::rocksdb_approximate_sizes_cf(
+                    self.inner,
+                    cf.inner,
+                    ranges.len() as c_int,
+                    starts.as_ptr(),
+                    start_lens.as_ptr(),
+                    limits.as_ptr(),
+                    limit_lens.as_ptr(),
+                    sizes.as_mut_ptr(),
+                ),
+                None => ffi::rocksdb_approximate_sizes(
+                    self.inner,
+                    ranges.len() as c_int,
+                    starts.as_ptr(),
+                    start_lens.as_ptr(),
+                    limits.as_ptr(),
+                    limit_lens.as_ptr(),
+                    sizes.as_mut_ptr(),
+                ),
+            }
+        }
+        sizes
+    }
+
+    /// Ends a load started with `Options::prepare_for_bulk_load` by
+    /// compacting every column family, memtables included, so that the
+    /// loaded data is sorted into levels before regular use. Automatic
+    /// compactions stay disabled until the database is reopened without the
+    /// bulk load options.
+    pub fn finish_bulk_load(&self) {
+        self.compact_range(None, None);
+        for (name, cf) in &self.cfs {
+            if name != "default" {
+                self.compact_range_cf(cf, None, None);
+            }
+        }
+    }
+
+    /// Returns the value of a database property such as `rocksdb.stats` or
+    /// `rocksdb.estimate-num-keys`, or `None` if the property is unknown.
+    ///
+    /// See [db.h](https://github.com/facebook/rocksdb/blob/master/include/rocksdb/db.h)
+    /// for the list of supported properties.
+    pub fn property_value(&self, name: &str) -> Result<Option<String>, Error> {
+        with_property_name(name, |name| unsafe {
+            property_string(ffi::rocksdb_property_value(self.inner, name))
+        })
+    }
+
+    /// Returns the value of a property of the given column family.
+    pub fn property_value_cf(&self, cf: &ColumnFamily, name: &str) -> Result<Option<String>, Error> {
+        with_property_name(name, |name| unsafe {
+            property_string(ffi::rocksdb_property_value_cf(self.inner, cf.inner, name))
+        })
+    }
+
+    /// Returns the value of a numeric property such as
+    /// `rocksdb.estimate-num-keys`, or `None` if the property is unknown or
+    /// not numeric.
+    pub fn property_int_value(&self, name: &str) -> Result<Option<u64>, Error> {
+        with_property_name(name, |name| unsafe {
+            property_int(ffi::rocksdb_property_value(self.inner, name))
+        })
+    }
+
+    /// Returns the value of a numeric property of the given column family.
+    pub fn property_int_value_cf(&self, cf: &ColumnFamily, name: &str) -> Result<Option<u64>, Error> {
+        with_property_name(name, |name| unsafe {
+            property_int(ffi::rocksdb_property_value_cf(self.inner, cf.inner, name))
+        })
+    }
+
+    /// Flushes all memtables to SST files, waiting for the flush to finish.
+    pub fn flush(&self) -> Result<(), Error> {
+        enter_span!("rocksdb.flush");
+        unsafe {
+            let opts = ffi::rocksdb_flushoptions_create();
+            ffi::rocksdb_flushoptions_set_wait(opts, 1);
+            let mut err: *mut c_char = ptr::null_mut();
+            ffi::rocksdb_flush(self.inner, opts, &mut err);
+            ffi::rocksdb_flushoptions_destroy(opts);
+            if !err.is_null() {
+                let message = error_message(err);
+                trace_event!(error = %message, "rocksdb call failed");
+                return Err(Error::new(message));
+            }
+        }
+        Ok(())
+    }
+
+    /// Prevents RocksDB from deleting obsolete files, e.g. while an external
+    /// tool copies or hard-links the files listed by `get_live_files`.
+    /// Calls nest: deletions resume once `enable_file_deletions` has been
+    /// called as many times, or once with `force`.
+    pub fn disable_file_deletions(&self) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(ffi::rocksdb_disable_file_deletions(self.inner,));
+        }
+        Ok(())
+    }
+
+    /// Allows file deletions again after `disable_file_deletions`. With
+    /// `force`, deletions are enabled regardless of how many times they
+    /// were disabled.
+    pub fn enable_file_deletions(&self, force: bool) -> Result<(), Error> {
+        unsafe {
+            ffi_try!(ffi::rocksdb_enable_file_deletions(self.inner, force as c_uchar,));
+        }
+        Ok(())
+    }
+
+    /// Deletes the SST file `name`, as listed by `get_live_files`, to reclaim
+    /// its space. RocksDB only deletes files in the last level holding data
+    /// and L0 files that are not overlapped by newer ones, and never deletes
+    /// files that are being compacted.
+    ///
+    /// The C API does not report whether the deletion happened, so check
+    /// `get_live_files` afterwards if it matters.
+    pub fn delete_file(&self, name: &str) -> Result<(), Error> {
+        let cname = match CString::new(name.as_bytes()) {
+            Ok(c) => c,
+            Err(_) => {
+                return Err(Error::new(
+                    "Failed to convert file name to CString when deleting file.".to_owned(),
+                ))
+            }
+        };
+        unsafe {
+            ffi::rocksdb_delete_file(self.inner, cname.as_ptr());
+        }
+        Ok(())
+    }
+
+    /// Returns the SST files making up the current state of the database,
+    /// first flushing the memtables if `flush_memtable` is set so that the
+    /// files contain every write.
+    ///
+    /// A consistent external copy also needs the `CURRENT` file and the
+    /// `MANIFEST` it names, and the WAL files if the memtables were not
+    /// flushed. Call `disable_file_deletions` first so that none of the
+    /// files disappear while they are copied.
+    pub fn get_live_files(&self, flush_memtable: bool) -> Result<Vec<LiveFile>, Error> {
+        if flush_memtable {
+            try!(self.flush());
+        }
+        unsafe {
+            let files = ffi::rocksdb_livefiles(self.inner);
+            if files.is_null() {
+                return Err(Error::new("Could not get live files.".to_owned()));
+            }
+            let count = ffi::rocksdb_livefiles_count(files);
+            let mut result = Vec::with_capacity(count as usize);
+            for i in 0..count {
+                let name = CStr::from_ptr(ffi::rocksdb_livefiles_name(files, i));
+                let mut smallest_len: size_t = 0;
+                let smallest = ffi::rocksdb_livefiles_smallestkey(files, i, &mut smallest_len);
+                let mut largest_len: size_t = 0;
+                let largest = ffi::rocksdb_livefiles_largestkey(files, i, &mut largest_len);
+                result.push(LiveFile {
+                    name: name.to_string_lossy().into_owned(),
+                    level: ffi::rocksdb_livefiles_level(files, i) as i32,
+                    size: ffi::rocksdb_livefiles_size(files, i) as usize,
+                    smallest_key: slice::from_raw_parts(smallest as *const u8, smallest_len as usize)
+                        .to_vec(),
+                    largest_key: slice::from_raw_parts(largest as *const u8, largest_len as usize)
+                        .to_vec(),
+                });
+            }
+            ffi::rocksdb_livefiles_destroy(files);
+            Ok(result)
+        }
+    }
+}
+
+// Property names are short, so they are NUL-terminated in a stack buffer
+// rather than allocating a CString for every lookup.
+fn with_property_name<T, F>(name: &str, f: F) -> Result<T, Error>
+where
+    F: FnOnce(*const c_char) -> T,
+{
+    let bytes = name.as_bytes();
+    if bytes.contains(&0) {
+        return Err(Error::new(
+            format!("Failed to convert property name to CString: {}", name),
+        ));
+    }
+    let mut buf = [0u8; 128];
+    if bytes.len() < buf.len() {
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Ok(f(buf.as_ptr() as *const c_char))
+    } else {
+        let cname = CString::new(bytes).unwrap();
+        Ok(f(cname.as_ptr()))
+    }
+}
+
+unsafe fn property_string(value: *mut c_char) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    let s = String::from_utf8_lossy(CStr::from_ptr(value).to_bytes()).into_owned();
+    libc::free(value as *mut c_void);
+    Some(s)
+}
+
+// Parses a numeric property in place, without copying it into a String.
+unsafe fn property_int(value: *mut c_char) -> Option<u64> {
+    if value.is_null() {
+        return None;
+    }
+    let n = str::from_utf8(CStr::from_ptr(value).to_bytes())
+        .ok()
+        .and_then(|v| v.trim().parse().ok());
+    libc::free(value as *mut c_void);
+    n
 }
 
 impl WriteBatch {
+    /// Creates a batch from the serialized form returned by `data`.
+    pub fn from_data(data: &[u8]) -> WriteBatch {
+        unsafe {
+            WriteBatch {
+                inner: ffi::rocksdb_writebatch_create_from(
+                    data.as_ptr() as *const c_char,
+                    data.len() as size_t,
+                ),
+            }
+        }
+    }
+
+    /// Returns the serialized form of the batch, which can be stored or sent
+    /// elsewhere and turned back into a batch with `from_data`.
+    pub fn data(&self) -> &[u8] {
+        unsafe {
+            let mut size: size_t = 0;
+            let data = ffi::rocksdb_writebatch_data(self.inner, &mut size);
+            slice::from_raw_parts(data as *const u8, size as usize)
+        }
+    }
+
     pub fn len(&self) -> usize {
         unsafe { ffi::rocksdb_writebatch_count(self.inner) as usize }
     }
@@ -1105,7 +2303,7 @@ impl WriteBatch {
         }
     }
 
-    pub fn put_cf(&mut self, cf: ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    pub fn put_cf(&mut self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
         unsafe {
             ffi::rocksdb_writebatch_put_cf(
                 self.inner,
@@ -1132,7 +2330,7 @@ impl WriteBatch {
         }
     }
 
-    pub fn merge_cf(&mut self, cf: ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+    pub fn merge_cf(&mut self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
         unsafe {
             ffi::rocksdb_writebatch_merge_cf(
                 self.inner,
@@ -1160,7 +2358,7 @@ impl WriteBatch {
         }
     }
 
-    pub fn delete_cf(&mut self, cf: ColumnFamily, key: &[u8]) -> Result<(), Error> {
+    pub fn delete_cf(&mut self, cf: &ColumnFamily, key: &[u8]) -> Result<(), Error> {
         unsafe {
             ffi::rocksdb_writebatch_delete_cf(
                 self.inner,
@@ -1185,6 +2383,300 @@ impl Drop for WriteBatch {
     }
 }
 
+impl WriteBatchWithIndex {
+    /// Creates an empty batch, reserving `reserved_bytes` for its updates.
+    /// If `overwrite_keys` is true, a later update of a key replaces the
+    /// earlier ones in the index, which `iterator_with_base` requires.
+    pub fn new(reserved_bytes: usize, overwrite_keys: bool) -> WriteBatchWithIndex {
+        let overwrite_keys = overwrite_keys as c_uchar;
+        WriteBatchWithIndex {
+            inner: unsafe { ffi::rocksdb_writebatch_wi_create(reserved_bytes, overwrite_keys) },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { ffi::rocksdb_writebatch_wi_count(self.inner) as usize }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn put(&mut self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_put(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+            Ok(())
+        }
+    }
+
+    pub fn put_cf(&mut self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_put_cf(
+                self.inner,
+                cf.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+            Ok(())
+        }
+    }
+
+    pub fn merge(&mut self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_merge(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+            Ok(())
+        }
+    }
+
+    pub fn merge_cf(&mut self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_merge_cf(
+                self.inner,
+                cf.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            );
+            Ok(())
+        }
+    }
+
+    pub fn delete(&mut self, key: &[u8]) -> Result<(), Error> {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_delete(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+            );
+            Ok(())
+        }
+    }
+
+    pub fn delete_cf(&mut self, cf: &ColumnFamily, key: &[u8]) -> Result<(), Error> {
+        unsafe {
+            ffi::rocksdb_writebatch_wi_delete_cf(
+                self.inner,
+                cf.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+            );
+            Ok(())
+        }
+    }
+
+    /// Reads `key` from the updates of the batch alone. Returns an error of
+    /// kind `MergeInProgress` if the batch only holds merge operands for
+    /// the key, as they cannot be resolved without the database.
+    pub fn get_from_batch(&self, opts: &Options, key: &[u8]) -> Result<Option<DBVector>, Error> {
+        self.get_from_batch_raw(opts, None, key)
+    }
+
+    pub fn get_from_batch_cf(
+        &self,
+        opts: &Options,
+        cf: &ColumnFamily,
+        key: &[u8],
+    ) -> Result<Option<DBVector>, Error> {
+        self.get_from_batch_raw(opts, Some(cf), key)
+    }
+
+    fn get_from_batch_raw(
+        &self,
+        opts: &Options,
+        cf: Option<&ColumnFamily>,
+        key: &[u8],
+    ) -> Result<Option<DBVector>, Error> {
+        unsafe {
+            let mut val_len: size_t = 0;
+            let val = match cf {
+                Some(cf) => ffi_try!(ffi::rocksdb_writebatch_wi_get_from_batch_cf(
+                    self.inner,
+                    opts.inner,
+                    cf.inner,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                )),
+                None => ffi_try!(ffi::rocksdb_writebatch_wi_get_from_batch(
+                    self.inner,
+                    opts.inner,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                )),
+            } as *mut u8;
+            if val.is_null() {
+                Ok(None)
+            } else {
+                Ok(Some(DBVector::from_c(val, val_len)))
+            }
+        }
+    }
+
+    /// Reads `key` from the batch merged over the current state of `db`, as
+    /// if the batch had been written. Merge operands in the batch are
+    /// applied on top of the value in the database.
+    pub fn get_from_batch_and_db(&self, db: &DB, key: &[u8]) -> Result<Option<DBVector>, Error> {
+        self.get_merged(db, None, key, &db.default_readopts)
+    }
+
+    pub fn get_from_batch_and_db_cf(
+        &self,
+        db: &DB,
+        cf: &ColumnFamily,
+        key: &[u8],
+    ) -> Result<Option<DBVector>, Error> {
+        self.get_merged(db, Some(cf), key, &db.default_readopts)
+    }
+
+    fn get_merged(
+        &self,
+        db: &DB,
+        cf: Option<&ColumnFamily>,
+        key: &[u8],
+        readopts: &ReadOptions,
+    ) -> Result<Option<DBVector>, Error> {
+        enter_span!("rocksdb.get", key_len = key.len());
+        unsafe {
+            let mut val_len: size_t = 0;
+            let val = match cf {
+                Some(cf) => ffi_try!(ffi::rocksdb_writebatch_wi_get_from_batch_and_db_cf(
+                    self.inner,
+                    db.inner,
+                    readopts.inner,
+                    cf.inner,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                )),
+                None => ffi_try!(ffi::rocksdb_writebatch_wi_get_from_batch_and_db(
+                    self.inner,
+                    db.inner,
+                    readopts.inner,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                )),
+            } as *mut u8;
+            trace_event!(found = !val.is_null());
+            if val.is_null() {
+                Ok(None)
+            } else {
+                Ok(Some(DBVector::from_c(val, val_len)))
+            }
+        }
+    }
+
+    /// Iterates over the batch merged over the current state of `db`, as if
+    /// the batch had been written. The batch must have been created with
+    /// `overwrite_keys`, as the `Default` one is.
+    ///
+    /// The merged iterator does not resolve merge operands in the batch, so
+    /// batches holding merges should be read with `get_from_batch_and_db`
+    /// instead.
+    pub fn iterator_with_base(&self, db: &DB, mode: IteratorMode) -> BatchIterator {
+        self.iterator_merged(db, None, &db.default_readopts, mode)
+    }
+
+    pub fn iterator_with_base_cf(
+        &self,
+        db: &DB,
+        cf_handle: &ColumnFamily,
+        mode: IteratorMode,
+    ) -> Result<BatchIterator, Error> {
+        Ok(self.iterator_merged(db, Some(cf_handle), &db.default_readopts, mode))
+    }
+
+    fn iterator_merged(
+        &self,
+        db: &DB,
+        cf: Option<&ColumnFamily>,
+        readopts: &ReadOptions,
+        mode: IteratorMode,
+    ) -> BatchIterator {
+        // The merged iterator takes ownership of the base iterator.
+        let inner = unsafe {
+            match cf {
+                Some(cf) => {
+                    let base = ffi::rocksdb_create_iterator_cf(db.inner, readopts.inner, cf.inner);
+                    ffi::rocksdb_writebatch_wi_create_iterator_with_base_cf(
+                        self.inner,
+                        base,
+                        cf.inner,
+                    )
+                }
+                None => {
+                    let base = ffi::rocksdb_create_iterator(db.inner, readopts.inner);
+                    ffi::rocksdb_writebatch_wi_create_iterator_with_base(self.inner, base)
+                }
+            }
+        };
+        let mut iter = DBIterator {
+            raw: DBRawIterator {
+                inner: inner,
+                readopts: None,
+                _live: LiveIterator::new(db),
+            },
+            direction: Direction::Forward, // blown away by set_mode()
+            just_seeked: false,
+        };
+        iter.set_mode(mode);
+        BatchIterator {
+            inner: iter,
+            _batch: PhantomData,
+        }
+    }
+}
+
+impl Default for WriteBatchWithIndex {
+    fn default() -> WriteBatchWithIndex {
+        WriteBatchWithIndex::new(0, true)
+    }
+}
+
+impl Drop for WriteBatchWithIndex {
+    fn drop(&mut self) {
+        unsafe { ffi::rocksdb_writebatch_wi_destroy(self.inner) }
+    }
+}
+
+impl<'a> BatchIterator<'a> {
+    pub fn set_mode(&mut self, mode: IteratorMode) {
+        self.inner.set_mode(mode)
+    }
+
+    pub fn valid(&self) -> bool {
+        self.inner.valid()
+    }
+
+    pub fn status(&self) -> Result<(), Error> {
+        self.inner.status()
+    }
+}
+
+impl<'a> Iterator for BatchIterator<'a> {
+    type Item = KVBytes;
+
+    fn next(&mut self) -> Option<KVBytes> {
+        self.inner.next()
+    }
+}
+
 impl Drop for DB {
     fn drop(&mut self) {
         unsafe {
@@ -1225,7 +2717,10 @@ impl ReadOptions {
         }
     }
 
+    /// Sets the key iterators stop before. The key is copied into the
+    /// options.
     pub fn set_iterate_upper_bound(&mut self, key: &[u8]) {
+        let key = key.to_vec();
         unsafe {
             ffi::rocksdb_readoptions_set_iterate_upper_bound(
                 self.inner,
@@ -1233,6 +2728,41 @@ impl ReadOptions {
                 key.len() as size_t,
             );
         }
+        self._iterate_upper_bound = Some(key);
+    }
+
+    /// Limits iterators to the keys starting with `prefix`, by setting the
+    /// upper bound to `keys::prefix_successor(prefix)`. If the prefix has
+    /// no successor, `prefix_same_as_start` is set instead, which only
+    /// takes effect with a prefix extractor; all keys from such a prefix on
+    /// start with it anyway.
+    ///
+    /// Iterators still have to be positioned at the prefix, e.g. with
+    /// `IteratorMode::From(prefix, Direction::Forward)`.
+    ///
+    /// ```
+    /// use rocksdb::{DB, Direction, IteratorMode, ReadOptions};
+    ///
+    /// let db = DB::open_default("path/for/rocksdb/storage_iterate_prefix").unwrap();
+    /// db.put(b"user1", b"a").unwrap();
+    /// db.put(b"user2", b"b").unwrap();
+    /// db.put(b"users", b"c").unwrap();
+    ///
+    /// let mut readopts = ReadOptions::default();
+    /// readopts.set_iterate_prefix(b"user");
+    /// let iter = db.iterator_opt(IteratorMode::From(b"user", Direction::Forward), readopts);
+    /// assert_eq!(iter.count(), 3);
+    ///
+    /// let mut readopts = ReadOptions::default();
+    /// readopts.set_iterate_prefix(b"user1");
+    /// let iter = db.iterator_opt(IteratorMode::From(b"user1", Direction::Forward), readopts);
+    /// assert_eq!(iter.count(), 1);
+    /// ```
+    pub fn set_iterate_prefix(&mut self, prefix: &[u8]) {
+        match keys::prefix_successor(prefix) {
+            Some(end) => self.set_iterate_upper_bound(&end),
+            None => self.set_prefix_same_as_start(true),
+        }
     }
 
     pub fn set_prefix_same_as_start(&mut self, v: bool) {
@@ -1241,6 +2771,25 @@ impl ReadOptions {
         }
     }
 
+    /// Limits the number of deleted or overwritten entries an iterator may
+    /// skip in one seek or step before failing with `ErrorKind::Incomplete`,
+    /// instead of scanning through long runs of tombstones, e.g. at the
+    /// head of a queue. The iterator then becomes invalid, so scans should
+    /// check `status` before treating the end of the iteration as the end
+    /// of the range.
+    ///
+    /// Default: `0`, no limit
+    pub fn set_max_skippable_internal_keys(&mut self, num: u64) {
+        unsafe {
+            ffi::rocksdb_readoptions_set_max_skippable_internal_keys(self.inner, num);
+        }
+    }
+
+    /// If true, iterators ignore the prefix extractor and seek over the
+    /// whole key space, so scans return keys across prefixes even when
+    /// prefix blooms or a hash-based memtable or table format are in use.
+    ///
+    /// Default: `false`
     pub fn set_total_order_seek(&mut self, v:bool) {
         unsafe {
             ffi::rocksdb_readoptions_set_total_order_seek(self.inner, v as c_uchar)
@@ -1250,10 +2799,28 @@ impl ReadOptions {
 
 impl Default for ReadOptions {
     fn default() -> ReadOptions {
-        unsafe { ReadOptions { inner: ffi::rocksdb_readoptions_create() } }
+        unsafe {
+            ReadOptions {
+                inner: ffi::rocksdb_readoptions_create(),
+                _iterate_upper_bound: None,
+            }
+        }
     }
 }
 
+/// Metadata of a live SST file, as returned by `DB::get_live_files`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveFile {
+    /// The file name relative to the database directory, e.g. `/000012.sst`.
+    pub name: String,
+    /// The LSM level the file belongs to.
+    pub level: i32,
+    /// The file size in bytes.
+    pub size: usize,
+    pub smallest_key: Vec<u8>,
+    pub largest_key: Vec<u8>,
+}
+
 /// Vector of bytes stored in the database.
 ///
 /// This is a `C` allocated byte array and a length value.
@@ -1264,6 +2831,10 @@ pub struct DBVector {
     len: usize,
 }
 
+// The buffer is owned by the vector and never changes after creation.
+unsafe impl Send for DBVector {}
+unsafe impl Sync for DBVector {}
+
 impl Deref for DBVector {
     type Target = [u8];
 
@@ -1272,6 +2843,12 @@ impl Deref for DBVector {
     }
 }
 
+impl AsRef<[u8]> for DBVector {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
 impl Drop for DBVector {
     fn drop(&mut self) {
         unsafe {
@@ -1354,6 +2931,185 @@ fn errors_do_stuff() {
     }
 }
 
+#[test]
+fn error_kinds() {
+    let path = "_rust_rocksdb_error_kinds";
+    match DB::open(&Options::default(), path) {
+        Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidArgument),
+        Ok(_) => panic!("should fail"),
+    }
+    assert_eq!(Error::new("Resource busy: ".to_owned()).kind(), ErrorKind::Busy);
+    assert!(Error::new("Operation failed. Try again.: ".to_owned()).is_retryable());
+    assert_eq!(Error::new("Could not initialize database.".to_owned()).kind(), ErrorKind::Unknown);
+    let err = Error::with_kind(ErrorKind::Busy, "still busy".to_owned());
+    assert_eq!(err.kind(), ErrorKind::Busy);
+    assert_eq!(err.to_string(), "still busy");
+    {
+        // The lock of an open DB cannot be taken by destroy.
+        let _db = DB::open_default(path).unwrap();
+        let err = DB::destroy(&Options::default(), path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IOError);
+        assert!(!err.is_retryable());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn property_test() {
+    let path = "_rust_rocksdb_propertytest";
+    {
+        let db = DB::open_default(path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        assert!(db.property_value("rocksdb.stats").unwrap().is_some());
+        assert!(db.property_int_value("rocksdb.estimate-num-keys").unwrap().is_some());
+        assert!(db.property_value("rocksdb.no-such-property").unwrap().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn identity_test() {
+    let path = "_rust_rocksdb_identitytest";
+    {
+        let identity = {
+            let db = DB::open_default(path).unwrap();
+            db.get_db_identity().unwrap()
+        };
+        assert!(!identity.is_empty());
+        let db = DB::open_default(path).unwrap();
+        assert_eq!(db.get_db_identity().unwrap(), identity);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+    assert!(version().starts_with("5."));
+}
+
+#[test]
+fn live_files_test() {
+    let path = "_rust_rocksdb_livefilestest";
+    {
+        let db = DB::open_default(path).unwrap();
+        db.put(b"a", b"1").unwrap();
+        db.put(b"z", b"2").unwrap();
+        assert!(db.get_live_files(false).unwrap().is_empty());
+
+        db.disable_file_deletions().unwrap();
+        let files = db.get_live_files(true).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].level, 0);
+        assert_eq!(files[0].smallest_key, b"a".to_vec());
+        assert_eq!(files[0].largest_key, b"z".to_vec());
+        assert!(db.path().join(&files[0].name[1..]).exists());
+        db.enable_file_deletions(true).unwrap();
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn delete_file_test() {
+    let path = "_rust_rocksdb_deletefiletest";
+    {
+        let db = DB::open_default(path).unwrap();
+        db.put(b"a", b"1").unwrap();
+        db.flush().unwrap();
+        db.compact_range(None, None);
+
+        let files = db.get_live_files(false).unwrap();
+        assert_eq!(files.len(), 1);
+        db.delete_file(&files[0].name).unwrap();
+        assert!(db.get_live_files(false).unwrap().is_empty());
+        assert!(db.get(b"a").unwrap().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn multi_get_test() {
+    let path = "_rust_rocksdb_multigettest";
+    {
+        let mut db = DB::open_default(path).unwrap();
+        db.create_cf("cf1", &Options::default()).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.put(b"k2", b"v2").unwrap();
+        db.put_cf(db.cf_handle("cf1").unwrap(), b"k1", b"cf1v1").unwrap();
+
+        let values: Vec<_> = db.multi_get(vec![&b"k2"[..], b"k3", b"k1"])
+            .into_iter()
+            .map(|v| v.unwrap().map(|v| v.to_vec()))
+            .collect();
+        assert_eq!(values, vec![Some(b"v2".to_vec()), None, Some(b"v1".to_vec())]);
+
+        let cf1 = db.cf_handle("cf1").unwrap();
+        let values: Vec<_> = db.multi_get_cf(vec![(cf1, &b"k1"[..]), (cf1, b"k2")])
+            .into_iter()
+            .map(|v| v.unwrap().map(|v| v.to_vec()))
+            .collect();
+        assert_eq!(values, vec![Some(b"cf1v1".to_vec()), None]);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn update_test() {
+    use std::sync::Arc;
+    use std::thread;
+
+    let path = "_rust_rocksdb_updatetest";
+    {
+        let db = Arc::new(DB::open_default(path).unwrap());
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                thread::spawn(move || for _ in 0..100 {
+                    db.update(b"counter", |v| {
+                        let n = v.map_or(0, |v| str::from_utf8(v).unwrap().parse().unwrap());
+                        Some((n + 1u32).to_string().into_bytes())
+                    }).unwrap();
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(&*db.get(b"counter").unwrap().unwrap(), b"400");
+
+        assert!(!db.compare_and_swap(b"counter", Some(b"399"), Some(b"0")).unwrap());
+        assert!(db.compare_and_swap(b"counter", Some(b"400"), None).unwrap());
+        assert!(db.get(b"counter").unwrap().is_none());
+        assert_eq!(db.update(b"counter", |_| None).unwrap(), None);
+
+        // `f` may update another key without deadlocking on a shared stripe.
+        for i in 0..128u32 {
+            let other = format!("other{}", i).into_bytes();
+            db.update(b"outer", |_| {
+                db.update(&other, |_| Some(b"v".to_vec())).unwrap();
+                Some(b"v".to_vec())
+            }).unwrap();
+        }
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn get_into_test() {
+    let path = "_rust_rocksdb_getintotest";
+    {
+        let mut db = DB::open_default(path).unwrap();
+        db.create_cf("cf1", &Options::default()).unwrap();
+        db.put(b"k1", b"value1").unwrap();
+        db.put_cf(db.cf_handle("cf1").unwrap(), b"k1", b"v1").unwrap();
+
+        let mut buf = b"stale".to_vec();
+        assert_eq!(db.get_into(b"k1", &mut buf).unwrap(), Some(6));
+        assert_eq!(buf, b"value1");
+        let cf1 = db.cf_handle("cf1").unwrap();
+        assert_eq!(db.get_cf_into(cf1, b"k1", &mut buf).unwrap(), Some(2));
+        assert_eq!(buf, b"v1");
+        assert_eq!(db.get_into(b"k2", &mut buf).unwrap(), None);
+        assert!(buf.is_empty());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
 #[test]
 fn writebatch_works() {
     let path = "_rust_rocksdb_writebacktest";
@@ -1430,6 +3186,14 @@ fn snapshot_test() {
 
         assert!(db.get(b"k2").unwrap().is_some());
         assert!(snap.get(b"k2").unwrap().is_none());
+
+        db.put(b"k0", b"v0000").unwrap();
+        let mut readopts = ReadOptions::default();
+        readopts.set_iterate_upper_bound(b"k2");
+        let keys: Vec<_> = snap.iterator_opt(IteratorMode::Start, readopts)
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec![b"k1".to_vec().into_boxed_slice()]);
     }
     let opts = Options::default();
     assert!(DB::destroy(&opts, path).is_ok());
@@ -14,23 +14,28 @@
 
 use std::ffi::{CStr, CString};
 use std::mem;
+use std::path::Path;
 
-use libc::{self, c_int, c_uchar, c_uint, c_void, size_t, uint64_t};
+use libc::{self, c_int, c_uchar, c_uint, c_void, int32_t, size_t, uint32_t, uint64_t};
 
 use ffi;
-use {BlockBasedOptions, DBCompactionStyle, DBCompressionType, DBRecoveryMode,
-	 Options, WriteOptions};
+use {BlockBasedIndexType, BlockBasedOptions, CompressionOptions, CuckooTableOptions,
+	 DBCompactionStyle, DBCompressionType, DBRecoveryMode, Env, Error, FifoCompactOptions,
+	 MemtableFactory, Options, PlainTableOptions, SliceTransform, StorageClass,
+	 UniversalCompactOptions, UniversalCompactionStopStyle, WriteOptions};
 use compaction_filter::{self, CompactionFilterCallback, CompactionFilterFn, filter_callback};
-use comparator::{self, ComparatorCallback, CompareFn};
-use merge_operator::{self, MergeFn, MergeOperatorCallback, full_merge_callback,
+use comparator::{self, ComparatorCallback, ComparatorFn, CompareFn};
+use merge_operator::{self, MergeFn, MergeOperatorCallback, MergeOperatorFn, full_merge_callback,
                      partial_merge_callback};
-use slice_transform::SliceTransform;
+use statistics::Statistics;
 
 pub fn new_cache(capacity: size_t) -> *mut ffi::rocksdb_cache_t {
     unsafe { ffi::rocksdb_cache_create_lru(capacity) }
 }
 
 unsafe impl Send for Options {}
+unsafe impl Send for WriteOptions {}
+unsafe impl Sync for WriteOptions {}
 
 impl Drop for Options {
     fn drop(&mut self) {
@@ -48,6 +53,30 @@ impl Drop for BlockBasedOptions {
     }
 }
 
+impl Drop for CuckooTableOptions {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_cuckoo_options_destroy(self.inner);
+        }
+    }
+}
+
+impl Drop for UniversalCompactOptions {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_universal_compaction_options_destroy(self.inner);
+        }
+    }
+}
+
+impl Drop for FifoCompactOptions {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_fifo_compaction_options_destroy(self.inner);
+        }
+    }
+}
+
 impl Drop for WriteOptions {
     fn drop(&mut self) {
         unsafe {
@@ -72,6 +101,27 @@ impl BlockBasedOptions {
         }
     }
 
+    /// Sets a bloom filter policy to reduce the number of disk reads needed
+    /// for point lookups of keys that do not exist.
+    ///
+    /// `bits_per_key` controls the false positive rate; `10` yields roughly a
+    /// 1% false positive rate. If `block_based` is true, the deprecated
+    /// per-block filter format is used, otherwise a single full filter is
+    /// built for each SST file, which is faster.
+    ///
+    /// Default: no filter
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{BlockBasedOptions, Options};
+    ///
+    /// let mut block_opts = BlockBasedOptions::default();
+    /// block_opts.set_bloom_filter(10, false);
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_block_based_table_factory(&block_opts);
+    /// ```
     pub fn set_bloom_filter(&mut self, bits_per_key: c_int, block_based: bool) {
         unsafe {
             let bloom = if block_based {
@@ -89,6 +139,250 @@ impl BlockBasedOptions {
             ffi::rocksdb_block_based_options_set_cache_index_and_filter_blocks(self.inner, v as u8);
         }
     }
+
+    /// Sets the index type used for this table.
+    ///
+    /// `BlockBasedIndexType::TwoLevelIndexSearch` partitions the index into
+    /// many small blocks plus a top-level index over them, so only the
+    /// partitions that are actually needed have to be loaded into memory.
+    /// Filters stay monolithic: partitioning them as well needs a newer
+    /// RocksDB, as the C API of the bundled 5.11 release has no
+    /// `partition_filters` or `metadata_block_size` setting.
+    ///
+    /// Default: `BlockBasedIndexType::BinarySearch`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{BlockBasedOptions, BlockBasedIndexType};
+    ///
+    /// let mut opts = BlockBasedOptions::default();
+    /// opts.set_index_type(BlockBasedIndexType::TwoLevelIndexSearch);
+    /// ```
+    pub fn set_index_type(&mut self, index_type: BlockBasedIndexType) {
+        unsafe {
+            ffi::rocksdb_block_based_options_set_index_type(self.inner, index_type as c_int);
+        }
+    }
+
+    /// If `cache_index_and_filter_blocks` is enabled, keep the filter and index
+    /// blocks of level-0 files pinned in the block cache.
+    ///
+    /// Default: `false`
+    pub fn set_pin_l0_filter_and_index_blocks_in_cache(&mut self, v: bool) {
+        unsafe {
+            ffi::rocksdb_block_based_options_set_pin_l0_filter_and_index_blocks_in_cache(
+                self.inner,
+                v as c_uchar,
+            );
+        }
+    }
+
+    /// If true, place whole keys in the filter (not just prefixes).
+    /// This must generally be true for point lookups to benefit from the
+    /// filter. Turning it off while a prefix extractor is configured
+    /// makes the filter smaller but only useful for prefix seeks.
+    ///
+    /// Default: `true`
+    pub fn set_whole_key_filtering(&mut self, v: bool) {
+        unsafe {
+            ffi::rocksdb_block_based_options_set_whole_key_filtering(self.inner, v as c_uchar);
+        }
+    }
+}
+
+impl CuckooTableOptions {
+    /// Determines the utilization of hash tables. Smaller values
+    /// result in larger hash tables with fewer collisions.
+    ///
+    /// Default: `0.9`
+    pub fn set_hash_ratio(&mut self, ratio: f64) {
+        unsafe {
+            ffi::rocksdb_cuckoo_options_set_hash_ratio(self.inner, ratio);
+        }
+    }
+
+    /// A property used by the builder to determine the depth to go to
+    /// search for a path to displace elements in case of collision.
+    /// Higher values result in more efficient hash tables with fewer
+    /// lookups but take more time to build.
+    ///
+    /// Default: `100`
+    pub fn set_max_search_depth(&mut self, depth: u32) {
+        unsafe {
+            ffi::rocksdb_cuckoo_options_set_max_search_depth(self.inner, depth as uint32_t);
+        }
+    }
+
+    /// In case of collision while inserting, the builder attempts to
+    /// insert in the next `cuckoo_block_size` locations before skipping
+    /// over to the next cuckoo hash function. This makes lookups more
+    /// cache friendly in case of collisions.
+    ///
+    /// Default: `5`
+    pub fn set_cuckoo_block_size(&mut self, size: u32) {
+        unsafe {
+            ffi::rocksdb_cuckoo_options_set_cuckoo_block_size(self.inner, size as uint32_t);
+        }
+    }
+
+    /// If this option is enabled, the user key is treated as `u64` and its
+    /// value is used as the hash value directly. This option changes the
+    /// builder's behavior. Readers ignore this option.
+    ///
+    /// Default: `false`
+    pub fn set_identity_as_first_hash(&mut self, v: bool) {
+        unsafe {
+            ffi::rocksdb_cuckoo_options_set_identity_as_first_hash(self.inner, v as c_uchar);
+        }
+    }
+
+    /// If this option is set to true, the module is used during hash
+    /// calculation. This often yields better space efficiency at the cost
+    /// of performance. If this option is set to false, the number of
+    /// entries in the table is constrained to be a power of two, and a
+    /// bit-and is used to calculate the hash, which is faster in general.
+    ///
+    /// Default: `true`
+    pub fn set_use_module_hash(&mut self, v: bool) {
+        unsafe {
+            ffi::rocksdb_cuckoo_options_set_use_module_hash(self.inner, v as c_uchar);
+        }
+    }
+}
+
+impl UniversalCompactOptions {
+    /// Percentage flexibility while comparing file size. If the candidate
+    /// file(s) size is 1% smaller than the next file's size, then include
+    /// the next file into this candidate set.
+    ///
+    /// Default: `1`
+    pub fn set_size_ratio(&mut self, ratio: i32) {
+        unsafe {
+            ffi::rocksdb_universal_compaction_options_set_size_ratio(self.inner, ratio as c_int);
+        }
+    }
+
+    /// The minimum number of files in a single compaction run.
+    ///
+    /// Default: `2`
+    pub fn set_min_merge_width(&mut self, num: i32) {
+        unsafe {
+            ffi::rocksdb_universal_compaction_options_set_min_merge_width(self.inner, num as c_int);
+        }
+    }
+
+    /// The maximum number of files in a single compaction run.
+    ///
+    /// Default: unlimited
+    pub fn set_max_merge_width(&mut self, num: i32) {
+        unsafe {
+            ffi::rocksdb_universal_compaction_options_set_max_merge_width(self.inner, num as c_int);
+        }
+    }
+
+    /// The size amplification is defined as the amount (in percentage) of
+    /// additional storage needed to store a single byte of data in the
+    /// database. For example, a size amplification of 2% means that a
+    /// database that contains 100 bytes of user data may occupy up to 102
+    /// bytes of physical storage.
+    ///
+    /// Default: `200`
+    pub fn set_max_size_amplification_percent(&mut self, percent: i32) {
+        unsafe {
+            ffi::rocksdb_universal_compaction_options_set_max_size_amplification_percent(
+                self.inner,
+                percent as c_int,
+            );
+        }
+    }
+
+    /// If this option is set to be -1, all the output files will follow the
+    /// compression type specified. Otherwise, older data that makes up this
+    /// percentage of the total size is compressed and newer data is not.
+    ///
+    /// Default: `-1`
+    pub fn set_compression_size_percent(&mut self, percent: i32) {
+        unsafe {
+            ffi::rocksdb_universal_compaction_options_set_compression_size_percent(
+                self.inner,
+                percent as c_int,
+            );
+        }
+    }
+
+    /// The algorithm used to stop picking files into a single compaction run.
+    ///
+    /// Default: `UniversalCompactionStopStyle::Total`
+    pub fn set_stop_style(&mut self, style: UniversalCompactionStopStyle) {
+        unsafe {
+            ffi::rocksdb_universal_compaction_options_set_stop_style(self.inner, style as c_int);
+        }
+    }
+}
+
+impl Default for UniversalCompactOptions {
+    fn default() -> UniversalCompactOptions {
+        let opts = unsafe { ffi::rocksdb_universal_compaction_options_create() };
+        if opts.is_null() {
+            panic!("Could not create RocksDB universal compaction options");
+        }
+        UniversalCompactOptions { inner: opts }
+    }
+}
+
+impl FifoCompactOptions {
+    /// Once the total size of all SST files exceeds this many bytes, the
+    /// oldest file is deleted.
+    ///
+    /// Default: `1GB`
+    pub fn set_max_table_files_size(&mut self, nbytes: u64) {
+        unsafe {
+            ffi::rocksdb_fifo_compaction_options_set_max_table_files_size(self.inner, nbytes);
+        }
+    }
+}
+
+impl Default for FifoCompactOptions {
+    fn default() -> FifoCompactOptions {
+        let opts = unsafe { ffi::rocksdb_fifo_compaction_options_create() };
+        if opts.is_null() {
+            panic!("Could not create RocksDB FIFO compaction options");
+        }
+        FifoCompactOptions { inner: opts }
+    }
+}
+
+impl Default for CuckooTableOptions {
+    fn default() -> CuckooTableOptions {
+        let opts = unsafe { ffi::rocksdb_cuckoo_options_create() };
+        if opts.is_null() {
+            panic!("Could not create RocksDB cuckoo table options");
+        }
+        CuckooTableOptions { inner: opts }
+    }
+}
+
+impl Default for PlainTableOptions {
+    fn default() -> PlainTableOptions {
+        PlainTableOptions {
+            user_key_length: 0,
+            bloom_bits_per_key: 10,
+            hash_table_ratio: 0.75,
+            index_sparseness: 16,
+        }
+    }
+}
+
+impl Default for CompressionOptions {
+    fn default() -> CompressionOptions {
+        CompressionOptions {
+            window_bits: -14,
+            level: -1,
+            strategy: 0,
+            max_dict_bytes: 0,
+        }
+    }
 }
 
 impl Default for BlockBasedOptions {
@@ -102,6 +396,55 @@ impl Default for BlockBasedOptions {
 }
 
 impl Options {
+    /// Creates a copy of `base` with the overrides in `opts_str` applied.
+    /// The string uses the same `name=value;` syntax as RocksDB's OPTIONS
+    /// files and tools, and may contain both database and column family
+    /// options. Sizes may be given with a `k`, `m`, `g` or `t` suffix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let opts = Options::from_string(
+    ///     &Options::default(),
+    ///     "create_if_missing=true;write_buffer_size=64m;max_write_buffer_number=4",
+    /// ).unwrap();
+    /// ```
+    pub fn from_string(base: &Options, opts_str: &str) -> Result<Options, Error> {
+        let c_opts = match CString::new(opts_str.as_bytes()) {
+            Ok(c) => c,
+            Err(_) => {
+                return Err(Error::new(
+                    "Failed to convert options string to CString".to_owned(),
+                ))
+            }
+        };
+        let mut opts = Options::default();
+        opts.env = base.env.clone();
+        unsafe {
+            ffi_try!(ffi::rocksdb_get_options_from_string(
+                base.inner,
+                c_opts.as_ptr(),
+                opts.inner,
+            ));
+        }
+        Ok(opts)
+    }
+
+    /// Copies every option into a new `Options`, which can then be changed
+    /// independently, e.g. to derive the options of a column family from a
+    /// base configuration. As in C++, the objects the options point to, such
+    /// as the comparator, merge operator, table factory and statistics, are
+    /// shared with the copy rather than duplicated.
+    ///
+    /// The bundled RocksDB has no direct way to copy options, so the copy
+    /// goes through `from_string` with no overrides and fails if RocksDB
+    /// cannot parse the options back.
+    pub fn try_clone(&self) -> Result<Options, Error> {
+        Options::from_string(self, "")
+    }
+
     /// By default, RocksDB uses only one background thread for flush and
     /// compaction. Calling this function will set it up such that total of
     /// `total_threads` is used. Good value for `total_threads` is the number of
@@ -122,6 +465,18 @@ impl Options {
         }
     }
 
+    /// Configures the options for level style compaction, sizing the
+    /// memtables and the first levels from `memtable_memory_budget` bytes.
+    /// Good for workloads with a lot of writes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.optimize_level_style_compaction(512 * 1024 * 1024);
+    /// ```
     pub fn optimize_level_style_compaction(&mut self, memtable_memory_budget: usize) {
         unsafe {
             ffi::rocksdb_options_optimize_level_style_compaction(
@@ -131,6 +486,120 @@ impl Options {
         }
     }
 
+    /// Configures the options for universal style compaction, sizing the
+    /// memtables from `memtable_memory_budget` bytes. Universal compaction
+    /// lowers write amplification at the cost of more space.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.optimize_universal_style_compaction(512 * 1024 * 1024);
+    /// ```
+    pub fn optimize_universal_style_compaction(&mut self, memtable_memory_budget: usize) {
+        unsafe {
+            ffi::rocksdb_options_optimize_universal_style_compaction(
+                self.inner,
+                memtable_memory_budget as uint64_t,
+            );
+        }
+    }
+
+    /// Returns options sized for a machine with `total_memory` bytes of RAM
+    /// and `cpu_count` cores dedicated to the database, stored on `storage`.
+    ///
+    /// A quarter of the memory goes to the block cache and an eighth to the
+    /// memtables of all column families; background jobs use every core.
+    /// SST files get a bloom filter.
+    ///
+    /// Snappy is the only compression the bundled RocksDB is built with, so
+    /// it is used for every compressed level, the bottommost one included.
+    /// On an SSD, L0 and L1 are left uncompressed to keep flushes and
+    /// compactions into L1 cheap. On an HDD, where reads and writes cost
+    /// more than compressing, every level is compressed.
+    ///
+    /// The result is a starting point: individual options can still be
+    /// overridden afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{Options, StorageClass};
+    ///
+    /// let mut opts = Options::auto_tuned(8 << 30, 4, StorageClass::Ssd);
+    /// opts.create_if_missing(true);
+    /// ```
+    pub fn auto_tuned(total_memory: usize, cpu_count: usize, storage: StorageClass) -> Options {
+        let cache_size = total_memory / 4;
+        let memtable_budget = total_memory / 8;
+        let cpus = cpu_count.max(1).min(c_int::max_value() as usize) as c_int;
+
+        let mut opts = Options::default();
+        opts.optimize_level_style_compaction(memtable_budget);
+        opts.set_db_write_buffer_size(memtable_budget);
+        opts.set_max_background_jobs(cpus.max(2));
+        opts.set_level_compaction_dynamic_level_bytes(true);
+        opts.set_bytes_per_sync(1024 * 1024);
+
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_lru_cache(cache_size);
+        block_opts.set_bloom_filter(10, false);
+        match storage {
+            StorageClass::Ssd => {
+                block_opts.set_block_size(16 * 1024);
+                opts.set_compression_per_level(&[
+                    DBCompressionType::None,
+                    DBCompressionType::None,
+                    DBCompressionType::Snappy,
+                    DBCompressionType::Snappy,
+                    DBCompressionType::Snappy,
+                    DBCompressionType::Snappy,
+                    DBCompressionType::Snappy,
+                ]);
+            }
+            StorageClass::Hdd => {
+                block_opts.set_block_size(64 * 1024);
+                opts.set_compression_per_level(&[DBCompressionType::Snappy; 7]);
+                opts.set_compaction_readahead_size(2 * 1024 * 1024);
+                opts.set_target_file_size_base(256 * 1024 * 1024);
+            }
+        }
+        opts.set_block_based_table_factory(&block_opts);
+        opts
+    }
+
+    /// Configures the options for loading a large amount of data into an
+    /// empty database as fast as possible: automatic compactions are
+    /// disabled, and memtables are large and flushed in parallel.
+    ///
+    /// When the load is done, call `DB::finish_bulk_load` to compact the
+    /// loaded files, then reopen the database without these options so that
+    /// automatic compactions resume. Reads during the load may be slow.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{DB, Options};
+    ///
+    /// let path = "path/for/rocksdb/storage_bulk_load";
+    /// let mut opts = Options::default();
+    /// opts.create_if_missing(true);
+    /// opts.prepare_for_bulk_load();
+    /// {
+    ///     let db = DB::open(&opts, path).unwrap();
+    ///     db.put(b"k", b"v").unwrap();
+    ///     db.finish_bulk_load();
+    /// }
+    /// let db = DB::open_default(path).unwrap();
+    /// ```
+    pub fn prepare_for_bulk_load(&mut self) {
+        unsafe {
+            ffi::rocksdb_options_prepare_for_bulk_load(self.inner);
+        }
+    }
+
     /// If true, the database will be created if it is missing.
     ///
     /// Default: `false`
@@ -168,6 +637,24 @@ impl Options {
         }
     }
 
+    /// If true, opening fails if the database already exists.
+    ///
+    /// Default: `false`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_error_if_exists(true);
+    /// ```
+    pub fn set_error_if_exists(&mut self, enabled: bool) {
+        unsafe {
+            ffi::rocksdb_options_set_error_if_exists(self.inner, enabled as c_uchar);
+        }
+    }
+
     /// Sets the compression algorithm that will be used for the bottommost level that
     /// contain files. If level-compaction is used, this option will only affect
     /// levels after base level.
@@ -188,6 +675,33 @@ impl Options {
         }
     }
 
+    /// Sets the parameters passed to the compression library, such as the
+    /// zstd or zlib compression level.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{CompressionOptions, DBCompressionType, Options};
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_compression_type(DBCompressionType::Zlib);
+    /// opts.set_compression_options(&CompressionOptions {
+    ///     level: 9,
+    ///     ..CompressionOptions::default()
+    /// });
+    /// ```
+    pub fn set_compression_options(&mut self, options: &CompressionOptions) {
+        unsafe {
+            ffi::rocksdb_options_set_compression_options(
+                self.inner,
+                options.window_bits as c_int,
+                options.level as c_int,
+                options.strategy as c_int,
+                options.max_dict_bytes as c_int,
+            );
+        }
+    }
+
     /// Different levels can have different compression policies. There
     /// are cases where most lower levels would like to use quick compression
     /// algorithms while the higher levels (which have more data) use
@@ -221,29 +735,89 @@ impl Options {
         }
     }
 
+    /// Sets the merge operator used to combine `merge` operands with the
+    /// existing value. If `partial_merge_fn` is `None`, `full_merge_fn` is
+    /// also used to combine operands with each other.
+    ///
+    /// If a merge function panics the merge fails, and reads of the key
+    /// return a `Corruption` error.
     pub fn set_merge_operator(&mut self, name: &str,
                               full_merge_fn: MergeFn,
                               partial_merge_fn: Option<MergeFn>) {
+        self.set_merge_operator_fns(
+            name,
+            full_merge_fn,
+            partial_merge_fn.unwrap_or(full_merge_fn),
+        );
+    }
 
+    /// Like `set_merge_operator`, but takes any function or closure. The
+    /// callbacks are monomorphized for `F` and `PF`, so merges do not go
+    /// through a function pointer.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    /// use rocksdb::merge_operator::MergeOperands;
+    ///
+    /// fn concat(_: &[u8], existing: Option<&[u8]>, operands: &mut MergeOperands) -> Option<Vec<u8>> {
+    ///     let mut result = existing.map(|v| v.to_vec()).unwrap_or_default();
+    ///     for op in operands {
+    ///         result.extend_from_slice(op);
+    ///     }
+    ///     Some(result)
+    /// }
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_merge_operator_fns("concat", concat, concat);
+    /// ```
+    pub fn set_merge_operator_fns<F, PF>(&mut self, name: &str, full_merge_fn: F, partial_merge_fn: PF)
+    where
+        F: MergeOperatorFn,
+        PF: MergeOperatorFn,
+    {
         let cb = Box::new(MergeOperatorCallback {
             name: CString::new(name.as_bytes()).unwrap(),
             full_merge_fn: full_merge_fn,
-            partial_merge_fn: partial_merge_fn.unwrap_or(full_merge_fn),
+            partial_merge_fn: partial_merge_fn,
         });
 
         unsafe {
             let mo = ffi::rocksdb_mergeoperator_create(
                 mem::transmute(cb),
-                Some(merge_operator::destructor_callback),
-                Some(full_merge_callback),
-                Some(partial_merge_callback),
+                Some(merge_operator::destructor_callback::<F, PF>),
+                Some(full_merge_callback::<F, PF>),
+                Some(partial_merge_callback::<F, PF>),
                 None,
-                Some(merge_operator::name_callback),
+                Some(merge_operator::name_callback::<F, PF>),
             );
             ffi::rocksdb_options_set_merge_operator(self.inner, mo);
         }
     }
 
+    /// Sets a merge operator appending every `merge` operand to the
+    /// existing value, separated by `delimiter`, as done by `DB::append`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{DB, Options};
+    ///
+    /// let mut opts = Options::default();
+    /// opts.create_if_missing(true);
+    /// opts.set_append_merge_operator(b"\n");
+    ///
+    /// let db = DB::open(&opts, "path/for/rocksdb/storage_append").unwrap();
+    /// db.append(b"log", b"started").unwrap();
+    /// db.append(b"log", b"stopped").unwrap();
+    /// assert_eq!(&*db.get(b"log").unwrap().unwrap(), b"started\nstopped");
+    /// ```
+    pub fn set_append_merge_operator(&mut self, delimiter: &[u8]) {
+        let append = merge_operator::append(delimiter);
+        self.set_merge_operator_fns("rust-rocksdb.append", append.clone(), append);
+    }
+
     #[deprecated(since = "0.5.0",
                  note = "add_merge_operator has been renamed to set_merge_operator")]
     pub fn add_merge_operator(&mut self, name: &str, merge_fn: MergeFn) {
@@ -260,6 +834,8 @@ impl Options {
     ///
     /// If multi-threaded compaction is used, `filter_fn` may be called multiple times
     /// simultaneously.
+    ///
+    /// If `filter_fn` panics the entry is kept.
     pub fn set_compaction_filter<F>(&mut self, name: &str, filter_fn: F)
     where
         F: CompactionFilterFn + Send + 'static,
@@ -286,7 +862,14 @@ impl Options {
     /// The client must ensure that the comparator supplied here has the same
     /// name and orders keys *exactly* the same as the comparator provided to
     /// previous open calls on the same DB.
-    pub fn set_comparator(&mut self, name: &str, compare_fn: CompareFn) {
+    ///
+    /// If `compare_fn` panics the process is aborted, as no ordering can be
+    /// assumed without corrupting the database.
+    ///
+    /// `compare_fn` may be a function or a closure; the callbacks are
+    /// monomorphized for its type. It is called concurrently from reads,
+    /// writes and compactions, so it must be `Send + Sync`.
+    pub fn set_comparator<F: ComparatorFn>(&mut self, name: &str, compare_fn: F) {
         let cb = Box::new(ComparatorCallback {
             name: CString::new(name.as_bytes()).unwrap(),
             f: compare_fn,
@@ -295,20 +878,47 @@ impl Options {
         unsafe {
             let cmp = ffi::rocksdb_comparator_create(
                 mem::transmute(cb),
-                Some(comparator::destructor_callback),
-                Some(comparator::compare_callback),
-                Some(comparator::name_callback),
+                Some(comparator::destructor_callback::<F>),
+                Some(comparator::compare_callback::<F>),
+                Some(comparator::name_callback::<F>),
             );
             ffi::rocksdb_options_set_comparator(self.inner, cmp);
         }
     }
 
+    /// Sets the transform used to extract key prefixes for prefix blooms
+    /// and prefix seeks. The options take ownership of the transform.
     pub fn set_prefix_extractor(&mut self, prefix_extractor: SliceTransform) {
         unsafe {
             ffi::rocksdb_options_set_prefix_extractor(
                 self.inner, prefix_extractor.inner
             )
         }
+        mem::forget(prefix_extractor);
+    }
+
+    /// If a prefix extractor is set and this is non-zero, a prefix bloom
+    /// filter of `write_buffer_size * ratio` bytes is built for each
+    /// memtable, so prefix seeks and lookups can skip memtables that do not
+    /// contain the prefix. Values above `0.25` are sanitized to `0.25`.
+    ///
+    /// Default: `0` (disabled)
+    ///
+    /// Dynamically changeable through SetOptions() API
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{Options, SliceTransform};
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(3));
+    /// opts.set_memtable_prefix_bloom_ratio(0.1);
+    /// ```
+    pub fn set_memtable_prefix_bloom_ratio(&mut self, ratio: f64) {
+        unsafe {
+            ffi::rocksdb_options_set_memtable_prefix_bloom_size_ratio(self.inner, ratio);
+        }
     }
 
     #[deprecated(since = "0.5.0", note = "add_comparator has been renamed to set_comparator")]
@@ -316,6 +926,17 @@ impl Options {
         self.set_comparator(name, compare_fn);
     }
 
+    /// Tunes the options for workloads that only do point lookups and never
+    /// iterate, using a block cache of `cache_size` megabytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.optimize_for_point_lookup(64);
+    /// ```
     pub fn optimize_for_point_lookup(&mut self, cache_size: u64) {
         unsafe {
             ffi::rocksdb_options_optimize_for_point_lookup(self.inner, cache_size);
@@ -414,6 +1035,34 @@ impl Options {
         }
     }
 
+    /// If true, threads synchronizing with the write batch group leader
+    /// wait for up to `write_thread_max_yield_usec` before blocking on a
+    /// mutex, which improves throughput for concurrent writers, especially
+    /// with `allow_concurrent_memtable_write`.
+    ///
+    /// Default: true
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{DB, Options};
+    ///
+    /// let mut opts = Options::default();
+    /// opts.create_if_missing(true);
+    /// opts.set_enable_write_thread_adaptive_yield(false);
+    ///
+    /// let db = DB::open(&opts, "path/for/rocksdb/storage_adaptive_yield").unwrap();
+    /// db.put(b"k1", b"v1").unwrap();
+    /// ```
+    pub fn set_enable_write_thread_adaptive_yield(&mut self, enabled: bool) {
+        unsafe {
+            ffi::rocksdb_options_set_enable_write_thread_adaptive_yield(
+                self.inner,
+                enabled as c_uchar,
+            )
+        }
+    }
+
     /// Enable direct I/O mode for reading
     /// they may or may not improve performance depending on the use case
     ///
@@ -446,6 +1095,10 @@ impl Options {
     /// be used. Memory mapped files are not impacted by these parameters.
     /// they may or may not improve performance depending on the use case
     ///
+    /// Without the page cache, compaction inputs are no longer read ahead
+    /// by the OS, so a `set_compaction_readahead_size` of at least 2MB is
+    /// recommended alongside this option.
+    ///
     /// Default: false
     ///
     /// # Example
@@ -455,6 +1108,7 @@ impl Options {
     ///
     /// let mut opts = Options::default();
     /// opts.set_use_direct_io_for_flush_and_compaction(true);
+    /// opts.set_compaction_readahead_size(2 * 1024 * 1024);
     /// ```
     pub fn set_use_direct_io_for_flush_and_compaction(&mut self, enabled: bool) {
         unsafe {
@@ -465,6 +1119,46 @@ impl Options {
         }
     }
 
+    /// Allow the OS to mmap files for reading SST tables, instead of going
+    /// through the read syscall path. Worthwhile for read-mostly datasets on
+    /// fast local storage, and required by the cuckoo table format.
+    ///
+    /// Default: false
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_allow_mmap_reads(true);
+    /// ```
+    pub fn set_allow_mmap_reads(&mut self, is_enabled: bool) {
+        unsafe {
+            ffi::rocksdb_options_set_allow_mmap_reads(self.inner, is_enabled as c_uchar);
+        }
+    }
+
+    /// Allow the OS to mmap files for writing.
+    ///
+    /// This is not compatible with `set_use_direct_io_for_flush_and_compaction`.
+    ///
+    /// Default: false
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_allow_mmap_writes(true);
+    /// ```
+    pub fn set_allow_mmap_writes(&mut self, is_enabled: bool) {
+        unsafe {
+            ffi::rocksdb_options_set_allow_mmap_writes(self.inner, is_enabled as c_uchar);
+        }
+    }
+
     /// Hints to the OS that it should not buffer disk I/O. Enabling this
     /// parameter may improve performance but increases pressure on the
     /// system cache.
@@ -573,30 +1267,77 @@ impl Options {
     /// use rocksdb::Options;
     ///
     /// let mut opts = Options::default();
-    /// opts.set_min_write_buffer_number(4);
+    /// opts.set_min_write_buffer_number(4);
+    /// ```
+    pub fn set_max_write_buffer_number(&mut self, nbuf: c_int) {
+        unsafe {
+            ffi::rocksdb_options_set_max_write_buffer_number(self.inner, nbuf);
+        }
+    }
+
+    /// Sets the amount of data to build up in memory (backed by an unsorted log
+    /// on disk) before converting to a sorted on-disk file.
+    ///
+    /// Larger values increase performance, especially during bulk loads.
+    /// Up to max_write_buffer_number write buffers may be held in memory
+    /// at the same time,
+    /// so you may wish to adjust this parameter to control memory usage.
+    /// Also, a larger write buffer will result in a longer recovery time
+    /// the next time the database is opened.
+    ///
+    /// Note that write_buffer_size is enforced per column family.
+    /// See db_write_buffer_size for sharing memory across column families.
+    ///
+    /// Default: `0x4000000` (64MiB)
+    ///
+    /// Dynamically changeable through SetOptions() API
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_write_buffer_size(128 * 1024 * 1024);
+    /// ```
+    pub fn set_write_buffer_size(&mut self, size: usize) {
+        unsafe {
+            ffi::rocksdb_options_set_write_buffer_size(self.inner, size);
+        }
+    }
+
+    /// Caps the total size of the memtables of all column families. Once
+    /// it is reached, the largest memtable is flushed even if it is below
+    /// its own `write_buffer_size`. Together with `set_max_total_wal_size`
+    /// and `set_max_open_files`, this bounds the memory and file handles a
+    /// database with many column families holds.
+    ///
+    /// Value `0` disables the cap.
+    ///
+    /// Default: `0`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_db_write_buffer_size(512 * 1024 * 1024);
     /// ```
-    pub fn set_max_write_buffer_number(&mut self, nbuf: c_int) {
+    pub fn set_db_write_buffer_size(&mut self, size: usize) {
         unsafe {
-            ffi::rocksdb_options_set_max_write_buffer_number(self.inner, nbuf);
+            ffi::rocksdb_options_set_db_write_buffer_size(self.inner, size);
         }
     }
 
-    /// Sets the amount of data to build up in memory (backed by an unsorted log
-    /// on disk) before converting to a sorted on-disk file.
-    ///
-    /// Larger values increase performance, especially during bulk loads.
-    /// Up to max_write_buffer_number write buffers may be held in memory
-    /// at the same time,
-    /// so you may wish to adjust this parameter to control memory usage.
-    /// Also, a larger write buffer will result in a longer recovery time
-    /// the next time the database is opened.
-    ///
-    /// Note that write_buffer_size is enforced per column family.
-    /// See db_write_buffer_size for sharing memory across column families.
+    /// Caps the total size of the write-ahead logs. Once it is exceeded,
+    /// the column families whose memtables keep the oldest log alive are
+    /// flushed so that it can be deleted.
     ///
-    /// Default: `0x4000000` (64MiB)
+    /// Value `0` caps it at four times the combined size of all write
+    /// buffers.
     ///
-    /// Dynamically changeable through SetOptions() API
+    /// Default: `0`
     ///
     /// # Example
     ///
@@ -604,11 +1345,11 @@ impl Options {
     /// use rocksdb::Options;
     ///
     /// let mut opts = Options::default();
-    /// opts.set_write_buffer_size(128 * 1024 * 1024);
+    /// opts.set_max_total_wal_size(1024 * 1024 * 1024);
     /// ```
-    pub fn set_write_buffer_size(&mut self, size: usize) {
+    pub fn set_max_total_wal_size(&mut self, size: u64) {
         unsafe {
-            ffi::rocksdb_options_set_write_buffer_size(self.inner, size);
+            ffi::rocksdb_options_set_max_total_wal_size(self.inner, size);
         }
     }
 
@@ -639,8 +1380,13 @@ impl Options {
         }
     }
 
+    /// Sets the ratio between the total size of level-(L+1) files and
+    /// the total size of level-L files for all L.
+    ///
     /// Default: `10`
     ///
+    /// Dynamically changeable through SetOptions() API
+    ///
     /// # Example
     ///
     /// ```
@@ -655,6 +1401,28 @@ impl Options {
         }
     }
 
+    /// If true, RocksDB picks the target size of each level dynamically,
+    /// working backwards from the size of the last level. This keeps space
+    /// amplification close to `1 + 1 / max_bytes_for_level_multiplier`
+    /// regardless of the database size, at the cost of leaving the upper
+    /// levels empty while the database is small.
+    ///
+    /// Default: `false`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_level_compaction_dynamic_level_bytes(true);
+    /// ```
+    pub fn set_level_compaction_dynamic_level_bytes(&mut self, v: bool) {
+        unsafe {
+            ffi::rocksdb_options_set_level_compaction_dynamic_level_bytes(self.inner, v as c_uchar);
+        }
+    }
+
     /// The manifest file is rolled over on reaching this limit.
     /// The older manifest file be deleted.
     /// The default value is MAX_INT so that roll-over does not take place.
@@ -700,6 +1468,48 @@ impl Options {
         }
     }
 
+    /// Sets the size ratio between the target files of consecutive levels.
+    /// See `set_target_file_size_base` for how the two are combined.
+    ///
+    /// Default: `1`
+    ///
+    /// Dynamically changeable through SetOptions() API
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_target_file_size_multiplier(2);
+    /// ```
+    pub fn set_target_file_size_multiplier(&mut self, mul: i32) {
+        unsafe {
+            ffi::rocksdb_options_set_target_file_size_multiplier(self.inner, mul as c_int);
+        }
+    }
+
+    /// Sets the upper limit on the total size of the input files of a
+    /// single compaction. A value of `0` uses `25 * target_file_size_base`.
+    ///
+    /// Default: `0`
+    ///
+    /// Dynamically changeable through SetOptions() API
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_max_compaction_bytes(1024 * 1024 * 1024);
+    /// ```
+    pub fn set_max_compaction_bytes(&mut self, nbytes: u64) {
+        unsafe {
+            ffi::rocksdb_options_set_max_compaction_bytes(self.inner, nbytes);
+        }
+    }
+
     /// Sets the minimum number of write buffers that will be merged together
     /// before writing to storage.  If set to `1`, then
     /// all write buffers are flushed to L0 as individual files and this increases
@@ -864,6 +1674,70 @@ impl Options {
         }
     }
 
+    /// Sets the maximum number of concurrent background jobs (compactions
+    /// and flushes). RocksDB decides by itself how to split this budget
+    /// between them.
+    ///
+    /// Default: `2`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_max_background_jobs(8);
+    /// ```
+    pub fn set_max_background_jobs(&mut self, jobs: c_int) {
+        unsafe {
+            ffi::rocksdb_options_set_max_background_jobs(self.inner, jobs);
+        }
+    }
+
+    /// Sets the maximum number of threads that will concurrently perform a
+    /// compaction job by breaking it into multiple, smaller ones that are
+    /// run simultaneously. This mostly speeds up large manual compactions
+    /// and L0 to L1 compactions.
+    ///
+    /// Default: `1` (i.e. no subcompactions)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_max_subcompactions(4);
+    /// ```
+    pub fn set_max_subcompactions(&mut self, num: u32) {
+        unsafe {
+            ffi::rocksdb_options_set_max_subcompactions(self.inner, num as uint32_t);
+        }
+    }
+
+    /// If non-zero, compaction inputs are read with big sequential reads of
+    /// this size instead of reading each block separately. Recommended on
+    /// spinning disks and network storage; with direct I/O, at least 2MB is
+    /// recommended.
+    ///
+    /// Default: `0`
+    ///
+    /// Dynamically changeable through SetOptions() API
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_compaction_readahead_size(2 * 1024 * 1024);
+    /// ```
+    pub fn set_compaction_readahead_size(&mut self, size: usize) {
+        unsafe {
+            ffi::rocksdb_options_set_compaction_readahead_size(self.inner, size as size_t);
+        }
+    }
+
     /// Disables automatic compactions. Manual compactions can still
     /// be issued on this column family
     ///
@@ -883,12 +1757,184 @@ impl Options {
         unsafe { ffi::rocksdb_options_set_disable_auto_compactions(self.inner, disable as c_int) }
     }
 
+    /// Sets the `Env` used to run background work and access the filesystem.
+    /// The default environment is used if this is never called.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{Env, Options};
+    ///
+    /// let mut env = Env::default();
+    /// env.set_background_threads(4);
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_env(&env);
+    /// ```
+    pub fn set_env(&mut self, env: &Env) {
+        unsafe {
+            ffi::rocksdb_options_set_env(self.inner, env.inner.ptr);
+        }
+        self.env = Some(env.clone());
+    }
+
     pub fn set_block_based_table_factory(&mut self, factory: &BlockBasedOptions) {
         unsafe {
             ffi::rocksdb_options_set_block_based_table_factory(self.inner, factory.inner);
         }
     }
 
+    /// Replaces the default skiplist memtable with an alternative representation.
+    ///
+    /// A vector memtable is fast for bulk loads that are only read after a
+    /// flush. The hash skiplist and hash linklist representations require a
+    /// prefix extractor and make prefix-scoped writes and scans much cheaper.
+    ///
+    /// Concurrent memtable writes are only supported by the default skiplist,
+    /// so `set_allow_concurrent_memtable_write(false)` is needed alongside
+    /// any of these.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{MemtableFactory, Options, SliceTransform};
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(3));
+    /// opts.set_allow_concurrent_memtable_write(false);
+    /// opts.set_memtable_factory(MemtableFactory::HashSkipList {
+    ///     bucket_count: 1_000_000,
+    ///     height: 4,
+    ///     branching_factor: 4,
+    /// });
+    /// ```
+    pub fn set_memtable_factory(&mut self, factory: MemtableFactory) {
+        unsafe {
+            match factory {
+                MemtableFactory::Vector => {
+                    ffi::rocksdb_options_set_memtable_vector_rep(self.inner);
+                }
+                MemtableFactory::HashSkipList {
+                    bucket_count,
+                    height,
+                    branching_factor,
+                } => {
+                    ffi::rocksdb_options_set_hash_skip_list_rep(
+                        self.inner,
+                        bucket_count as size_t,
+                        height as int32_t,
+                        branching_factor as int32_t,
+                    );
+                }
+                MemtableFactory::HashLinkList { bucket_count } => {
+                    ffi::rocksdb_options_set_hash_link_list_rep(self.inner, bucket_count as size_t);
+                }
+            }
+        }
+    }
+
+    /// Sets the options used by universal style compaction. These only take
+    /// effect with `set_compaction_style(DBCompactionStyle::Universal)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{DBCompactionStyle, Options, UniversalCompactOptions,
+    ///               UniversalCompactionStopStyle};
+    ///
+    /// let mut uco = UniversalCompactOptions::default();
+    /// uco.set_size_ratio(2);
+    /// uco.set_max_size_amplification_percent(150);
+    /// uco.set_stop_style(UniversalCompactionStopStyle::Similar);
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_compaction_style(DBCompactionStyle::Universal);
+    /// opts.set_universal_compaction_options(&uco);
+    /// ```
+    pub fn set_universal_compaction_options(&mut self, uco: &UniversalCompactOptions) {
+        unsafe {
+            ffi::rocksdb_options_set_universal_compaction_options(self.inner, uco.inner);
+        }
+    }
+
+    /// Sets the options used by FIFO style compaction. These only take
+    /// effect with `set_compaction_style(DBCompactionStyle::Fifo)`.
+    ///
+    /// FIFO compaction never merges files; it bounds the database size by
+    /// dropping the oldest SST files, which suits time-series and cache data.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{DBCompactionStyle, FifoCompactOptions, Options};
+    ///
+    /// let mut fifo_opts = FifoCompactOptions::default();
+    /// fifo_opts.set_max_table_files_size(100 * 1024 * 1024);
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_compaction_style(DBCompactionStyle::Fifo);
+    /// opts.set_fifo_compaction_options(&fifo_opts);
+    /// ```
+    pub fn set_fifo_compaction_options(&mut self, fifo_opts: &FifoCompactOptions) {
+        unsafe {
+            ffi::rocksdb_options_set_fifo_compaction_options(self.inner, fifo_opts.inner);
+        }
+    }
+
+    /// Use the cuckoo hash table format for SST files instead of the
+    /// default block-based format. Cuckoo tables give O(1) point lookups
+    /// for read-mostly data but do not support range scans efficiently.
+    /// They also require `set_allow_mmap_reads(true)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{CuckooTableOptions, Options};
+    ///
+    /// let mut cuckoo_opts = CuckooTableOptions::default();
+    /// cuckoo_opts.set_hash_ratio(0.75);
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_allow_mmap_reads(true);
+    /// opts.set_cuckoo_table_factory(&cuckoo_opts);
+    /// ```
+    pub fn set_cuckoo_table_factory(&mut self, factory: &CuckooTableOptions) {
+        unsafe {
+            ffi::rocksdb_options_set_cuckoo_table_factory(self.inner, factory.inner);
+        }
+    }
+
+    /// Use the plain table format for SST files instead of the default
+    /// block-based format. Plain tables are optimized for low-latency
+    /// lookups on data that lives in memory (or in mmap-ed files), and
+    /// require a prefix extractor to be set.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{Options, PlainTableOptions, SliceTransform};
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(4));
+    /// opts.set_plain_table_factory(&PlainTableOptions {
+    ///     user_key_length: 0,
+    ///     bloom_bits_per_key: 20,
+    ///     hash_table_ratio: 0.75,
+    ///     index_sparseness: 16,
+    /// });
+    /// ```
+    pub fn set_plain_table_factory(&mut self, options: &PlainTableOptions) {
+        unsafe {
+            ffi::rocksdb_options_set_plain_table_factory(
+                self.inner,
+                options.user_key_length as uint32_t,
+                options.bloom_bits_per_key as c_int,
+                options.hash_table_ratio,
+                options.index_sparseness as size_t,
+            );
+        }
+    }
+
     /// Measure IO stats in compactions and flushes, if `true`.
     ///
     /// Default: `false`
@@ -925,6 +1971,95 @@ impl Options {
         }
     }
 
+    /// Sets the directory the write-ahead log files are placed in, so the
+    /// WAL can live on a different device from the data files. When empty,
+    /// the WAL is written to the database directory.
+    ///
+    /// Default: empty
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_wal_dir("/mnt/fast-disk/rocksdb-wal");
+    /// ```
+    pub fn set_wal_dir<P: AsRef<Path>>(&mut self, path: P) {
+        let cpath = CString::new(path.as_ref().to_string_lossy().as_bytes()).unwrap();
+        unsafe {
+            ffi::rocksdb_options_set_wal_dir(self.inner, cpath.as_ptr());
+        }
+    }
+
+    /// Sets how long obsolete WAL files are kept in the archive directory
+    /// before being deleted, in seconds. Together with
+    /// `set_wal_size_limit_mb` this controls WAL archival:
+    ///
+    /// - If both are `0`, obsolete WAL files are deleted immediately and not
+    ///   archived.
+    /// - If only the size limit is non-zero, the oldest archived files are
+    ///   deleted once the archive exceeds it.
+    /// - If only the TTL is non-zero, archived files older than it are
+    ///   deleted.
+    /// - If both are non-zero, files are deleted on either condition, with
+    ///   the TTL checked first.
+    ///
+    /// Default: `0`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_wal_ttl_seconds(60 * 60);
+    /// ```
+    pub fn set_wal_ttl_seconds(&mut self, secs: u64) {
+        unsafe {
+            ffi::rocksdb_options_set_WAL_ttl_seconds(self.inner, secs);
+        }
+    }
+
+    /// Sets the size limit of the WAL archive, in megabytes. See
+    /// `set_wal_ttl_seconds` for how the two interact.
+    ///
+    /// Default: `0`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_wal_size_limit_mb(1024);
+    /// ```
+    pub fn set_wal_size_limit_mb(&mut self, size: u64) {
+        unsafe {
+            ffi::rocksdb_options_set_WAL_size_limit_MB(self.inner, size);
+        }
+    }
+
+    /// If non-zero, up to this many obsolete log files are kept around and
+    /// overwritten instead of creating new ones, which avoids metadata
+    /// updates on file systems where preallocation is expensive.
+    ///
+    /// Default: `0`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::Options;
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_recycle_log_file_num(4);
+    /// ```
+    pub fn set_recycle_log_file_num(&mut self, num: usize) {
+        unsafe {
+            ffi::rocksdb_options_set_recycle_log_file_num(self.inner, num as size_t);
+        }
+    }
+
     pub fn enable_statistics(&mut self) {
         unsafe {
             ffi::rocksdb_options_enable_statistics(self.inner);
@@ -945,6 +2080,12 @@ impl Options {
         }
     }
 
+    /// Returns the current value of every ticker and histogram, or `None`
+    /// if statistics were not enabled with `enable_statistics`.
+    pub fn get_statistics_snapshot(&self) -> Option<Statistics> {
+        self.get_statistics().map(|s| Statistics::parse(&s))
+    }
+
     /// If not zero, dump `rocksdb.stats` to LOG every `stats_dump_period_sec`.
     ///
     /// Default: `600` (10 mins)
@@ -989,7 +2130,10 @@ impl Default for Options {
             if opts.is_null() {
                 panic!("Could not create RocksDB options");
             }
-            Options { inner: opts }
+            Options {
+                inner: opts,
+                env: None,
+            }
         }
     }
 }
@@ -1036,4 +2180,15 @@ mod tests {
         let opts = Options::default();
         assert!(opts.get_statistics().is_none());
     }
+
+    #[test]
+    fn test_get_statistics_snapshot() {
+        let mut opts = Options::default();
+        opts.enable_statistics();
+        let stats = opts.get_statistics_snapshot().unwrap();
+        assert!(stats.tickers.contains_key("rocksdb.block.cache.miss"));
+        assert!(stats.histograms.contains_key("rocksdb.db.get.micros"));
+
+        assert!(Options::default().get_statistics_snapshot().is_none());
+    }
 }
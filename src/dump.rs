@@ -0,0 +1,576 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dumping key-value pairs as text, and importing them back, e.g. for
+//! migrations and debugging.
+//!
+//! Two formats are supported:
+//!
+//! - `Format::JsonLines` writes one `{"key":"...","value":"..."}` object per
+//!   line;
+//! - `Format::Csv` writes a `key,value` header followed by one record per
+//!   line, quoting fields as described in RFC 4180.
+//!
+//! Keys and values are written as hexadecimal, base64 or UTF-8 text. UTF-8
+//! is only lossless for data that is valid UTF-8: dumping anything else
+//! with it fails.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::DB;
+//! use rocksdb::dump::{self, DumpOptions, Encoding, Format};
+//!
+//! let db = DB::open_default("path/for/rocksdb/storage_dump").unwrap();
+//! db.put(b"k1", b"v1").unwrap();
+//! db.put(b"k2", b"v2").unwrap();
+//!
+//! let mut opts = DumpOptions::default();
+//! opts.set_format(Format::Csv);
+//! opts.set_key_encoding(Encoding::Utf8);
+//! opts.set_value_encoding(Encoding::Utf8);
+//!
+//! let mut out = Vec::new();
+//! assert_eq!(dump::dump(&db, None, Some(b"k2"), None, &opts, &mut out).unwrap(), 1);
+//! assert_eq!(out, b"key,value\nk2,v2\n");
+//!
+//! let db2 = DB::open_default("path/for/rocksdb/storage_dump_imported").unwrap();
+//! assert_eq!(dump::import(&db2, None, &opts, &out[..]).unwrap(), 1);
+//! assert_eq!(&*db2.get(b"k2").unwrap().unwrap(), b"v2");
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::str;
+
+use {ColumnFamily, DB, Direction, Error, ErrorKind, IteratorMode, ReadOptions, WriteBatch};
+
+// The size of the batches written by `import`.
+const IMPORT_BATCH_BYTES: usize = 4 << 20;
+
+const BASE64: &'static [u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The text format of a dump.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Format {
+    JsonLines,
+    Csv,
+}
+
+/// How keys or values are written as text.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Encoding {
+    /// Lowercase hexadecimal.
+    Hex,
+    /// Standard base64, with padding.
+    Base64,
+    /// The bytes themselves, which must be valid UTF-8.
+    Utf8,
+}
+
+/// The format and encodings shared by `dump` and `import`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DumpOptions {
+    format: Format,
+    key_encoding: Encoding,
+    value_encoding: Encoding,
+}
+
+impl Default for DumpOptions {
+    fn default() -> DumpOptions {
+        DumpOptions {
+            format: Format::JsonLines,
+            key_encoding: Encoding::Hex,
+            value_encoding: Encoding::Hex,
+        }
+    }
+}
+
+impl DumpOptions {
+    /// Default: `Format::JsonLines`
+    pub fn set_format(&mut self, format: Format) {
+        self.format = format;
+    }
+
+    /// Default: `Encoding::Hex`
+    pub fn set_key_encoding(&mut self, encoding: Encoding) {
+        self.key_encoding = encoding;
+    }
+
+    /// Default: `Encoding::Hex`
+    pub fn set_value_encoding(&mut self, encoding: Encoding) {
+        self.value_encoding = encoding;
+    }
+}
+
+fn invalid(line: usize, msg: &str) -> Error {
+    Error::with_kind(ErrorKind::InvalidArgument, format!("line {}: {}", line, msg))
+}
+
+fn io_error(e: io::Error) -> Error {
+    Error::with_kind(ErrorKind::IOError, e.to_string())
+}
+
+fn encode(bytes: &[u8], encoding: Encoding) -> Result<String, Error> {
+    match encoding {
+        Encoding::Hex => Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect()),
+        Encoding::Base64 => {
+            let mut s = String::with_capacity((bytes.len() + 2) / 3 * 4);
+            for chunk in bytes.chunks(3) {
+                let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| {
+                    n | (b as u32) << (16 - 8 * i)
+                });
+                for i in 0..4 {
+                    if i <= chunk.len() {
+                        s.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+                    } else {
+                        s.push('=');
+                    }
+                }
+            }
+            Ok(s)
+        }
+        Encoding::Utf8 => match str::from_utf8(bytes) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(_) => Err(Error::with_kind(
+                ErrorKind::InvalidArgument,
+                "data is not valid UTF-8, use another encoding".to_owned(),
+            )),
+        },
+    }
+}
+
+fn decode(s: &str, encoding: Encoding) -> Option<Vec<u8>> {
+    match encoding {
+        Encoding::Hex => {
+            if s.len() % 2 != 0 {
+                return None;
+            }
+            (0..s.len())
+                .step_by(2)
+                .map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+                .collect()
+        }
+        Encoding::Base64 => {
+            let s = s.as_bytes();
+            if s.len() % 4 != 0 {
+                return None;
+            }
+            let mut bytes = Vec::with_capacity(s.len() / 4 * 3);
+            for (i, chunk) in s.chunks(4).enumerate() {
+                let last = i == s.len() / 4 - 1;
+                let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+                if padding > 2 || (padding > 0 && !last) {
+                    return None;
+                }
+                let mut n = 0u32;
+                for &c in &chunk[..4 - padding] {
+                    let digit = match BASE64.iter().position(|&d| d == c) {
+                        Some(digit) => digit as u32,
+                        None => return None,
+                    };
+                    n = n << 6 | digit;
+                }
+                n <<= 6 * padding as u32;
+                for i in 0..3 - padding {
+                    bytes.push((n >> (16 - 8 * i)) as u8);
+                }
+            }
+            Some(bytes)
+        }
+        Encoding::Utf8 => Some(s.as_bytes().to_vec()),
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Writes the entries of `cf`, or of the default column family, from `start`
+/// up to but excluding `end`, to `writer`. Returns the number of entries
+/// written.
+pub fn dump<W: Write>(
+    db: &DB,
+    cf: Option<&ColumnFamily>,
+    start: Option<&[u8]>,
+    end: Option<&[u8]>,
+    opts: &DumpOptions,
+    writer: &mut W,
+) -> Result<u64, Error> {
+    let mode = match start {
+        Some(start) => IteratorMode::From(start, Direction::Forward),
+        None => IteratorMode::Start,
+    };
+    let mut readopts = ReadOptions::default();
+    if let Some(end) = end {
+        readopts.set_iterate_upper_bound(end);
+    }
+    let iter = match cf {
+        Some(cf) => try!(db.iterator_cf_opt(cf, mode, readopts)),
+        None => db.iterator_opt(mode, readopts),
+    };
+    if opts.format == Format::Csv {
+        try!(writer.write_all(b"key,value\n").map_err(io_error));
+    }
+    let mut count = 0;
+    for (key, value) in iter {
+        let key = try!(encode(&key, opts.key_encoding));
+        let value = try!(encode(&value, opts.value_encoding));
+        let line = match opts.format {
+            Format::JsonLines => {
+                format!("{{\"key\":{},\"value\":{}}}\n", json_string(&key), json_string(&value))
+            }
+            Format::Csv => format!("{},{}\n", csv_field(&key), csv_field(&value)),
+        };
+        try!(writer.write_all(line.as_bytes()).map_err(io_error));
+        count += 1;
+    }
+    try!(writer.flush().map_err(io_error));
+    Ok(count)
+}
+
+// Parses a JSON string starting at the opening quote, returning it and the
+// rest of the input.
+fn parse_json_string(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.char_indices();
+    if chars.next() != Some((0, '"')) {
+        return None;
+    }
+    let mut out = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &s[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, '"')) => out.push('"'),
+                Some((_, '\\')) => out.push('\\'),
+                Some((_, '/')) => out.push('/'),
+                Some((_, 'b')) => out.push('\u{8}'),
+                Some((_, 'f')) => out.push('\u{c}'),
+                Some((_, 'n')) => out.push('\n'),
+                Some((_, 'r')) => out.push('\r'),
+                Some((_, 't')) => out.push('\t'),
+                Some((j, 'u')) => {
+                    let c = s.get(j + 1..j + 5)
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .and_then(::std::char::from_u32);
+                    match c {
+                        Some(c) => out.push(c),
+                        None => return None,
+                    }
+                    for _ in 0..4 {
+                        chars.next();
+                    }
+                }
+                _ => return None,
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+// Parses a `{"key":"...","value":"..."}` object.
+fn parse_json_line(line: &str) -> Option<(String, String)> {
+    let mut rest = line.trim();
+    if !rest.starts_with('{') || !rest.ends_with('}') {
+        return None;
+    }
+    rest = rest[1..rest.len() - 1].trim();
+    let (mut key, mut value) = (None, None);
+    while !rest.is_empty() {
+        let (name, after) = match parse_json_string(rest) {
+            Some(parsed) => parsed,
+            None => return None,
+        };
+        let after = after.trim_start();
+        if !after.starts_with(':') {
+            return None;
+        }
+        let (field, after) = match parse_json_string(after[1..].trim_start()) {
+            Some(parsed) => parsed,
+            None => return None,
+        };
+        match name.as_str() {
+            "key" => key = Some(field),
+            "value" => value = Some(field),
+            _ => {}
+        }
+        rest = after.trim_start();
+        if rest.starts_with(',') {
+            rest = rest[1..].trim_start();
+        } else if !rest.is_empty() {
+            return None;
+        }
+    }
+    match (key, value) {
+        (Some(key), Some(value)) => Some((key, value)),
+        _ => None,
+    }
+}
+
+// Splits a CSV record into its fields, or returns `None` if a quoted field
+// continues on the next line.
+fn parse_csv_record(record: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(::std::mem::replace(&mut field, String::new())),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+// Appends the next line of `reader`, with its line ending, to `line`, and
+// returns false at the end of the input.
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> Result<bool, Error> {
+    Ok(try!(reader.read_line(line).map_err(io_error)) > 0)
+}
+
+// Removes the `\n` or `\r\n` ending a line, if any.
+fn strip_line_ending(line: &str) -> &str {
+    if line.ends_with("\r\n") {
+        &line[..line.len() - 2]
+    } else if line.ends_with('\n') {
+        &line[..line.len() - 1]
+    } else {
+        line
+    }
+}
+
+/// Reads entries written by `dump` with the same options from `reader`,
+/// and writes them to `cf`, or to the default column family, in batches.
+/// Returns the number of entries imported.
+///
+/// Entries read before an invalid line are still imported.
+pub fn import<R: Read>(
+    db: &DB,
+    cf: Option<&ColumnFamily>,
+    opts: &DumpOptions,
+    reader: R,
+) -> Result<u64, Error> {
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    let mut batch = WriteBatch::default();
+    let mut batch_size = 0;
+    let mut count = 0;
+    let mut line_number = 0;
+    if opts.format == Format::Csv {
+        if !try!(read_line(&mut reader, &mut line)) {
+            return Ok(0);
+        }
+        line_number += 1;
+        if line.trim_end() != "key,value" {
+            return Err(invalid(line_number, "expected a `key,value` header"));
+        }
+    }
+    loop {
+        line.clear();
+        if !try!(read_line(&mut reader, &mut line)) {
+            break;
+        }
+        line_number += 1;
+        let first_line = line_number;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (key, value) = match opts.format {
+            Format::JsonLines => match parse_json_line(strip_line_ending(&line)) {
+                Some(entry) => entry,
+                None => return Err(invalid(first_line, "expected a key and value object")),
+            },
+            Format::Csv => {
+                // The line breaks inside a quoted field, `\r\n` included, are
+                // part of it, so only the one ending the record is removed.
+                let fields = loop {
+                    if let Some(fields) = parse_csv_record(strip_line_ending(&line)) {
+                        break fields;
+                    }
+                    if !try!(read_line(&mut reader, &mut line)) {
+                        return Err(invalid(first_line, "unterminated quoted field"));
+                    }
+                    line_number += 1;
+                };
+                if fields.len() != 2 {
+                    return Err(invalid(first_line, "expected a key and a value"));
+                }
+                let mut fields = fields.into_iter();
+                (fields.next().unwrap(), fields.next().unwrap())
+            }
+        };
+        let key = match decode(&key, opts.key_encoding) {
+            Some(key) => key,
+            None => return Err(invalid(first_line, "invalid key encoding")),
+        };
+        let value = match decode(&value, opts.value_encoding) {
+            Some(value) => value,
+            None => return Err(invalid(first_line, "invalid value encoding")),
+        };
+        match cf {
+            Some(cf) => try!(batch.put_cf(cf, &key, &value)),
+            None => try!(batch.put(&key, &value)),
+        }
+        batch_size += key.len() + value.len();
+        count += 1;
+        if batch_size >= IMPORT_BATCH_BYTES {
+            try!(db.write(batch));
+            batch = WriteBatch::default();
+            batch_size = 0;
+        }
+    }
+    try!(db.write(batch));
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DumpOptions, Encoding, Format, decode, dump, encode, import, parse_csv_record,
+                parse_json_line};
+    use {DB, Options};
+
+    #[test]
+    fn test_encodings() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"\x00\xff\xfe", "AP/+"),
+        ];
+        for &(bytes, base64) in cases {
+            assert_eq!(encode(bytes, Encoding::Base64).unwrap(), base64);
+            assert_eq!(decode(base64, Encoding::Base64).unwrap(), bytes);
+            let hex = encode(bytes, Encoding::Hex).unwrap();
+            assert_eq!(decode(&hex, Encoding::Hex).unwrap(), bytes);
+        }
+        assert_eq!(encode(b"\x00\xab", Encoding::Hex).unwrap(), "00ab");
+        assert!(encode(b"\xff", Encoding::Utf8).is_err());
+        assert!(decode("abc", Encoding::Hex).is_none());
+        assert!(decode("zz", Encoding::Hex).is_none());
+        assert!(decode("Zg=", Encoding::Base64).is_none());
+        assert!(decode("Zg==Zg==", Encoding::Base64).is_none());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_json_line(r#" { "value" : "a\"\\\né", "key":"k" } "#),
+            Some(("k".to_owned(), "a\"\\\n\u{e9}".to_owned()))
+        );
+        assert_eq!(parse_json_line(r#"{"key":"k"}"#), None);
+        assert_eq!(parse_json_line(r#"{"key":"k" "value":"v"}"#), None);
+        assert_eq!(
+            parse_csv_record("a,\"b,\"\"c\"\"\""),
+            Some(vec!["a".to_owned(), "b,\"c\"".to_owned()])
+        );
+        assert_eq!(parse_csv_record("a,\"b"), None);
+    }
+
+    #[test]
+    fn test_dump_import() {
+        let path = "_rust_rocksdb_dump_test";
+        let imported = "_rust_rocksdb_dump_imported_test";
+        {
+            let db = DB::open_default(path).unwrap();
+            db.put(b"k1", b"plain").unwrap();
+            db.put(b"k2", b"with \"quotes\", commas\nand lines").unwrap();
+            db.put(b"k3", b"\x00\xff").unwrap();
+
+            let mut opts = DumpOptions::default();
+            let mut out = Vec::new();
+            assert_eq!(dump(&db, None, None, None, &opts, &mut out).unwrap(), 3);
+            assert!(out.starts_with(b"{\"key\":\"6b31\",\"value\":\"706c61696e\"}\n"));
+
+            opts.set_key_encoding(Encoding::Utf8);
+            let mut utf8 = opts;
+            utf8.set_value_encoding(Encoding::Utf8);
+            assert!(dump(&db, None, None, None, &utf8, &mut Vec::new()).is_err());
+
+            let db2 = DB::open_default(imported).unwrap();
+            for &format in &[Format::JsonLines, Format::Csv] {
+                opts.set_format(format);
+                opts.set_value_encoding(Encoding::Base64);
+                let mut out = Vec::new();
+                assert_eq!(dump(&db, None, Some(b"k2"), None, &opts, &mut out).unwrap(), 2);
+                assert_eq!(import(&db2, None, &opts, &out[..]).unwrap(), 2);
+
+                opts.set_value_encoding(Encoding::Hex);
+                let mut out = Vec::new();
+                assert_eq!(dump(&db, None, None, Some(b"k2"), &opts, &mut out).unwrap(), 1);
+                assert_eq!(import(&db2, None, &opts, &out[..]).unwrap(), 1);
+            }
+            for (key, value) in db.iterator(::IteratorMode::Start) {
+                assert_eq!(db2.get(&key).unwrap().unwrap().to_vec(), value.to_vec());
+            }
+
+            opts.set_format(Format::Csv);
+            opts.set_value_encoding(Encoding::Utf8);
+            let mut out = Vec::new();
+            dump(&db, None, Some(b"k2"), Some(b"k3"), &opts, &mut out).unwrap();
+            assert_eq!(
+                out,
+                &b"key,value\nk2,\"with \"\"quotes\"\", commas\nand lines\"\n"[..]
+            );
+            assert_eq!(import(&db2, None, &opts, &out[..]).unwrap(), 1);
+            assert!(import(&db2, None, &opts, &b"key,value\nk1\n"[..]).is_err());
+
+            // Line endings inside quoted fields are kept as they are.
+            let csv = b"key,value\r\nk4,\"crlf\r\nin value\"\r\nk5,v5\r\n";
+            assert_eq!(import(&db2, None, &opts, &csv[..]).unwrap(), 2);
+            assert_eq!(&*db2.get(b"k4").unwrap().unwrap(), b"crlf\r\nin value");
+            assert_eq!(&*db2.get(b"k5").unwrap().unwrap(), b"v5");
+        }
+        assert!(DB::destroy(&Options::default(), path).is_ok());
+        assert!(DB::destroy(&Options::default(), imported).is_ok());
+    }
+}
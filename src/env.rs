@@ -0,0 +1,229 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{Arc, Mutex};
+
+use libc::c_int;
+
+use ffi;
+use {Env, Error, ErrorKind, Options};
+
+/// Owns the underlying `rocksdb_env_t`. It is shared between every `Env`
+/// handle, `Options` and `DB` using it, and destroyed once the last one is
+/// dropped, so the environment always outlives the databases running on it.
+pub struct EnvHandle {
+    pub ptr: *mut ffi::rocksdb_env_t,
+}
+
+unsafe impl Send for EnvHandle {}
+unsafe impl Sync for EnvHandle {}
+
+impl Drop for EnvHandle {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_env_destroy(self.ptr);
+        }
+    }
+}
+
+impl Env {
+    fn from_raw(env: *mut ffi::rocksdb_env_t, kind: &str) -> Env {
+        if env.is_null() {
+            panic!("Could not create RocksDB {} env", kind);
+        }
+        Env { inner: Arc::new(EnvHandle { ptr: env }) }
+    }
+
+    /// Creates an environment that keeps all files in memory. Databases
+    /// opened with it behave normally but never touch the filesystem, and
+    /// their contents are lost once the last handle to the `Env` is dropped.
+    ///
+    /// The paths passed to `DB::open` only name databases within this
+    /// environment; the same path can be reopened as long as the `Env` is
+    /// alive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::{DB, Env, Options};
+    ///
+    /// let env = Env::mem_env();
+    /// let mut opts = Options::default();
+    /// opts.create_if_missing(true);
+    /// opts.set_env(&env);
+    ///
+    /// let db = DB::open(&opts, "in-memory-db").unwrap();
+    /// db.put(b"my key", b"my value").unwrap();
+    /// ```
+    pub fn mem_env() -> Env {
+        Env::from_raw(unsafe { ffi::rocksdb_create_mem_env() }, "in-memory")
+    }
+
+    /// Sets the number of background threads in the LOW priority pool,
+    /// which runs compactions.
+    ///
+    /// Default: `1`
+    pub fn set_background_threads(&mut self, num_threads: c_int) {
+        unsafe {
+            ffi::rocksdb_env_set_background_threads(self.inner.ptr, num_threads);
+        }
+    }
+
+    /// Sets the number of background threads in the HIGH priority pool,
+    /// which runs memtable flushes.
+    ///
+    /// Default: `1`
+    pub fn set_high_priority_background_threads(&mut self, num_threads: c_int) {
+        unsafe {
+            ffi::rocksdb_env_set_high_priority_background_threads(self.inner.ptr, num_threads);
+        }
+    }
+
+    /// Waits for all threads started by the background thread pools to
+    /// terminate.
+    pub fn join_all_threads(&mut self) {
+        unsafe {
+            ffi::rocksdb_env_join_all_threads(self.inner.ptr);
+        }
+    }
+}
+
+impl Default for Env {
+    /// Returns a handle to the process-wide default environment. Thread
+    /// pool sizes set through any handle apply to all of them.
+    fn default() -> Env {
+        Env::from_raw(unsafe { ffi::rocksdb_create_default_env() }, "default")
+    }
+}
+
+fn thread_count_error(num_threads: c_int, max_threads: c_int) -> Error {
+    Error::with_kind(ErrorKind::InvalidArgument, format!(
+        "thread count {} is not between 1 and {}",
+        num_threads,
+        max_threads
+    ))
+}
+
+struct Pools {
+    env: Env,
+    compactions: c_int,
+    flushes: c_int,
+}
+
+/// Centrally bounds the threads running background work for every database
+/// it is applied to, such as all databases of a process sharing the default
+/// `Env`.
+///
+/// Each database is allowed up to `max_threads` compactions and flushes, so
+/// the number of jobs actually running at once is bounded by the size of
+/// the `Env` thread pools alone. Those can be resized at any time, from any
+/// clone of the limiter, without reopening the databases.
+///
+/// # Examples
+///
+/// ```
+/// use rocksdb::{DB, Env, Options, ThreadLimiter};
+///
+/// let limiter = ThreadLimiter::new(Env::default(), 8, 2, 1).unwrap();
+///
+/// let mut opts = Options::default();
+/// opts.create_if_missing(true);
+/// limiter.apply(&mut opts);
+/// let db = DB::open(&opts, "path/for/rocksdb/storage_limiter").unwrap();
+///
+/// // Give compactions more cores while the process is otherwise idle.
+/// limiter.set_compaction_threads(6).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct ThreadLimiter {
+    pools: Arc<Mutex<Pools>>,
+    max_threads: c_int,
+}
+
+impl ThreadLimiter {
+    /// Takes over the thread pools of `env`, sizing them to
+    /// `compaction_threads` and `flush_threads`. Pools can later be resized
+    /// up to `max_threads`; both sizes must be between 1 and `max_threads`.
+    ///
+    /// The sizes are given explicitly because RocksDB cannot report the
+    /// current ones, and other users of `env` may depend on them.
+    pub fn new(
+        mut env: Env,
+        max_threads: c_int,
+        compaction_threads: c_int,
+        flush_threads: c_int,
+    ) -> Result<ThreadLimiter, Error> {
+        let limiter_max = max_threads.max(1);
+        for &num_threads in &[compaction_threads, flush_threads] {
+            if num_threads < 1 || num_threads > limiter_max {
+                return Err(thread_count_error(num_threads, limiter_max));
+            }
+        }
+        env.set_background_threads(compaction_threads);
+        env.set_high_priority_background_threads(flush_threads);
+        Ok(ThreadLimiter {
+            pools: Arc::new(Mutex::new(Pools {
+                env: env,
+                compactions: compaction_threads,
+                flushes: flush_threads,
+            })),
+            max_threads: limiter_max,
+        })
+    }
+
+    /// Makes `opts` run its background work on the limited thread pools.
+    pub fn apply(&self, opts: &mut Options) {
+        opts.set_env(&self.pools.lock().unwrap().env);
+        opts.set_max_background_compactions(self.max_threads);
+        opts.set_max_background_flushes(self.max_threads);
+    }
+
+    fn check(&self, num_threads: c_int) -> Result<(), Error> {
+        if num_threads < 1 || num_threads > self.max_threads {
+            return Err(thread_count_error(num_threads, self.max_threads));
+        }
+        Ok(())
+    }
+
+    /// Resizes the LOW priority pool, which runs compactions.
+    pub fn set_compaction_threads(&self, num_threads: c_int) -> Result<(), Error> {
+        try!(self.check(num_threads));
+        let mut pools = self.pools.lock().unwrap();
+        pools.env.set_background_threads(num_threads);
+        pools.compactions = num_threads;
+        Ok(())
+    }
+
+    /// Resizes the HIGH priority pool, which runs memtable flushes.
+    pub fn set_flush_threads(&self, num_threads: c_int) -> Result<(), Error> {
+        try!(self.check(num_threads));
+        let mut pools = self.pools.lock().unwrap();
+        pools.env.set_high_priority_background_threads(num_threads);
+        pools.flushes = num_threads;
+        Ok(())
+    }
+
+    pub fn compaction_threads(&self) -> c_int {
+        self.pools.lock().unwrap().compactions
+    }
+
+    pub fn flush_threads(&self) -> c_int {
+        self.pools.lock().unwrap().flushes
+    }
+
+    /// The largest size either pool may be given.
+    pub fn max_threads(&self) -> c_int {
+        self.max_threads
+    }
+}
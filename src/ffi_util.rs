@@ -14,7 +14,11 @@
 //
 
 use libc::{self, c_char, c_void};
+#[cfg(feature = "tracing")]
+use std::any::Any;
 use std::ffi::CStr;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
 use std::ptr;
 
 pub fn error_message(ptr: *const c_char) -> String {
@@ -38,8 +42,74 @@ macro_rules! ffi_try {
         let mut err: *mut ::libc::c_char = ::std::ptr::null_mut();
         let result = $($function)::*($($arg),*, &mut err);
         if !err.is_null() {
-            return Err(Error::new($crate::ffi_util::error_message(err)));
+            let message = $crate::ffi_util::error_message(err);
+            trace_event!(error = %message, "rocksdb call failed");
+            return Err(Error::new(message));
         }
         result
     })
 }
+
+// Enters a `tracing` span, at debug level, for the rest of the enclosing
+// block. Expands to nothing without the `tracing` feature.
+#[cfg(feature = "tracing")]
+macro_rules! enter_span {
+    ($($arg:tt)*) => (
+        let _span = debug_span!($($arg)*).entered();
+    )
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! enter_span {
+    ($($arg:tt)*) => ()
+}
+
+// Emits a `tracing` event, at debug level, in the current span. Expands to
+// nothing without the `tracing` feature.
+#[cfg(feature = "tracing")]
+macro_rules! trace_event {
+    ($($arg:tt)*) => (
+        debug!($($arg)*);
+    )
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_event {
+    ($($arg:tt)*) => ()
+}
+
+#[cfg(feature = "tracing")]
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "Box<Any>"
+    }
+}
+
+/// Runs a user callback invoked by RocksDB, returning `None` if it panicked.
+/// A panic must not unwind into C++, so callers substitute a safe fallback.
+///
+/// The panic hook has already reported the panic; with the `tracing`
+/// feature, an event also names the callback.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn catch_callback_panic<T, F: FnOnce() -> T>(callback: &str, f: F) -> Option<T> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(v) => Some(v),
+        Err(payload) => {
+            trace_event!(callback, panic = panic_message(&*payload), "callback panicked");
+            None
+        }
+    }
+}
+
+/// Runs a user callback invoked by RocksDB that has no safe fallback, such
+/// as a comparator, aborting the process if it panics.
+pub fn abort_on_callback_panic<T, F: FnOnce() -> T>(callback: &str, f: F) -> T {
+    match catch_callback_panic(callback, f) {
+        Some(v) => v,
+        None => process::abort(),
+    }
+}
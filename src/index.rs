@@ -0,0 +1,282 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Secondary indexes kept in their own column families.
+//!
+//! `IndexedDB` stores records in the default column family. Every index
+//! derives any number of index keys from a record, and maps each of them
+//! back to the record's primary key in its column family. A record and its
+//! index entries are always written in one batch, so readers never see an
+//! index entry without its record.
+//!
+//! All writes to the records must go through the `IndexedDB`: writes made
+//! directly on the `DB` do not update the indexes.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::{DB, Options};
+//! use rocksdb::index::IndexedDB;
+//!
+//! let mut opts = Options::default();
+//! opts.create_if_missing(true);
+//! opts.create_missing_column_families(true);
+//! let db = DB::open_cf(&opts, "path/for/rocksdb/storage_index", &["by_city"]).unwrap();
+//!
+//! // Records are "name:city"; index them by city.
+//! let mut db = IndexedDB::new(db);
+//! db.add_index("by_city", |_: &[u8], value: &[u8]| {
+//!     value.split(|&b| b == b':').nth(1).map(|city| city.to_vec()).into_iter().collect()
+//! }).unwrap();
+//!
+//! db.put(b"1", b"ada:london").unwrap();
+//! db.put(b"2", b"alan:london").unwrap();
+//! db.put(b"3", b"grace:new york").unwrap();
+//! assert_eq!(db.lookup("by_city", b"london").unwrap(), vec![b"1".to_vec(), b"2".to_vec()]);
+//!
+//! db.put(b"2", b"alan:manchester").unwrap();
+//! assert_eq!(db.lookup("by_city", b"london").unwrap(), vec![b"1".to_vec()]);
+//! ```
+
+use std::collections::BTreeSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, MutexGuard};
+
+use {ColumnFamily, DB, Direction, Error, ErrorKind, IteratorMode, WriteBatch};
+
+// The number of locks writes are spread over by primary key.
+const LOCK_STRIPES: usize = 64;
+
+/// Derives the index keys of a record from its primary key and value.
+///
+/// It is called for both the old and the new value on every write, so it
+/// must be deterministic. It must be `Send + Sync` as writes may come from
+/// any thread.
+pub trait IndexFn: Fn(&[u8], &[u8]) -> Vec<Vec<u8>> + Send + Sync + 'static {}
+impl<F> IndexFn for F
+where
+    F: Fn(&[u8], &[u8]) -> Vec<Vec<u8>>,
+    F: Send + Sync + 'static,
+{
+}
+
+struct Index {
+    cf: String,
+    extract: Box<dyn IndexFn>,
+}
+
+/// A record found through an index.
+pub type Record = (Vec<u8>, Vec<u8>);
+
+/// A `DB` whose records are indexed in other column families.
+pub struct IndexedDB {
+    db: DB,
+    indexes: Vec<Index>,
+    locks: Vec<Mutex<()>>,
+}
+
+// An index entry is keyed by the index key, the primary key and the length
+// of the index key as a big-endian `u32`, so that entries of different index
+// and primary keys never collide. Entries of the same index key are ordered
+// by primary key.
+fn entry_key(index_key: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::with_capacity(index_key.len() + key.len() + 4);
+    entry.extend_from_slice(index_key);
+    entry.extend_from_slice(key);
+    let len = index_key.len() as u32;
+    entry.extend_from_slice(&[(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8]);
+    entry
+}
+
+// Splits an index entry into its index and primary key.
+fn split_entry(entry: &[u8]) -> Option<(&[u8], &[u8])> {
+    if entry.len() < 4 {
+        return None;
+    }
+    let (keys, len) = entry.split_at(entry.len() - 4);
+    let len = (len[0] as usize) << 24 | (len[1] as usize) << 16 | (len[2] as usize) << 8 |
+        len[3] as usize;
+    if len > keys.len() {
+        return None;
+    }
+    Some(keys.split_at(len))
+}
+
+impl IndexedDB {
+    pub fn new(db: DB) -> IndexedDB {
+        IndexedDB {
+            db: db,
+            indexes: Vec::new(),
+            locks: (0..LOCK_STRIPES).map(|_| Mutex::new(())).collect(),
+        }
+    }
+
+    /// Returns the underlying database, for reads and for writes to other
+    /// column families.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// Returns the underlying database, giving up the indexes.
+    pub fn into_inner(self) -> DB {
+        self.db
+    }
+
+    /// Maintains an index in the existing column family `cf`, whose entries
+    /// are derived with `extract`. Records written before the index was
+    /// added are only indexed by `rebuild_index`.
+    pub fn add_index<F: IndexFn>(&mut self, cf: &str, extract: F) -> Result<(), Error> {
+        if self.db.cf_handle(cf).is_none() {
+            return Err(Error::with_kind(ErrorKind::InvalidArgument,
+                                        format!("no column family {}", cf)));
+        }
+        if self.indexes.iter().any(|index| index.cf == cf) {
+            return Err(Error::with_kind(ErrorKind::InvalidArgument,
+                                        format!("{} is already an index", cf)));
+        }
+        self.indexes.push(Index {
+            cf: cf.to_owned(),
+            extract: Box::new(extract),
+        });
+        Ok(())
+    }
+
+    fn index_cf(&self, cf: &str) -> Result<(&Index, &ColumnFamily), Error> {
+        match self.indexes.iter().find(|index| index.cf == cf) {
+            Some(index) => Ok((index, self.db.cf_handle(cf).unwrap())),
+            None => Err(Error::with_kind(ErrorKind::InvalidArgument,
+                                         format!("{} is not an index", cf))),
+        }
+    }
+
+    fn lock(&self, key: &[u8]) -> MutexGuard<()> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let stripe = hasher.finish() as usize % self.locks.len();
+        match self.locks[stripe].lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Adds to `batch` the changes of every index when the record `key`
+    /// goes from `old` to `new`.
+    fn update_indexes(
+        &self,
+        batch: &mut WriteBatch,
+        key: &[u8],
+        old: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> Result<(), Error> {
+        for index in &self.indexes {
+            let cf = self.db.cf_handle(&index.cf).unwrap();
+            let old: BTreeSet<_> = old.map_or(Vec::new(), |old| (index.extract)(key, old))
+                .into_iter()
+                .collect();
+            let new: BTreeSet<_> = new.map_or(Vec::new(), |new| (index.extract)(key, new))
+                .into_iter()
+                .collect();
+            for index_key in old.difference(&new) {
+                try!(batch.delete_cf(cf, &entry_key(index_key, key)));
+            }
+            for index_key in new.difference(&old) {
+                try!(batch.put_cf(cf, &entry_key(index_key, key), b""));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a record and updates every index in one batch.
+    pub fn put(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        let _lock = self.lock(key);
+        let old = try!(self.db.get(key));
+        let mut batch = WriteBatch::default();
+        try!(self.update_indexes(&mut batch, key, old.as_ref().map(|v| &**v), Some(value)));
+        try!(batch.put(key, value));
+        self.db.write(batch)
+    }
+
+    /// Deletes a record and its index entries in one batch.
+    pub fn delete(&self, key: &[u8]) -> Result<(), Error> {
+        let _lock = self.lock(key);
+        let old = match try!(self.db.get(key)) {
+            Some(old) => old,
+            None => return Ok(()),
+        };
+        let mut batch = WriteBatch::default();
+        try!(self.update_indexes(&mut batch, key, Some(&old), None));
+        try!(batch.delete(key));
+        self.db.write(batch)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        Ok(try!(self.db.get(key)).map(|v| v.to_vec()))
+    }
+
+    /// Returns the primary keys of the records with the given index key, in
+    /// key order.
+    pub fn lookup(&self, index: &str, index_key: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+        let (_, cf) = try!(self.index_cf(index));
+        let iter = try!(self.db.iterator_cf(cf, IteratorMode::From(index_key, Direction::Forward)));
+        let mut keys = Vec::new();
+        for (entry, _) in iter {
+            if !entry.starts_with(index_key) {
+                break;
+            }
+            // Entries of longer index keys starting with `index_key` are
+            // interleaved with the ones looked for.
+            match split_entry(&entry) {
+                Some((found, key)) if found == index_key => keys.push(key.to_vec()),
+                _ => {}
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Returns the records with the given index key, in key order.
+    pub fn lookup_records(&self, index: &str, index_key: &[u8]) -> Result<Vec<Record>, Error> {
+        let mut records = Vec::new();
+        for key in try!(self.lookup(index, index_key)) {
+            if let Some(value) = try!(self.db.get(&key)) {
+                records.push((key, value.to_vec()));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Recomputes an index from every record, e.g. after it was added to a
+    /// database that already had records. Writes wait until it is done.
+    pub fn rebuild_index(&self, index: &str) -> Result<(), Error> {
+        let (index, cf) = try!(self.index_cf(index));
+        let _locks: Vec<_> = self.locks
+            .iter()
+            .map(|lock| match lock.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            })
+            .collect();
+
+        let mut batch = WriteBatch::default();
+        for (entry, _) in try!(self.db.iterator_cf(cf, IteratorMode::Start)) {
+            try!(batch.delete_cf(cf, &entry));
+        }
+        for (key, value) in self.db.iterator(IteratorMode::Start) {
+            for index_key in (index.extract)(&key, &value) {
+                try!(batch.put_cf(cf, &entry_key(&index_key, &key), b""));
+            }
+        }
+        self.db.write(batch)
+    }
+}
@@ -0,0 +1,393 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Key encodings whose bytewise order, the order of RocksDB's default
+//! comparator, matches the natural order of the encoded values.
+//!
+//! - unsigned integers are stored big-endian, and signed integers with
+//!   their sign bit flipped;
+//! - floats are ordered as by `f64::total_cmp`: `-0.0` sorts before
+//!   `0.0`, and NaNs sort beyond the infinity of their sign;
+//! - strings and byte strings escape `0x00` and are terminated, so that a
+//!   string sorts before any longer string it is a prefix of;
+//! - tuples and options concatenate their fields, so the encoding of the
+//!   first fields of a tuple is a prefix of the encoding of the tuple.
+//!   This allows scanning all keys sharing their first fields.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::{DB, Direction, IteratorMode};
+//! use rocksdb::keys;
+//!
+//! let db = DB::open_default("path/for/rocksdb/storage_keys").unwrap();
+//! db.put(&keys::encode(&("user", 10u32)), b"ten").unwrap();
+//! db.put(&keys::encode(&("user", 9u32)), b"nine").unwrap();
+//! db.put(&keys::encode(&("users", 1u32)), b"other").unwrap();
+//!
+//! let prefix = keys::encode(&("user",));
+//! let ids: Vec<u32> = db.iterator(IteratorMode::From(&prefix, Direction::Forward))
+//!     .take_while(|&(ref k, _)| k.starts_with(&prefix))
+//!     .map(|(k, _)| keys::decode::<(String, u32)>(&k).unwrap().1)
+//!     .collect();
+//! assert_eq!(ids, vec![9, 10]);
+//! ```
+
+use {Error, ErrorKind};
+
+// Escapes and terminator of encoded strings: a 0x00 byte is stored as
+// 0x00 0xff, and the string ends with 0x00 0x01.
+const ESCAPE: u8 = 0x00;
+const ESCAPED_ZERO: u8 = 0xff;
+const TERMINATOR: u8 = 0x01;
+
+fn corrupt(what: &str) -> Error {
+    Error::with_kind(ErrorKind::Corruption, format!("invalid key encoding of {}", what))
+}
+
+/// A value that can be encoded as an order-preserving key.
+pub trait KeyEncode {
+    /// Appends the encoding of `self` to `out`.
+    fn encode_key(&self, out: &mut Vec<u8>);
+}
+
+/// A value that can be decoded from its order-preserving key encoding.
+pub trait KeyDecode: Sized {
+    /// Decodes a value from the start of `input`, advancing it past the
+    /// bytes read.
+    fn decode_key(input: &mut &[u8]) -> Result<Self, Error>;
+}
+
+/// Returns the order-preserving encoding of `value`.
+pub fn encode<T: KeyEncode + ?Sized>(value: &T) -> Vec<u8> {
+    let mut out = Vec::new();
+    value.encode_key(&mut out);
+    out
+}
+
+/// Decodes a value from the whole of `bytes`.
+pub fn decode<T: KeyDecode>(mut bytes: &[u8]) -> Result<T, Error> {
+    let value = try!(T::decode_key(&mut bytes));
+    if !bytes.is_empty() {
+        return Err(Error::with_kind(ErrorKind::Corruption, "trailing bytes after key".to_owned()));
+    }
+    Ok(value)
+}
+
+/// Returns the smallest key that sorts after every key starting with
+/// `prefix`, to be used as the exclusive end of a prefix scan, or `None` if
+/// the prefix is empty or made of `0xff` bytes only, in which case every key
+/// from the prefix on starts with it.
+///
+/// ```
+/// use rocksdb::keys;
+///
+/// assert_eq!(keys::prefix_successor(b"ab"), Some(b"ac".to_vec()));
+/// assert_eq!(keys::prefix_successor(b"a\xff\xff"), Some(b"b".to_vec()));
+/// assert_eq!(keys::prefix_successor(b"\xff"), None);
+/// ```
+pub fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let len = match prefix.iter().rposition(|&b| b != 0xff) {
+        Some(i) => i + 1,
+        None => return None,
+    };
+    let mut end = prefix[..len].to_vec();
+    end[len - 1] += 1;
+    Some(end)
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize, what: &str) -> Result<&'a [u8], Error> {
+    if input.len() < n {
+        return Err(corrupt(what));
+    }
+    let (bytes, rest) = input.split_at(n);
+    *input = rest;
+    Ok(bytes)
+}
+
+macro_rules! unsigned_key {
+    ($t:ty, $n:expr) => {
+        impl KeyEncode for $t {
+            fn encode_key(&self, out: &mut Vec<u8>) {
+                for i in (0..$n).rev() {
+                    out.push((*self >> (i * 8)) as u8);
+                }
+            }
+        }
+
+        impl KeyDecode for $t {
+            fn decode_key(input: &mut &[u8]) -> Result<$t, Error> {
+                let bytes = try!(take(input, $n, stringify!($t)));
+                Ok(bytes.iter().fold(0u64, |v, &b| (v << 8) | b as u64) as $t)
+            }
+        }
+    }
+}
+
+unsigned_key!(u8, 1);
+unsigned_key!(u16, 2);
+unsigned_key!(u32, 4);
+unsigned_key!(u64, 8);
+
+macro_rules! signed_key {
+    ($t:ty, $u:ty) => {
+        impl KeyEncode for $t {
+            fn encode_key(&self, out: &mut Vec<u8>) {
+                // Flipping the sign bit orders negative values first.
+                ((*self as $u) ^ (1 << (8 * ::std::mem::size_of::<$u>() - 1))).encode_key(out)
+            }
+        }
+
+        impl KeyDecode for $t {
+            fn decode_key(input: &mut &[u8]) -> Result<$t, Error> {
+                let v = try!(<$u>::decode_key(input));
+                Ok((v ^ (1 << (8 * ::std::mem::size_of::<$u>() - 1))) as $t)
+            }
+        }
+    }
+}
+
+signed_key!(i8, u8);
+signed_key!(i16, u16);
+signed_key!(i32, u32);
+signed_key!(i64, u64);
+
+macro_rules! float_key {
+    ($t:ty, $u:ty) => {
+        impl KeyEncode for $t {
+            fn encode_key(&self, out: &mut Vec<u8>) {
+                // Negative values have all bits flipped so that larger
+                // magnitudes sort first, positive ones only their sign bit.
+                let bits = self.to_bits();
+                let sign = 1 << (8 * ::std::mem::size_of::<$u>() - 1);
+                let bits = if bits & sign != 0 { !bits } else { bits | sign };
+                bits.encode_key(out)
+            }
+        }
+
+        impl KeyDecode for $t {
+            fn decode_key(input: &mut &[u8]) -> Result<$t, Error> {
+                let bits = try!(<$u>::decode_key(input));
+                let sign = 1 << (8 * ::std::mem::size_of::<$u>() - 1);
+                let bits = if bits & sign != 0 { bits & !sign } else { !bits };
+                Ok(<$t>::from_bits(bits))
+            }
+        }
+    }
+}
+
+float_key!(f32, u32);
+float_key!(f64, u64);
+
+impl KeyEncode for bool {
+    fn encode_key(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl KeyDecode for bool {
+    fn decode_key(input: &mut &[u8]) -> Result<bool, Error> {
+        match try!(take(input, 1, "bool"))[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(corrupt("bool")),
+        }
+    }
+}
+
+impl KeyEncode for [u8] {
+    fn encode_key(&self, out: &mut Vec<u8>) {
+        for &b in self {
+            out.push(b);
+            if b == ESCAPE {
+                out.push(ESCAPED_ZERO);
+            }
+        }
+        out.push(ESCAPE);
+        out.push(TERMINATOR);
+    }
+}
+
+impl KeyEncode for Vec<u8> {
+    fn encode_key(&self, out: &mut Vec<u8>) {
+        self[..].encode_key(out)
+    }
+}
+
+impl KeyDecode for Vec<u8> {
+    fn decode_key(input: &mut &[u8]) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::new();
+        loop {
+            let b = try!(take(input, 1, "bytes"))[0];
+            if b != ESCAPE {
+                bytes.push(b);
+                continue;
+            }
+            match try!(take(input, 1, "bytes"))[0] {
+                ESCAPED_ZERO => bytes.push(ESCAPE),
+                TERMINATOR => return Ok(bytes),
+                _ => return Err(corrupt("bytes")),
+            }
+        }
+    }
+}
+
+impl KeyEncode for str {
+    fn encode_key(&self, out: &mut Vec<u8>) {
+        self.as_bytes().encode_key(out)
+    }
+}
+
+impl KeyEncode for String {
+    fn encode_key(&self, out: &mut Vec<u8>) {
+        self.as_bytes().encode_key(out)
+    }
+}
+
+impl KeyDecode for String {
+    fn decode_key(input: &mut &[u8]) -> Result<String, Error> {
+        String::from_utf8(try!(Vec::decode_key(input))).map_err(|_| corrupt("string"))
+    }
+}
+
+impl<'a, T: KeyEncode + ?Sized> KeyEncode for &'a T {
+    fn encode_key(&self, out: &mut Vec<u8>) {
+        (**self).encode_key(out)
+    }
+}
+
+/// `None` sorts before every `Some`.
+impl<T: KeyEncode> KeyEncode for Option<T> {
+    fn encode_key(&self, out: &mut Vec<u8>) {
+        match *self {
+            None => out.push(0),
+            Some(ref v) => {
+                out.push(1);
+                v.encode_key(out);
+            }
+        }
+    }
+}
+
+impl<T: KeyDecode> KeyDecode for Option<T> {
+    fn decode_key(input: &mut &[u8]) -> Result<Option<T>, Error> {
+        match try!(take(input, 1, "option"))[0] {
+            0 => Ok(None),
+            1 => Ok(Some(try!(T::decode_key(input)))),
+            _ => Err(corrupt("option")),
+        }
+    }
+}
+
+macro_rules! tuple_key {
+    ($($name:ident)+) => {
+        impl<$($name: KeyEncode),+> KeyEncode for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode_key(&self, out: &mut Vec<u8>) {
+                let ($(ref $name,)+) = *self;
+                $($name.encode_key(out);)+
+            }
+        }
+
+        impl<$($name: KeyDecode),+> KeyDecode for ($($name,)+) {
+            fn decode_key(input: &mut &[u8]) -> Result<($($name,)+), Error> {
+                Ok(($(try!($name::decode_key(input)),)+))
+            }
+        }
+    }
+}
+
+tuple_key!(A);
+tuple_key!(A B);
+tuple_key!(A B C);
+tuple_key!(A B C D);
+tuple_key!(A B C D E);
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyDecode, KeyEncode, decode, encode, prefix_successor};
+    use std::fmt::Debug;
+
+    // Checks that `values`, given in increasing order, encode to increasing
+    // keys and decode back.
+    fn check_order<T: KeyEncode + KeyDecode + Debug + PartialEq>(values: &[T]) {
+        let keys: Vec<Vec<u8>> = values.iter().map(|v| encode(v)).collect();
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(&decode::<T>(key).unwrap(), &values[i]);
+            if i > 0 {
+                assert!(keys[i - 1] < *key, "{:?} >= {:?}", values[i - 1], values[i]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_integers() {
+        check_order(&[0u8, 1, 127, 255]);
+        check_order(&[0u32, 1, 256, 65_536, u32::max_value()]);
+        check_order(&[i64::min_value(), -256, -1, 0, 1, 255, i64::max_value()]);
+        check_order(&[i8::min_value(), -1, 0, i8::max_value()]);
+        assert_eq!(encode(&258u16), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_floats() {
+        use std::f64::{INFINITY, NEG_INFINITY};
+        check_order(&[NEG_INFINITY, -1e10, -1.5, -0.0, 0.0, 1e-10, 2.5, INFINITY]);
+        check_order(&[-1.0f32, 0.0, 1.0]);
+        assert!(decode::<f64>(&encode(&::std::f64::NAN)).unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_strings() {
+        check_order(&["".to_owned(), "a".to_owned(), "a\0".to_owned(), "a\0b".to_owned(),
+                      "ab".to_owned(), "b".to_owned()]);
+        check_order(&[vec![], vec![0u8], vec![0, 0], vec![0, 1], vec![1], vec![255]]);
+        assert_eq!(encode("a"), encode(&"a".to_owned()));
+        assert!(decode::<String>(&encode(&vec![0xffu8])).is_err());
+    }
+
+    #[test]
+    fn test_tuples() {
+        check_order(&[
+            ("a".to_owned(), 2u32),
+            ("a".to_owned(), 10),
+            ("ab".to_owned(), 1),
+            ("b".to_owned(), 0),
+        ]);
+        check_order(&[(None, false), (Some(-1i32), true), (Some(0), false)]);
+        assert!(encode(&("a", 1u8, true)).starts_with(&encode(&("a", 1u8))));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(decode::<u32>(&[1, 2, 3]).is_err());
+        assert!(decode::<u8>(&[1, 2]).is_err());
+        assert!(decode::<Vec<u8>>(b"abc").is_err());
+        assert!(decode::<Vec<u8>>(&[0, 2]).is_err());
+        assert!(decode::<bool>(&[2]).is_err());
+    }
+
+    #[test]
+    fn test_prefix_successor() {
+        assert_eq!(prefix_successor(b"a"), Some(b"b".to_vec()));
+        assert_eq!(prefix_successor(&[1, 0xfe, 0xff]), Some(vec![1, 0xff]));
+        assert_eq!(prefix_successor(&[0xff, 0xff]), None);
+        assert_eq!(prefix_successor(b""), None);
+
+        let prefix = encode(&("user",));
+        let end = prefix_successor(&prefix).unwrap();
+        assert!(encode(&("user", 10u32)) < end);
+        assert!(encode(&("users", 1u32)) >= end);
+    }
+}
@@ -46,40 +46,131 @@
 //! ```
 //!
 
+#[cfg(feature = "arrow")]
+extern crate arrow;
+#[cfg(feature = "typed")]
+extern crate bincode;
+#[cfg(feature = "bytes")]
+extern crate bytes;
 extern crate libc;
 extern crate librocksdb_sys as ffi;
+#[cfg(feature = "metrics")]
+#[macro_use]
+extern crate metrics;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "typed")]
+extern crate serde;
+#[cfg(feature = "config")]
+extern crate serde_yaml;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "config")]
+extern crate toml;
+#[cfg(feature = "tracing")]
+#[macro_use]
+extern crate tracing;
 
 #[macro_use]
 mod ffi_util;
 
+pub mod admin;
+#[cfg(feature = "arrow")]
+pub mod arrow_export;
+#[cfg(feature = "tokio")]
+pub mod async_db;
 pub mod backup;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "bytes")]
+mod bytes_interop;
+mod chunk_scan;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod copy;
+pub mod dump;
+#[cfg(feature = "metrics")]
+pub mod metrics_exporter;
 mod comparator;
 pub mod merge_operator;
 pub mod compaction_filter;
 mod db;
 mod db_options;
+mod env;
+pub mod index;
+pub mod keys;
+mod memory_usage;
+#[cfg(feature = "rayon")]
+pub mod par_scan;
+pub mod property_watcher;
+pub mod remote_backup;
+pub mod replication;
+mod retry;
 mod slice_transform;
+pub mod statistics;
+#[cfg(feature = "typed")]
+pub mod typed;
 
 pub use compaction_filter::Decision as CompactionDecision;
-pub use db::{DBCompactionStyle, DBCompressionType, DBIterator, DBRawIterator, DBRecoveryMode,
-             DBVector, ReadOptions, Direction, IteratorMode, Snapshot, WriteBatch,
-             new_bloom_filter};
+pub use comparator::ComparatorFn;
+pub use db::{BatchIterator, BlockBasedIndexType, DBCompactionStyle, DBCompressionType, DBIterator,
+             DBRawIterator, DBRecoveryMode, DBVector, ReadOptions, Direction, IteratorMode, LiveFile,
+             MemtableFactory, OpenOptions, Snapshot, StorageClass, UniversalCompactionStopStyle,
+             WalIterator, WriteBatch, WriteBatchWithIndex, new_bloom_filter, version};
 
-pub use slice_transform::SliceTransform;
+#[cfg(feature = "bytes")]
+pub use bytes_interop::BytesIterator;
+pub use chunk_scan::{Chunk, ChunkIter, ChunkScan};
+pub use env::ThreadLimiter;
+pub use memory_usage::{MemoryUsage, get_approximate_memory_usage};
+pub use retry::{Backoff, retry};
+pub use slice_transform::{SliceTransformFns, validate_slice_transform};
 
 pub use merge_operator::MergeOperands;
 use std::collections::BTreeMap;
 use std::error;
 use std::fmt;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicUsize;
 
 /// A RocksDB database.
 ///
 /// See crate level documentation for a simple usage example.
+///
+/// `DB` is `Send` and `Sync`: reads and writes take `&self`, and RocksDB
+/// synchronizes them internally, so a database can be shared between
+/// threads by wrapping it in an `Arc` without any extra locking. Only
+/// creating and dropping column families needs `&mut self`.
+///
+/// ```
+/// use rocksdb::DB;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let db = Arc::new(DB::open_default("path/for/rocksdb/storage_shared").unwrap());
+/// let handles: Vec<_> = (0..4).map(|i| {
+///     let db = db.clone();
+///     thread::spawn(move || db.put(format!("key{}", i).as_bytes(), b"value").unwrap())
+/// }).collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// ```
 pub struct DB {
     inner: *mut ffi::rocksdb_t,
     cfs: BTreeMap<String, ColumnFamily>,
     path: PathBuf,
+    _env: Option<Env>,
+    // The number of iterators not yet dropped; see `DB::close`.
+    live_iterators: Arc<AtomicUsize>,
+    // Shared by the methods without an options argument, so that each call
+    // does not create and destroy its own.
+    default_readopts: ReadOptions,
+    default_writeopts: WriteOptions,
+    // Serialize the `DB::update` and `DB::compare_and_swap` writes on keys
+    // hashing to the same stripe.
+    update_locks: Vec<Mutex<()>>,
 }
 
 /// A descriptor for a RocksDB column family.
@@ -90,16 +181,84 @@ pub struct ColumnFamilyDescriptor {
     options: Options,
 }
 
-/// A simple wrapper round a string, used for errors reported from
-/// ffi calls.
+/// The category of an `Error`, mirroring the status codes used by RocksDB.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    NotFound,
+    Corruption,
+    NotSupported,
+    InvalidArgument,
+    IOError,
+    MergeInProgress,
+    Incomplete,
+    ShutdownInProgress,
+    TimedOut,
+    Aborted,
+    Busy,
+    Expired,
+    TryAgain,
+    /// An error whose kind could not be determined from its message.
+    Unknown,
+}
+
+/// An error reported from ffi calls or by this crate, made up of a message
+/// and its `ErrorKind`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Error {
     message: String,
+    kind: ErrorKind,
 }
 
 impl Error {
     fn new(message: String) -> Error {
-        Error { message: message }
+        let kind = ErrorKind::from_message(&message);
+        Error {
+            message: message,
+            kind: kind,
+        }
+    }
+
+    /// Creates an error of the given kind, e.g. for an `ObjectStore` or other
+    /// code outside the crate reporting a failure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::{Error, ErrorKind};
+    ///
+    /// let err = Error::with_kind(ErrorKind::NotFound, "no such object".to_owned());
+    /// assert_eq!(err.kind(), ErrorKind::NotFound);
+    /// assert_eq!(err.to_string(), "no such object");
+    /// ```
+    pub fn with_kind(kind: ErrorKind, message: String) -> Error {
+        Error {
+            message: message,
+            kind: kind,
+        }
+    }
+
+    /// Returns the category of this error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rocksdb::{DB, ErrorKind, Options};
+    ///
+    /// let err = DB::open(&Options::default(), "path/that/does/not/exist").unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns true if the failed operation may succeed when retried, i.e.
+    /// for `Busy`, `TimedOut`, `TryAgain` and `Incomplete` errors.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind {
+            ErrorKind::Busy | ErrorKind::TimedOut | ErrorKind::TryAgain |
+            ErrorKind::Incomplete => true,
+            _ => false,
+        }
     }
 
     pub fn to_string(self) -> String {
@@ -107,6 +266,33 @@ impl Error {
     }
 }
 
+impl ErrorKind {
+    // Matches the prefixes written by rocksdb::Status::ToString().
+    fn from_message(message: &str) -> ErrorKind {
+        let prefixes = [
+            ("NotFound", ErrorKind::NotFound),
+            ("Corruption", ErrorKind::Corruption),
+            ("Not implemented", ErrorKind::NotSupported),
+            ("Invalid argument", ErrorKind::InvalidArgument),
+            ("IO error", ErrorKind::IOError),
+            ("Merge in progress", ErrorKind::MergeInProgress),
+            ("Result incomplete", ErrorKind::Incomplete),
+            ("Shutdown in progress", ErrorKind::ShutdownInProgress),
+            ("Operation timed out", ErrorKind::TimedOut),
+            ("Operation aborted", ErrorKind::Aborted),
+            ("Resource busy", ErrorKind::Busy),
+            ("Operation expired", ErrorKind::Expired),
+            ("Operation failed. Try again.", ErrorKind::TryAgain),
+        ];
+        for &(prefix, kind) in prefixes.iter() {
+            if message.starts_with(prefix) {
+                return kind;
+            }
+        }
+        ErrorKind::Unknown
+    }
+}
+
 impl AsRef<str> for Error {
     fn as_ref(&self) -> &str {
         &self.message
@@ -136,6 +322,66 @@ pub struct BlockBasedOptions {
     inner: *mut ffi::rocksdb_block_based_table_options_t,
 }
 
+/// For configuring cuckoo hash based file storage, used with
+/// `Options::set_cuckoo_table_factory`.
+pub struct CuckooTableOptions {
+    inner: *mut ffi::rocksdb_cuckoo_table_options_t,
+}
+
+/// For configuring universal style compaction, used with
+/// `Options::set_universal_compaction_options`.
+pub struct UniversalCompactOptions {
+    inner: *mut ffi::rocksdb_universal_compaction_options_t,
+}
+
+/// For configuring FIFO style compaction, used with
+/// `Options::set_fifo_compaction_options`.
+pub struct FifoCompactOptions {
+    inner: *mut ffi::rocksdb_fifo_compaction_options_t,
+}
+
+/// A SliceTranform is a generic pluggable way of transforming one string
+/// to another. Its primary use-case is in configuring rocksdb
+/// to store prefix blooms by setting prefix_extractor in
+/// ColumnFamilyOptions.
+///
+/// A transform is owned by the `Options` it is passed to through
+/// `Options::set_prefix_extractor`, and is freed when dropped otherwise.
+pub struct SliceTransform {
+    pub inner: *mut ffi::rocksdb_slicetransform_t,
+}
+
+/// For configuring the plain table file format, used with
+/// `Options::set_plain_table_factory`.
+///
+/// See the [PlainTable format wiki page](https://github.com/facebook/rocksdb/wiki/PlainTable-Format)
+/// for details on each field.
+pub struct PlainTableOptions {
+    /// Fixed length of every user key, or `0` for variable length keys.
+    pub user_key_length: u32,
+    /// Bits per key used for the prefix bloom filter, or `0` to disable it.
+    pub bloom_bits_per_key: i32,
+    /// Desired utilization of the prefix hash table; `0` disables hashing
+    /// and uses binary search over the index instead.
+    pub hash_table_ratio: f64,
+    /// Number of keys per prefix between consecutive index records.
+    pub index_sparseness: usize,
+}
+
+/// Tuning parameters for the compression library, used with
+/// `Options::set_compression_options`.
+pub struct CompressionOptions {
+    /// zlib window size, as passed to `deflateInit2`.
+    pub window_bits: i32,
+    /// Compression level, or `-1` to use the library's default level.
+    pub level: i32,
+    /// zlib compression strategy.
+    pub strategy: i32,
+    /// Maximum size of the dictionary sampled from each SST file, or `0` to
+    /// compress without a dictionary.
+    pub max_dict_bytes: i32,
+}
+
 /// Database-wide options around performance and behavior.
 ///
 /// Please read [the official tuning guide](https://github.com/facebook/rocksdb/wiki/RocksDB-Tuning-Guide), and most importantly, measure performance under realistic workloads with realistic hardware.
@@ -171,6 +417,36 @@ pub struct BlockBasedOptions {
 /// ```
 pub struct Options {
     inner: *mut ffi::rocksdb_options_t,
+    env: Option<Env>,
+}
+
+/// The interface RocksDB uses to access operating system functionality,
+/// such as the filesystem and the background thread pools that run
+/// flushes and compactions.
+///
+/// An `Env` is shared by every database whose options reference it via
+/// `Options::set_env`, and is kept alive for as long as any of them is
+/// open. Cloning an `Env` returns another handle to the same environment.
+///
+/// # Examples
+///
+/// ```
+/// use rocksdb::{DB, Env, Options};
+///
+/// let mut env = Env::default();
+/// env.set_background_threads(4);
+/// env.set_high_priority_background_threads(2);
+///
+/// let mut opts = Options::default();
+/// opts.create_if_missing(true);
+/// opts.set_env(&env);
+/// opts.set_max_background_jobs(6);
+///
+/// let db = DB::open(&opts, "path/for/rocksdb/storage_with_env").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Env {
+    inner: Arc<env::EnvHandle>,
 }
 
 /// Optionally disable WAL or sync for this write.
@@ -200,9 +476,10 @@ pub struct WriteOptions {
 }
 
 
-/// An opaque type used to represent a column family. Returned from some functions, and used
-/// in others
-#[derive(Copy, Clone)]
+/// An opaque type used to represent a column family. Handles are owned by the
+/// `DB` and borrowed through `DB::cf_handle`, so the borrow checker ensures a
+/// handle is not used after its column family is dropped or the database is
+/// closed.
 pub struct ColumnFamily {
     inner: *mut ffi::rocksdb_column_family_handle_t,
 }
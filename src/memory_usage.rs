@@ -0,0 +1,77 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use {DB, Error};
+
+/// Approximate memory usage, in bytes, of a set of databases, as computed
+/// by `get_approximate_memory_usage`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MemoryUsage {
+    /// Memory used by all memtables, including ones waiting to be flushed
+    /// or pinned by iterators.
+    pub mem_table_total: u64,
+    /// Memory used by memtables that have not been flushed yet.
+    pub mem_table_unflushed: u64,
+    /// Memory used by table readers, such as index and filter blocks that
+    /// are not held in the block cache.
+    pub mem_table_readers_total: u64,
+    /// Memory used by the block caches of the databases. A cache shared
+    /// between databases is counted once for each of them.
+    pub cache_total: u64,
+}
+
+// Sums a numeric property over the column families of `db`.
+fn sum_cf_property(db: &DB, name: &str) -> Result<u64, Error> {
+    let mut total = 0;
+    // The default column family is only in `cfs` if it was opened by name.
+    if !db.cfs.contains_key("default") {
+        total += try!(db.property_int_value(name)).unwrap_or(0);
+    }
+    for cf in db.cfs.values() {
+        total += try!(db.property_int_value_cf(cf, name)).unwrap_or(0);
+    }
+    Ok(total)
+}
+
+/// Returns the combined memory usage of `dbs`, broken down by type.
+///
+/// The usage is read from the memtable, table reader and block cache
+/// properties of each database, as the bundled RocksDB has no memory usage
+/// API of its own.
+///
+/// # Examples
+///
+/// ```
+/// use rocksdb::{DB, get_approximate_memory_usage};
+///
+/// let db = DB::open_default("path/for/rocksdb/storage_memory_usage").unwrap();
+/// let usage = get_approximate_memory_usage(&[&db]).unwrap();
+/// println!("memtables: {} bytes", usage.mem_table_total);
+/// ```
+pub fn get_approximate_memory_usage(dbs: &[&DB]) -> Result<MemoryUsage, Error> {
+    let mut usage = MemoryUsage {
+        mem_table_total: 0,
+        mem_table_unflushed: 0,
+        mem_table_readers_total: 0,
+        cache_total: 0,
+    };
+    for db in dbs {
+        usage.mem_table_total += try!(sum_cf_property(db, "rocksdb.size-all-mem-tables"));
+        usage.mem_table_unflushed += try!(sum_cf_property(db, "rocksdb.cur-size-all-mem-tables"));
+        usage.mem_table_readers_total +=
+            try!(sum_cf_property(db, "rocksdb.estimate-table-readers-mem"));
+        usage.cache_total += try!(db.property_int_value("rocksdb.block-cache-usage")).unwrap_or(0);
+    }
+    Ok(usage)
+}
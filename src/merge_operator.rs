@@ -60,24 +60,90 @@ use std::mem;
 use std::ptr;
 use std::slice;
 
+use ffi_util::catch_callback_panic;
+
 pub type MergeFn = fn(&[u8], Option<&[u8]>, &mut MergeOperands) -> Option<Vec<u8>>;
 
-pub struct MergeOperatorCallback {
+/// A merge function, taking the key, the existing value if any and the
+/// operands to merge, and returning the merged value or `None` on failure.
+///
+/// Merge functions are called concurrently from reads and background
+/// compactions, so they must be `Send + Sync`.
+pub trait MergeOperatorFn
+	: Fn(&[u8], Option<&[u8]>, &mut MergeOperands) -> Option<Vec<u8>> + Send + Sync + 'static {
+}
+impl<F> MergeOperatorFn for F
+where
+	F: Fn(&[u8], Option<&[u8]>, &mut MergeOperands) -> Option<Vec<u8>>,
+	F: Send + Sync + 'static,
+{
+}
+
+/// Returns a merge function appending each operand to the existing value,
+/// with `delimiter` between consecutive parts. It is both a valid full and
+/// partial merge function; `Options::set_append_merge_operator` sets it up.
+pub fn append<D: Into<Vec<u8>>>(delimiter: D) -> impl MergeOperatorFn + Clone {
+	let delimiter = delimiter.into();
+	move |_: &[u8], existing: Option<&[u8]>, operands: &mut MergeOperands| {
+		let mut result = Vec::new();
+		let mut first = true;
+		if let Some(v) = existing {
+			result.extend_from_slice(v);
+			first = false;
+		}
+		for op in operands {
+			if !first {
+				result.extend_from_slice(&delimiter);
+			}
+			result.extend_from_slice(op);
+			first = false;
+		}
+		Some(result)
+	}
+}
+
+pub struct MergeOperatorCallback<F: MergeOperatorFn, PF: MergeOperatorFn> {
 	pub name: CString,
-	pub full_merge_fn: MergeFn,
-	pub partial_merge_fn: MergeFn,
+	pub full_merge_fn: F,
+	pub partial_merge_fn: PF,
 }
 
-pub unsafe extern "C" fn destructor_callback(raw_cb: *mut c_void) {
-	let _: Box<MergeOperatorCallback> = mem::transmute(raw_cb);
+pub unsafe extern "C" fn destructor_callback<F: MergeOperatorFn, PF: MergeOperatorFn>(
+	raw_cb: *mut c_void,
+) {
+	let cb: Box<MergeOperatorCallback<F, PF>> = mem::transmute(raw_cb);
+	catch_callback_panic("merge operator destructor", || drop(cb));
 }
 
-pub unsafe extern "C" fn name_callback(raw_cb: *mut c_void) -> *const c_char {
-	let cb = &mut *(raw_cb as *mut MergeOperatorCallback);
+pub unsafe extern "C" fn name_callback<F: MergeOperatorFn, PF: MergeOperatorFn>(
+	raw_cb: *mut c_void,
+) -> *const c_char {
+	let cb = &*(raw_cb as *const MergeOperatorCallback<F, PF>);
 	cb.name.as_ptr()
 }
 
-pub unsafe extern "C" fn full_merge_callback(
+// Copies a merge result into a buffer rocksdb frees with free().
+unsafe fn merge_result(
+	result: Option<Option<Vec<u8>>>,
+	success: *mut u8,
+	new_value_length: *mut size_t,
+	) -> *mut c_char {
+	if let Some(mut result) = result.and_then(|r| r) {
+		result.shrink_to_fit();
+		// TODO(tan) investigate zero-copy techniques to improve performance
+		let buf = libc::malloc(result.len() as size_t);
+		assert!(!buf.is_null());
+		*new_value_length = result.len() as size_t;
+		*success = 1 as u8;
+		ptr::copy(result.as_ptr() as *mut c_void, &mut *buf, result.len());
+		buf as *mut c_char
+	} else {
+		*success = 0 as u8;
+		ptr::null_mut::<c_char>()
+	}
+}
+
+pub unsafe extern "C" fn full_merge_callback<F: MergeOperatorFn, PF: MergeOperatorFn>(
 	raw_cb: *mut c_void,
 	raw_key: *const c_char,
 	key_len: size_t,
@@ -89,7 +155,7 @@ pub unsafe extern "C" fn full_merge_callback(
 	success: *mut u8,
 	new_value_length: *mut size_t,
 	) -> *mut c_char {
-	let cb = &mut *(raw_cb as *mut MergeOperatorCallback);
+	let cb = &*(raw_cb as *const MergeOperatorCallback<F, PF>);
 	let operands = &mut MergeOperands::new(operands_list, operands_list_len, num_operands);
 	let key = slice::from_raw_parts(raw_key as *const u8, key_len as usize);
 	let oldval = 
@@ -98,22 +164,12 @@ pub unsafe extern "C" fn full_merge_callback(
 		} else {
 			Some(slice::from_raw_parts(existing_value as *const u8, existing_value_len as usize))
 		};
-	if let Some(mut result) = (cb.full_merge_fn)(key, oldval, operands) {
-		result.shrink_to_fit();
-		// TODO(tan) investigate zero-copy techniques to improve performance
-		let buf = libc::malloc(result.len() as size_t);
-		assert!(!buf.is_null());
-		*new_value_length = result.len() as size_t;
-		*success = 1 as u8;
-		ptr::copy(result.as_ptr() as *mut c_void, &mut *buf, result.len());
-		buf as *mut c_char
-	} else {
-		*success = 0 as u8;
-		ptr::null_mut() as *mut c_char
-	}
+	// A panic fails the merge, which rocksdb reports as corruption.
+	let result = catch_callback_panic("full merge", || (cb.full_merge_fn)(key, oldval, operands));
+	merge_result(result, success, new_value_length)
 }
 
-pub unsafe extern "C" fn partial_merge_callback(
+pub unsafe extern "C" fn partial_merge_callback<F: MergeOperatorFn, PF: MergeOperatorFn>(
 	raw_cb: *mut c_void,
 	raw_key: *const c_char,
 	key_len: size_t,
@@ -123,22 +179,11 @@ pub unsafe extern "C" fn partial_merge_callback(
 	success: *mut u8,
 	new_value_length: *mut size_t,
 	) -> *mut c_char {
-	let cb = &mut *(raw_cb as *mut MergeOperatorCallback);
+	let cb = &*(raw_cb as *const MergeOperatorCallback<F, PF>);
 	let operands = &mut MergeOperands::new(operands_list, operands_list_len, num_operands);
 	let key = slice::from_raw_parts(raw_key as *const u8, key_len as usize);
-	if let Some(mut result) = (cb.partial_merge_fn)(key, None, operands) {
-		result.shrink_to_fit();
-		// TODO(tan) investigate zero-copy techniques to improve performance
-		let buf = libc::malloc(result.len() as size_t);
-		assert!(!buf.is_null());
-		*new_value_length = result.len() as size_t;
-		*success = 1 as u8;
-		ptr::copy(result.as_ptr() as *mut c_void, &mut *buf, result.len());
-		buf as *mut c_char
-	} else {
-		*success = 0 as u8;
-		ptr::null_mut::<c_char>()
-	}
+	let result = catch_callback_panic("partial merge", || (cb.partial_merge_fn)(key, None, operands));
+	merge_result(result, success, new_value_length)
 }
 
 
@@ -258,6 +303,81 @@ mod test {
 		assert!(DB::destroy(&opts, path).is_ok());
 	}
 
+#[test]
+	fn closure_mergetest() {
+		use {DB, Options};
+
+		let path = "_rust_rocksdb_closure_mergetest";
+		let delimiter = b",".to_vec();
+		let join = move |_: &[u8], existing: Option<&[u8]>, operands: &mut MergeOperands| {
+			let mut result = existing.map(|v| v.to_vec()).unwrap_or_default();
+			for op in operands {
+				if !result.is_empty() {
+					result.extend_from_slice(&delimiter);
+				}
+				result.extend_from_slice(op);
+			}
+			Some(result)
+		};
+		let mut opts = Options::default();
+		opts.create_if_missing(true);
+		opts.set_merge_operator_fns("join operator", join.clone(), join);
+		{
+			let db = DB::open(&opts, path).unwrap();
+			assert!(db.put(b"k1", b"a").is_ok());
+			assert!(db.merge(b"k1", b"b").is_ok());
+			assert!(db.merge(b"k1", b"c").is_ok());
+			assert_eq!(db.get(b"k1").unwrap().unwrap().to_utf8(), Some("a,b,c"));
+		}
+		assert!(DB::destroy(&opts, path).is_ok());
+	}
+
+#[test]
+	fn append_mergetest() {
+		use {DB, Options};
+
+		let path = "_rust_rocksdb_append_mergetest";
+		let mut opts = Options::default();
+		opts.create_if_missing(true);
+		opts.set_append_merge_operator(b", ");
+		{
+			let db = DB::open(&opts, path).unwrap();
+			assert!(db.append(b"k1", b"a").is_ok());
+			assert!(db.append(b"k1", b"").is_ok());
+			assert!(db.append(b"k1", b"c").is_ok());
+			assert_eq!(db.get(b"k1").unwrap().unwrap().to_utf8(), Some("a, , c"));
+			db.compact_range(None, None);
+			assert!(db.append(b"k1", b"d").is_ok());
+			assert_eq!(db.get(b"k1").unwrap().unwrap().to_utf8(), Some("a, , c, d"));
+		}
+		assert!(DB::destroy(&opts, path).is_ok());
+	}
+
+	fn test_panicking_merge(
+		_new_key: &[u8],
+		_existing_val: Option<&[u8]>,
+		_operands: &mut MergeOperands,
+		) -> Option<Vec<u8>> {
+		panic!("merge failed");
+	}
+
+#[test]
+	fn panicking_mergetest() {
+		use {DB, ErrorKind, Options};
+
+		let path = "_rust_rocksdb_panicking_mergetest";
+		let mut opts = Options::default();
+		opts.create_if_missing(true);
+		opts.set_merge_operator("panicking operator", test_panicking_merge, None);
+		{
+			let db = DB::open(&opts, path).unwrap();
+			assert!(db.put(b"k1", b"a").is_ok());
+			assert!(db.merge(b"k1", b"b").is_ok());
+			assert_eq!(db.get(b"k1").err().unwrap().kind(), ErrorKind::Corruption);
+		}
+		assert!(DB::destroy(&opts, path).is_ok());
+	}
+
 	unsafe fn to_slice<T: Sized>(p: &T) -> &[u8] {
 		::std::slice::from_raw_parts(
 			(p as *const T) as *const u8,
@@ -0,0 +1,126 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Publishes RocksDB statistics through the `metrics` crate, available with
+//! the `metrics` feature.
+//!
+//! Any `metrics` recorder can be installed to collect them, for example
+//! `metrics-exporter-prometheus` to serve them to Prometheus. Metric names
+//! are the RocksDB ticker and histogram names with `.` replaced by `_`:
+//!
+//! - every ticker becomes a counter, e.g. `rocksdb_block_cache_miss`;
+//! - every histogram becomes a gauge per percentile, labelled with
+//!   `quantile`, plus `_count` and `_sum` counters;
+//! - every property sampled by a `PropertyWatcher` becomes a gauge, with
+//!   `-` also replaced by `_`, e.g. `rocksdb_estimate_num_keys`, when
+//!   passed to `record_sample`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::{DB, Options};
+//! use rocksdb::metrics_exporter::Exporter;
+//! use std::time::Duration;
+//!
+//! let mut opts = Options::default();
+//! opts.create_if_missing(true);
+//! opts.enable_statistics();
+//! let db = DB::open(&opts, "path/for/rocksdb/storage_metrics").unwrap();
+//!
+//! // The database shares its statistics with the options it was opened with.
+//! let exporter = Exporter::spawn(opts, Duration::from_secs(10));
+//! db.put(b"k", b"v").unwrap();
+//! drop(exporter);
+//! ```
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use Options;
+use property_watcher::Sample;
+use statistics::Statistics;
+
+fn metric_name(name: &str) -> String {
+    name.replace('.', "_")
+}
+
+/// Records the current value of every ticker and histogram in `stats`.
+pub fn record(stats: &Statistics) {
+    for (name, &count) in &stats.tickers {
+        counter!(metric_name(name)).absolute(count);
+    }
+    for (name, hist) in &stats.histograms {
+        let name = metric_name(name);
+        gauge!(name.clone(), "quantile" => "0.5").set(hist.p50);
+        gauge!(name.clone(), "quantile" => "0.95").set(hist.p95);
+        gauge!(name.clone(), "quantile" => "0.99").set(hist.p99);
+        if let Some(max) = hist.max {
+            gauge!(name.clone(), "quantile" => "1").set(max);
+        }
+        counter!(format!("{}_count", name)).absolute(hist.count);
+        if let Some(sum) = hist.sum {
+            counter!(format!("{}_sum", name)).absolute(sum);
+        }
+    }
+}
+
+/// Records the properties and statistics of a `PropertyWatcher` sample,
+/// e.g. as its callback.
+pub fn record_sample(sample: &Sample) {
+    for (name, &value) in &sample.properties {
+        gauge!(metric_name(name).replace('-', "_")).set(value as f64);
+    }
+    if let Some(ref stats) = sample.statistics {
+        record(stats);
+    }
+}
+
+/// A background thread recording the statistics of an `Options` at a fixed
+/// interval. The thread stops when the `Exporter` is dropped.
+pub struct Exporter {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Exporter {
+    /// Starts recording the statistics of `opts` every `interval`. `opts`
+    /// must have had `enable_statistics` called before the database was
+    /// opened; otherwise nothing is recorded.
+    pub fn spawn(opts: Options, interval: Duration) -> Exporter {
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::spawn(move || loop {
+            if let Some(stats) = opts.get_statistics_snapshot() {
+                record(&stats);
+            }
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+        });
+        Exporter {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parallel scans of key ranges on the rayon thread pool, available with
+//! the `rayon` feature.
+//!
+//! `par_scan` splits every range into partitions of about the same size on
+//! disk, using the boundaries of the SST files of the column family as
+//! split points and `DB::approximate_sizes` to weigh them, and folds every
+//! partition with its own iterator. All partitions read from one snapshot,
+//! so the scan is consistent.
+//!
+//! Partitions are computed with the default bytewise key order, so they do
+//! not apply to column families using a custom comparator.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::DB;
+//! use rocksdb::par_scan::{self, KeyRange};
+//!
+//! let db = DB::open_default("path/for/rocksdb/storage_par_scan").unwrap();
+//! for i in 0..1000u32 {
+//!     db.put(format!("k{:04}", i).as_bytes(), b"value").unwrap();
+//! }
+//!
+//! // Count the keys and the size of the values of every partition.
+//! let counts = par_scan::par_scan(&db, None, &[KeyRange::all()], || (0, 0), |(n, size), _, value| {
+//!     (n + 1, size + value.len())
+//! }).unwrap();
+//! let total = counts.iter().fold(0, |total, &(n, _)| total + n);
+//! assert_eq!(total, 1000);
+//! ```
+
+use std::collections::HashSet;
+
+use rayon;
+use rayon::prelude::*;
+
+use {ColumnFamily, DB, Direction, Error, IteratorMode, LiveFile, ReadOptions};
+
+/// The keys from `start`, inclusive, up to `end`, exclusive, where `None`
+/// leaves that side unbounded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyRange {
+    pub start: Option<Vec<u8>>,
+    pub end: Option<Vec<u8>>,
+}
+
+impl KeyRange {
+    pub fn new(start: &[u8], end: &[u8]) -> KeyRange {
+        KeyRange {
+            start: Some(start.to_vec()),
+            end: Some(end.to_vec()),
+        }
+    }
+
+    /// The range of all keys.
+    pub fn all() -> KeyRange {
+        KeyRange::default()
+    }
+
+    fn contains(&self, key: &[u8]) -> bool {
+        self.start.as_ref().map_or(true, |start| key >= &start[..]) &&
+            self.end.as_ref().map_or(true, |end| key < &end[..])
+    }
+}
+
+// Returns the numbers of the files listed in a `rocksdb.sstables` property.
+// Files are listed one per line, as
+// ` <number>:<size>[<smallest key> .. <largest key>]`, under a header line
+// for every level.
+fn sst_numbers(sstables: &str) -> HashSet<u64> {
+    sstables
+        .lines()
+        .filter(|line| line.starts_with(' '))
+        .filter_map(|line| line.trim_start().split(':').next().and_then(|n| n.parse().ok()))
+        .collect()
+}
+
+// Returns the number of an SST file named like `/000012.sst`.
+fn sst_number(name: &str) -> Option<u64> {
+    name.trim_start_matches('/').split('.').next().and_then(|n| n.parse().ok())
+}
+
+/// Splits `range` into at most `partitions` consecutive ranges of about the
+/// same size on disk. Data that is only in the memtables is not weighed, so
+/// a range whose data has not been flushed yet is not split.
+pub fn split_range(
+    db: &DB,
+    cf: Option<&ColumnFamily>,
+    range: &KeyRange,
+    partitions: usize,
+) -> Result<Vec<KeyRange>, Error> {
+    // Only the files of the scanned column family give split points. The C
+    // API does not tell which column family a live file belongs to, so its
+    // files are looked up in the `rocksdb.sstables` property; a file written
+    // in between is just not used.
+    let sstables = try!(match cf {
+        Some(cf) => db.property_value_cf(cf, "rocksdb.sstables"),
+        None => db.property_value("rocksdb.sstables"),
+    });
+    let numbers = sst_numbers(&sstables.unwrap_or_default());
+    let files: Vec<LiveFile> = try!(db.get_live_files(false))
+        .into_iter()
+        .filter(|file| sst_number(&file.name).map_or(false, |n| numbers.contains(&n)))
+        .collect();
+    let mut bounds: Vec<Vec<u8>> = files
+        .iter()
+        .flat_map(|file| vec![file.smallest_key.clone(), file.largest_key.clone()])
+        .filter(|key| range.contains(key) && Some(key) != range.start.as_ref())
+        .collect();
+    bounds.sort();
+    bounds.dedup();
+    if partitions <= 1 || bounds.is_empty() {
+        return Ok(vec![range.clone()]);
+    }
+
+    // The last interval ends just after the largest key when unbounded.
+    let start = range.start.clone().unwrap_or_else(Vec::new);
+    let end = range.end.clone().unwrap_or_else(|| {
+        let mut end = files.iter().map(|file| &file.largest_key).max().unwrap().clone();
+        end.push(0);
+        end
+    });
+    let mut points = Vec::with_capacity(bounds.len() + 2);
+    points.push(start);
+    points.extend(bounds);
+    points.push(end);
+    let intervals: Vec<(&[u8], &[u8])> =
+        points.windows(2).map(|w| (&w[0][..], &w[1][..])).collect();
+    let sizes = match cf {
+        Some(cf) => db.approximate_sizes_cf(cf, &intervals),
+        None => db.approximate_sizes(&intervals),
+    };
+    let total: u64 = sizes.iter().sum();
+    if total == 0 {
+        return Ok(vec![range.clone()]);
+    }
+
+    let target = total / partitions as u64;
+    let mut split = Vec::with_capacity(partitions);
+    let mut current = range.start.clone();
+    let mut size = 0;
+    for (&(_, limit), interval_size) in intervals.iter().zip(sizes).take(intervals.len() - 1) {
+        size += interval_size;
+        if size >= target && split.len() + 1 < partitions {
+            split.push(KeyRange {
+                start: current,
+                end: Some(limit.to_vec()),
+            });
+            current = Some(limit.to_vec());
+            size = 0;
+        }
+    }
+    split.push(KeyRange {
+        start: current,
+        end: range.end.clone(),
+    });
+    Ok(split)
+}
+
+/// Folds the entries of every range in parallel, with `init` creating the
+/// initial value of every partition and `fold` adding an entry to it.
+/// Returns the folded value of every partition, in key order of the
+/// partitions within each range, and in the order of `ranges`.
+///
+/// Every range is split into as many partitions as the rayon pool has
+/// threads.
+pub fn par_scan<T, I, F>(
+    db: &DB,
+    cf: Option<&ColumnFamily>,
+    ranges: &[KeyRange],
+    init: I,
+    fold: F,
+) -> Result<Vec<T>, Error>
+where
+    T: Send,
+    I: Fn() -> T + Sync + Send,
+    F: Fn(T, &[u8], &[u8]) -> T + Sync + Send,
+{
+    let threads = rayon::current_num_threads();
+    let mut partitions = Vec::new();
+    for range in ranges {
+        partitions.extend(try!(split_range(db, cf, range, threads)));
+    }
+    let snapshot = db.snapshot();
+    partitions
+        .par_iter()
+        .map(|partition| {
+            let mode = match partition.start {
+                Some(ref start) => IteratorMode::From(start, Direction::Forward),
+                None => IteratorMode::Start,
+            };
+            let mut readopts = ReadOptions::default();
+            if let Some(ref end) = partition.end {
+                readopts.set_iterate_upper_bound(end);
+            }
+            let iter = match cf {
+                Some(cf) => try!(snapshot.iterator_cf_opt(cf, mode, readopts)),
+                None => snapshot.iterator_opt(mode, readopts),
+            };
+            let mut acc = init();
+            for (key, value) in iter {
+                acc = fold(acc, &key, &value);
+            }
+            Ok(acc)
+        })
+        .collect()
+}
@@ -0,0 +1,154 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sampling database properties and statistics on a background thread.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::sync::mpsc;
+//! use std::time::Duration;
+//! use rocksdb::{DB, Options};
+//! use rocksdb::property_watcher::PropertyWatcher;
+//!
+//! let mut opts = Options::default();
+//! opts.create_if_missing(true);
+//! opts.enable_statistics();
+//! let db = Arc::new(DB::open(&opts, "path/for/rocksdb/storage_property_watcher").unwrap());
+//!
+//! let (samples, received) = mpsc::channel();
+//! let watcher = PropertyWatcher::new(Duration::from_secs(10))
+//!     .property("rocksdb.estimate-num-keys")
+//!     .property("rocksdb.num-running-compactions")
+//!     .statistics(opts)
+//!     .spawn(&db, move |sample| {
+//!         let _ = samples.send(sample.clone());
+//!     });
+//!
+//! let sample = received.recv().unwrap();
+//! assert!(sample.properties.contains_key("rocksdb.estimate-num-keys"));
+//! assert!(sample.statistics.is_some());
+//! drop(watcher);
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Weak};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use {DB, Options};
+use statistics::Statistics;
+
+/// The values read by one round of sampling.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sample {
+    /// The value of every watched property, by name. Properties that are
+    /// unknown or not numeric are left out.
+    pub properties: BTreeMap<String, u64>,
+    /// The statistics of the options passed to `statistics`, if any were
+    /// passed and they have statistics enabled.
+    pub statistics: Option<Statistics>,
+}
+
+/// A background thread sampling numeric properties of a database, such as
+/// `rocksdb.estimate-pending-compaction-bytes`, and optionally its
+/// statistics, at a fixed interval, and passing every `Sample` to a
+/// callback.
+///
+/// The watcher only holds a weak reference to the database, and stops
+/// when the database is dropped or when the `PropertyWatcher` is.
+pub struct PropertyWatcher {
+    interval: Duration,
+    properties: Vec<String>,
+    statistics: Option<Options>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PropertyWatcher {
+    /// Creates a watcher sampling every `interval`, starting as soon as it
+    /// is spawned.
+    pub fn new(interval: Duration) -> PropertyWatcher {
+        PropertyWatcher {
+            interval: interval,
+            properties: Vec::new(),
+            statistics: None,
+            stop: None,
+            thread: None,
+        }
+    }
+
+    /// Adds a numeric property to sample.
+    pub fn property(mut self, name: &str) -> PropertyWatcher {
+        self.properties.push(name.to_owned());
+        self
+    }
+
+    /// Also samples the statistics of `opts`, which must be the options the
+    /// database was opened with, after `enable_statistics`.
+    pub fn statistics(mut self, opts: Options) -> PropertyWatcher {
+        self.statistics = Some(opts);
+        self
+    }
+
+    /// Starts sampling `db` on a new thread.
+    pub fn spawn<F>(mut self, db: &Arc<DB>, mut callback: F) -> PropertyWatcher
+    where
+        F: FnMut(&Sample) + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel();
+        let db: Weak<DB> = Arc::downgrade(db);
+        let interval = self.interval;
+        let properties = self.properties.clone();
+        let statistics = self.statistics.take();
+        let thread = thread::spawn(move || loop {
+            let sample = match db.upgrade() {
+                Some(db) => sample(&db, &properties, statistics.as_ref()),
+                None => break,
+            };
+            callback(&sample);
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+        });
+        self.stop = Some(stop);
+        self.thread = Some(thread);
+        self
+    }
+}
+
+/// Reads the given numeric properties of `db`, and the statistics of
+/// `statistics` if given, once.
+pub fn sample(db: &DB, properties: &[String], statistics: Option<&Options>) -> Sample {
+    let mut sample = Sample::default();
+    for name in properties {
+        if let Ok(Some(value)) = db.property_int_value(name) {
+            sample.properties.insert(name.clone(), value);
+        }
+    }
+    sample.statistics = statistics.and_then(|opts| opts.get_statistics_snapshot());
+    sample
+}
+
+impl Drop for PropertyWatcher {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
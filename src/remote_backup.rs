@@ -0,0 +1,477 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Backups kept in an object store such as S3, GCS or Azure Blob Storage.
+//!
+//! `RemoteBackup` creates a backup with a `BackupEngine` in a local staging
+//! directory, then uploads the files the store does not have yet. Backup
+//! files never change once written, so every backup only uploads the files
+//! that are new since the previous one. The metadata of a backup is
+//! uploaded last: an interrupted upload leaves no visible backup, and the
+//! next one picks up where it stopped.
+//!
+//! Restoring downloads a backup, by default the latest one, into the
+//! staging directory, skipping the files an earlier, interrupted restore
+//! already downloaded, and restores the database from there. Files already
+//! in the staging directory are checked against the checksums in the
+//! metadata of the backup. Backups and restores may share a staging
+//! directory: each prefix gets its own subdirectory of it, holding the
+//! backups being created in `backup/` and those being restored in
+//! `restore/`.
+//!
+//! The store is any implementation of `ObjectStore`; `DirectoryStore` keeps
+//! the objects in a local directory, e.g. a mounted network file system.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::DB;
+//! use rocksdb::backup::RestoreOptions;
+//! use rocksdb::remote_backup::{DirectoryStore, RemoteBackup};
+//!
+//! let store = DirectoryStore::new("path/for/rocksdb/storage_remote_store");
+//! let mut backup = RemoteBackup::new(store, "path/for/rocksdb/storage_remote_staging");
+//! backup.set_prefix("db1/");
+//!
+//! let db = DB::open_default("path/for/rocksdb/storage_remote").unwrap();
+//! db.put(b"k1", b"v1").unwrap();
+//! backup.create(&db).unwrap();
+//!
+//! let restored = "path/for/rocksdb/storage_remote_restored";
+//! backup.restore(restored, restored, &RestoreOptions::default()).unwrap();
+//! let db = DB::open_default(restored).unwrap();
+//! assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+//! ```
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use {DB, Error, ErrorKind};
+use backup::{BackupEngine, BackupEngineOptions, RestoreOptions};
+
+fn io_error(e: io::Error) -> Error {
+    Error::with_kind(ErrorKind::IOError, e.to_string())
+}
+
+/// A store of named objects. Names are made of `/`-separated components.
+///
+/// Implementations report their own failures with `Error::with_kind`, e.g.
+/// `ErrorKind::IOError` for a request that could not be completed.
+pub trait ObjectStore {
+    /// Stores `data` as the object `name`, replacing any existing object.
+    fn put(&self, name: &str, data: &[u8]) -> Result<(), Error>;
+
+    /// Returns the content of the object `name`, if it exists.
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Returns the names of all objects starting with `prefix`.
+    fn list(&self, prefix: &str) -> Result<Vec<String>, Error>;
+
+    /// Stores the object `name` from its consecutive `parts`, which are read
+    /// from the backed up file as they are consumed.
+    ///
+    /// Stores supporting multipart uploads should override this to upload
+    /// every part as it comes; by default the parts are gathered and
+    /// written with `put`.
+    fn put_multipart(
+        &self,
+        name: &str,
+        parts: &mut dyn Iterator<Item = Result<Vec<u8>, Error>>,
+    ) -> Result<(), Error> {
+        let mut data = Vec::new();
+        for part in parts {
+            data.extend_from_slice(&try!(part));
+        }
+        self.put(name, &data)
+    }
+
+    /// Writes the content of the object `name` to `dst`, returning false if
+    /// it does not exist.
+    ///
+    /// Stores should override this to stream large objects; by default the
+    /// object is read with `get`.
+    fn download(&self, name: &str, dst: &mut dyn Write) -> Result<bool, Error> {
+        match try!(self.get(name)) {
+            Some(data) => {
+                try!(dst.write_all(&data).map_err(io_error));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+// Appends to `names` the paths of the files under `dir`, each prefixed with
+// `rel`.
+fn walk(dir: &Path, rel: &str, names: &mut Vec<String>) -> io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in try!(fs::read_dir(dir)) {
+        let entry = try!(entry);
+        let name = format!("{}{}", rel, entry.file_name().to_string_lossy());
+        if try!(entry.file_type()).is_dir() {
+            try!(walk(&entry.path(), &format!("{}/", name), names));
+        } else {
+            names.push(name);
+        }
+    }
+    Ok(())
+}
+
+/// An `ObjectStore` keeping every object in a file under a directory.
+pub struct DirectoryStore {
+    root: PathBuf,
+}
+
+impl DirectoryStore {
+    pub fn new<P: AsRef<Path>>(root: P) -> DirectoryStore {
+        DirectoryStore { root: root.as_ref().to_path_buf() }
+    }
+}
+
+impl ObjectStore for DirectoryStore {
+    fn put(&self, name: &str, data: &[u8]) -> Result<(), Error> {
+        let path = self.root.join(name);
+        let tmp = self.root.join(format!("{}.tmp", name));
+        if let Some(parent) = path.parent() {
+            try!(fs::create_dir_all(parent).map_err(io_error));
+        }
+        try!(fs::write(&tmp, data).map_err(io_error));
+        fs::rename(&tmp, &path).map_err(io_error)
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        match fs::read(self.root.join(name)) {
+            Ok(data) => Ok(Some(data)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let mut names = Vec::new();
+        try!(walk(&self.root, "", &mut names).map_err(io_error));
+        names.retain(|name| name.starts_with(prefix) && !name.ends_with(".tmp"));
+        names.sort();
+        Ok(names)
+    }
+}
+
+// Computes the CRC-32C checksum RocksDB records for the files of a backup.
+fn crc32c(src: &mut dyn Read) -> io::Result<u32> {
+    let mut table = [0u32; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut crc = i as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82f6_3b78 } else { crc >> 1 };
+        }
+        *entry = crc;
+    }
+    let mut crc = !0u32;
+    let mut buf = vec![0; 64 << 10];
+    loop {
+        let n = try!(src.read(&mut buf));
+        if n == 0 {
+            return Ok(!crc);
+        }
+        for &byte in &buf[..n] {
+            crc = table[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8);
+        }
+    }
+}
+
+// What the metadata of a backup records about one of its files.
+#[derive(Default)]
+struct FileInfo {
+    size: Option<u64>,
+    crc32c: Option<u32>,
+}
+
+impl FileInfo {
+    // Parses the fields following the path of `file` on its line of the
+    // metadata, e.g. `crc32 <checksum>`. The names of the files in
+    // `shared_checksum/` also hold their size, as in
+    // `<number>_<checksum>_<size>.sst`.
+    fn parse<'a, I: Iterator<Item = &'a str>>(file: &str, mut fields: I) -> FileInfo {
+        let mut info = FileInfo::default();
+        while let (Some(key), Some(value)) = (fields.next(), fields.next()) {
+            match key {
+                "crc32" => info.crc32c = value.parse().ok(),
+                "size" => info.size = value.parse().ok(),
+                _ => {}
+            }
+        }
+        if info.size.is_none() && file.starts_with("shared_checksum/") {
+            let stem = file.rsplit('/').next().unwrap_or("").split('.').next().unwrap_or("");
+            let parts: Vec<&str> = stem.split('_').collect();
+            if parts.len() == 3 {
+                info.size = parts[2].parse().ok();
+            }
+        }
+        info
+    }
+
+    // Returns whether the file at `path` has the recorded size and checksum.
+    fn matches(&self, path: &Path) -> Result<bool, Error> {
+        if let Some(size) = self.size {
+            if try!(fs::metadata(path).map_err(io_error)).len() != size {
+                return Ok(false);
+            }
+        }
+        if let Some(crc) = self.crc32c {
+            let mut file = try!(File::open(path).map_err(io_error));
+            return Ok(try!(crc32c(&mut file).map_err(io_error)) == crc);
+        }
+        Ok(true)
+    }
+}
+
+// The parts of a file being uploaded.
+struct FileParts {
+    file: File,
+    part_size: usize,
+}
+
+impl Iterator for FileParts {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, Error>> {
+        let mut part = Vec::with_capacity(self.part_size);
+        match (&mut self.file).take(self.part_size as u64).read_to_end(&mut part) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(part)),
+            Err(e) => Some(Err(io_error(e))),
+        }
+    }
+}
+
+/// Backups of databases uploaded to an `ObjectStore`.
+pub struct RemoteBackup<S> {
+    store: S,
+    staging: PathBuf,
+    prefix: String,
+    part_size: usize,
+}
+
+impl<S: ObjectStore> RemoteBackup<S> {
+    /// Keeps the backups in `store`, using the local directory `staging`
+    /// to create and restore them.
+    pub fn new<P: AsRef<Path>>(store: S, staging: P) -> RemoteBackup<S> {
+        RemoteBackup {
+            store: store,
+            staging: staging.as_ref().to_path_buf(),
+            prefix: String::new(),
+            part_size: 8 << 20,
+        }
+    }
+
+    /// Prefixes the names of the objects, so that a store can hold the
+    /// backups of several databases.
+    ///
+    /// Default: empty
+    pub fn set_prefix(&mut self, prefix: &str) {
+        self.prefix = prefix.to_owned();
+    }
+
+    /// Sets the size of the parts passed to `ObjectStore::put_multipart`.
+    ///
+    /// Default: 8MB
+    pub fn set_part_size(&mut self, part_size: usize) {
+        self.part_size = part_size;
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    // The subdirectory of the staging directory used for the prefix.
+    fn staging_dir(&self) -> PathBuf {
+        self.staging.join(self.prefix.trim_start_matches('/'))
+    }
+
+    // The backup directory in which backups are created.
+    fn backup_dir(&self) -> PathBuf {
+        self.staging_dir().join("backup")
+    }
+
+    // The backup directory into which backups are downloaded to be
+    // restored. It is kept apart from `backup_dir`, so that a restore
+    // cannot change the ids of the backups created next.
+    fn restore_dir(&self) -> PathBuf {
+        self.staging_dir().join("restore")
+    }
+
+    /// Backs up `db` and uploads the backup. Only the latest backup is kept
+    /// in the staging directory once it is uploaded.
+    ///
+    /// Fails with `ErrorKind::InvalidArgument` if the store already has a
+    /// backup with the id of the new one, e.g. because the staging
+    /// directory was lost since it was uploaded.
+    pub fn create(&mut self, db: &DB) -> Result<(), Error> {
+        let staging = self.backup_dir();
+        let mut engine = try!(BackupEngine::open(&BackupEngineOptions::default(), &staging));
+        try!(engine.create_new_backup(db));
+        let id = engine.backup_ids().last().cloned().unwrap_or(0);
+        try!(self.upload(id));
+        engine.purge_old_backups(1)
+    }
+
+    fn upload(&self, id: u32) -> Result<(), Error> {
+        let existing: HashSet<String> = try!(self.store.list(&self.prefix)).into_iter().collect();
+        let new_meta = format!("{}meta/{}", self.prefix, id);
+        if existing.contains(&new_meta) {
+            let msg = format!("backup {} is already in the object store as {}", id, new_meta);
+            return Err(Error::with_kind(ErrorKind::InvalidArgument, msg));
+        }
+        let staging = self.backup_dir();
+        let mut files = Vec::new();
+        try!(walk(&staging, "", &mut files).map_err(io_error));
+        // Leave out the files still being written by the engine or a
+        // restore.
+        files.retain(|file| {
+            ["shared/", "shared_checksum/", "private/", "meta/"]
+                .iter()
+                .any(|dir| file.starts_with(dir)) && !file.ends_with(".tmp") &&
+                !file.ends_with(".download")
+        });
+        // Upload the metadata, which makes the new backup visible, last.
+        let (meta, data): (Vec<String>, Vec<String>) =
+            files.into_iter().partition(|file| file.starts_with("meta/"));
+        for file in data.iter().chain(meta.iter()) {
+            let name = format!("{}{}", self.prefix, file);
+            if existing.contains(&name) {
+                continue;
+            }
+            let mut parts = FileParts {
+                file: try!(File::open(staging.join(file)).map_err(io_error)),
+                part_size: self.part_size,
+            };
+            try!(self.store.put_multipart(&name, &mut parts));
+        }
+        Ok(())
+    }
+
+    // Downloads a file of a backup into the restore directory, unless it is
+    // already there and matches `info`.
+    fn fetch(&self, file: &str, info: &FileInfo) -> Result<(), Error> {
+        let path = self.restore_dir().join(file);
+        if path.exists() && try!(info.matches(&path)) {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            try!(fs::create_dir_all(parent).map_err(io_error));
+        }
+        let tmp = self.restore_dir().join(format!("{}.download", file));
+        let mut dst = try!(File::create(&tmp).map_err(io_error));
+        let name = format!("{}{}", self.prefix, file);
+        if !try!(self.store.download(&name, &mut dst)) {
+            let msg = format!("{} is missing from the object store", name);
+            return Err(Error::with_kind(ErrorKind::Corruption, msg));
+        }
+        try!(dst.sync_all().map_err(io_error));
+        if !try!(info.matches(&tmp)) {
+            let msg = format!("{} does not match the metadata of its backup", name);
+            return Err(Error::with_kind(ErrorKind::Corruption, msg));
+        }
+        fs::rename(&tmp, &path).map_err(io_error)
+    }
+
+    /// Returns the ids of the backups in the store, oldest first.
+    pub fn backup_ids(&self) -> Result<Vec<u32>, Error> {
+        let meta_prefix = format!("{}meta/", self.prefix);
+        let mut ids: Vec<u32> = try!(self.store.list(&meta_prefix))
+            .iter()
+            .filter_map(|name| name[meta_prefix.len()..].parse::<u32>().ok())
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Restores the latest backup of the store into `db_dir`, and its log
+    /// files into `wal_dir`. Fails with `ErrorKind::NotFound` if the store
+    /// has no backup.
+    ///
+    /// A restore that failed while downloading can be retried, and only
+    /// downloads the files that are still missing.
+    pub fn restore<D: AsRef<Path>, W: AsRef<Path>>(
+        &self,
+        db_dir: D,
+        wal_dir: W,
+        opts: &RestoreOptions,
+    ) -> Result<(), Error> {
+        match try!(self.backup_ids()).last() {
+            Some(&id) => self.restore_backup(id, db_dir, wal_dir, opts),
+            None => Err(Error::with_kind(ErrorKind::NotFound,
+                                         "no backup in the object store".to_owned())),
+        }
+    }
+
+    /// Restores the backup `id` of the store, like `restore`. Fails with
+    /// `ErrorKind::NotFound` if the store has no such backup.
+    ///
+    /// A `BackupEngine` can only restore its latest backup, so the backups
+    /// newer than `id` that an earlier restore downloaded are dropped from
+    /// the restore directory.
+    pub fn restore_backup<D: AsRef<Path>, W: AsRef<Path>>(
+        &self,
+        id: u32,
+        db_dir: D,
+        wal_dir: W,
+        opts: &RestoreOptions,
+    ) -> Result<(), Error> {
+        let meta = format!("meta/{}", id);
+        let content = match try!(self.store.get(&format!("{}{}", self.prefix, meta))) {
+            Some(content) => content,
+            None => {
+                let msg = format!("no backup {} in the object store", id);
+                return Err(Error::with_kind(ErrorKind::NotFound, msg));
+            }
+        };
+        // Every file of the backup is listed on its own line, starting with
+        // its path relative to the backup directory.
+        for line in String::from_utf8_lossy(&content).lines() {
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some(file) if file.contains('/') => {
+                    try!(self.fetch(file, &FileInfo::parse(file, fields)))
+                }
+                _ => {}
+            }
+        }
+
+        let meta_dir = self.restore_dir().join("meta");
+        try!(fs::create_dir_all(&meta_dir).map_err(io_error));
+        for entry in try!(fs::read_dir(&meta_dir).map_err(io_error)) {
+            let entry = try!(entry.map_err(io_error));
+            match entry.file_name().to_string_lossy().parse::<u32>() {
+                Ok(other) if other > id => try!(fs::remove_file(entry.path()).map_err(io_error)),
+                _ => {}
+            }
+        }
+        let tmp = meta_dir.join(format!("{}.download", id));
+        try!(fs::write(&tmp, &content).map_err(io_error));
+        try!(fs::rename(&tmp, meta_dir.join(id.to_string())).map_err(io_error));
+
+        let staging = self.restore_dir();
+        let mut engine = try!(BackupEngine::open(&BackupEngineOptions::default(), &staging));
+        if engine.backup_ids().last() != Some(&id) {
+            let msg = format!("backup {} could not be read from {}", id, staging.display());
+            return Err(Error::with_kind(ErrorKind::Corruption, msg));
+        }
+        engine.restore_from_latest_backup(db_dir, wal_dir, opts)
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replication of a database by tailing its write-ahead log.
+//!
+//! A `ReplicationStream` reads the write batches of a leader database in
+//! the order they were written, and a `Follower` applies them to another
+//! database, recording the last applied sequence number in the same write
+//! so that replication can resume where it stopped after a restart.
+//!
+//! The leader only keeps the log files that are still needed for recovery
+//! unless told otherwise: use `retain_wal` on its options so that followers
+//! that fall behind can catch up.
+//!
+//! Batches refer to column families by id, so a follower must have the
+//! same column families as the leader, created in the same order, e.g. by
+//! starting from a backup or checkpoint of the leader.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use rocksdb::{DB, Options};
+//! use rocksdb::replication::{self, Follower, ReplicationStream};
+//!
+//! let mut opts = Options::default();
+//! opts.create_if_missing(true);
+//! replication::retain_wal(&mut opts, 3600, 1024);
+//! let leader = Arc::new(DB::open(&opts, "path/for/rocksdb/storage_leader").unwrap());
+//!
+//! let db = DB::open_default("path/for/rocksdb/storage_follower").unwrap();
+//! let mut follower = Follower::new(db);
+//!
+//! leader.put(b"k1", b"v1").unwrap();
+//!
+//! let mut stream = ReplicationStream::new(leader.clone(), follower.last_applied().unwrap());
+//! follower.apply_all(&stream.poll(100).unwrap()).unwrap();
+//! assert_eq!(&*follower.db().get(b"k1").unwrap().unwrap(), b"v1");
+//! ```
+
+use std::sync::Arc;
+
+use {DB, Error, ErrorKind, Options, WriteBatch};
+
+// The key of the last applied sequence number in the default column family.
+const PROGRESS_KEY: &'static [u8] = b"rust-rocksdb.replication.last_applied";
+
+/// Keeps the write-ahead log files of a leader for `ttl_seconds` after they
+/// are no longer needed for recovery, or until they take more than
+/// `size_limit_mb` megabytes, whichever comes first. A value of 0 disables
+/// that limit; keeping no log files at all requires both to be 0.
+pub fn retain_wal(opts: &mut Options, ttl_seconds: u64, size_limit_mb: u64) {
+    opts.set_wal_ttl_seconds(ttl_seconds);
+    opts.set_wal_size_limit_mb(size_limit_mb);
+}
+
+// Encodes a sequence number as the value of `PROGRESS_KEY`.
+fn encode_progress(last: u64) -> [u8; 8] {
+    [
+        (last >> 56) as u8,
+        (last >> 48) as u8,
+        (last >> 40) as u8,
+        (last >> 32) as u8,
+        (last >> 24) as u8,
+        (last >> 16) as u8,
+        (last >> 8) as u8,
+        last as u8,
+    ]
+}
+
+/// One write batch of the leader.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update {
+    sequence: u64,
+    count: u64,
+    data: Vec<u8>,
+}
+
+impl Update {
+    /// Rebuilds an update from its sequence number and the data of its
+    /// batch, e.g. after it was sent to a follower.
+    pub fn from_parts(sequence: u64, data: Vec<u8>) -> Update {
+        let count = WriteBatch::from_data(&data).len() as u64;
+        Update {
+            sequence: sequence,
+            count: count,
+            data: data,
+        }
+    }
+
+    /// Returns the sequence number of the first write of the batch.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the sequence number of the last write of the batch.
+    pub fn last_sequence(&self) -> u64 {
+        self.sequence + self.count.max(1) - 1
+    }
+
+    /// Returns the serialized batch, as returned by `WriteBatch::data`.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn to_batch(&self) -> WriteBatch {
+        WriteBatch::from_data(&self.data)
+    }
+}
+
+/// Reads the updates of a leader that come after the last one a follower
+/// applied.
+pub struct ReplicationStream {
+    db: Arc<DB>,
+    last: u64,
+    // Whether the next update may follow a gap, for a stream starting from
+    // the oldest update still in the log.
+    from_oldest: bool,
+}
+
+impl ReplicationStream {
+    /// Streams the updates of `db` after the one numbered `last_applied`,
+    /// where 0 means that no update was applied yet.
+    pub fn new(db: Arc<DB>, last_applied: u64) -> ReplicationStream {
+        ReplicationStream {
+            db: db,
+            last: last_applied,
+            from_oldest: false,
+        }
+    }
+
+    /// Streams every update of `db` still in the log, starting from the
+    /// oldest one, whatever its sequence number. This only suits followers
+    /// that do not need the updates already deleted from the log.
+    pub fn from_oldest(db: Arc<DB>) -> ReplicationStream {
+        ReplicationStream {
+            db: db,
+            last: 0,
+            from_oldest: true,
+        }
+    }
+
+    /// Returns the sequence number of the last update returned by `poll`.
+    pub fn last_sequence(&self) -> u64 {
+        self.last
+    }
+
+    /// Returns up to `max_updates` of the updates written since the last
+    /// call, in order, or none if the follower is up to date.
+    ///
+    /// Fails with `ErrorKind::NotFound` if the updates that follow have
+    /// already been deleted from the log, i.e. if the first update left
+    /// comes after `last_sequence() + 1`, in which case the follower has to
+    /// be rebuilt from a copy of the leader.
+    pub fn poll(&mut self, max_updates: usize) -> Result<Vec<Update>, Error> {
+        let mut updates = Vec::new();
+        if max_updates == 0 || self.db.latest_sequence_number() <= self.last {
+            return Ok(updates);
+        }
+        for item in try!(self.db.get_updates_since(self.last + 1)) {
+            let (sequence, batch) = try!(item);
+            let update = Update {
+                sequence: sequence,
+                count: batch.len() as u64,
+                data: batch.data().to_vec(),
+            };
+            if update.last_sequence() <= self.last {
+                continue;
+            }
+            if update.sequence > self.last + 1 && !self.from_oldest {
+                return Err(Error::with_kind(ErrorKind::NotFound, format!(
+                    "updates {} to {} are no longer in the write-ahead log",
+                    self.last + 1,
+                    update.sequence - 1
+                )));
+            }
+            self.last = update.last_sequence();
+            self.from_oldest = false;
+            updates.push(update);
+            if updates.len() == max_updates {
+                break;
+            }
+        }
+        Ok(updates)
+    }
+}
+
+/// A database that applies the updates of a leader.
+///
+/// The progress is kept in the default column family under a reserved key,
+/// `rust-rocksdb.replication.last_applied`, which the leader must not write.
+pub struct Follower {
+    db: DB,
+}
+
+impl Follower {
+    /// Applies updates to `db`.
+    pub fn new(db: DB) -> Follower {
+        Follower { db: db }
+    }
+
+    /// Like `new`, but records `last_applied` as the progress, e.g. for a
+    /// follower started from a checkpoint or backup of the leader taken
+    /// once the leader had written the update numbered `last_applied`.
+    pub fn new_at(db: DB, last_applied: u64) -> Result<Follower, Error> {
+        let follower = Follower::new(db);
+        try!(follower.db.put(PROGRESS_KEY, &encode_progress(last_applied)));
+        Ok(follower)
+    }
+
+    /// Returns the underlying database, for reads.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    pub fn into_inner(self) -> DB {
+        self.db
+    }
+
+    /// Returns the sequence number of the last applied update, or 0 if
+    /// none was applied yet.
+    pub fn last_applied(&self) -> Result<u64, Error> {
+        match try!(self.db.get(PROGRESS_KEY)) {
+            Some(ref value) if value.len() == 8 => {
+                Ok(value.iter().fold(0, |n, &b| n << 8 | b as u64))
+            }
+            Some(_) => Err(Error::with_kind(ErrorKind::Corruption,
+                                            "invalid replication progress".to_owned())),
+            None => Ok(0),
+        }
+    }
+
+    /// Applies an update together with the new progress, in one write.
+    /// Returns false, and does nothing, if it was already applied.
+    pub fn apply(&mut self, update: &Update) -> Result<bool, Error> {
+        if update.last_sequence() <= try!(self.last_applied()) {
+            return Ok(false);
+        }
+        let mut batch = update.to_batch();
+        try!(batch.put(PROGRESS_KEY, &encode_progress(update.last_sequence())));
+        try!(self.db.write(batch));
+        Ok(true)
+    }
+
+    /// Applies updates in order.
+    pub fn apply_all(&mut self, updates: &[Update]) -> Result<(), Error> {
+        for update in updates {
+            try!(self.apply(update));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+
+//! Retrying operations that failed with a transient error.
+
+use std::cmp;
+use std::thread;
+use std::time::Duration;
+
+use Error;
+
+/// How `retry` waits between attempts: the first delay is `initial`, and
+/// every following one is twice the previous one, up to `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    /// The number of attempts, including the first one.
+    pub attempts: u32,
+}
+
+impl Default for Backoff {
+    /// Five attempts, waiting from 1ms up to 100ms in between.
+    fn default() -> Backoff {
+        Backoff {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(100),
+            attempts: 5,
+        }
+    }
+}
+
+/// Runs `f` until it succeeds, fails with an error that is not retryable
+/// (see `Error::is_retryable`), or has been attempted `backoff.attempts`
+/// times, sleeping between attempts. Returns the result of the last
+/// attempt.
+///
+/// # Examples
+///
+/// ```
+/// use rocksdb::{Backoff, DB, retry};
+///
+/// let db = DB::open_default("path/for/rocksdb/storage_retry").unwrap();
+/// retry(&Backoff::default(), || db.put(b"k", b"v")).unwrap();
+/// ```
+pub fn retry<T, F>(backoff: &Backoff, mut f: F) -> Result<T, Error>
+where
+    F: FnMut() -> Result<T, Error>,
+{
+    let mut delay = backoff.initial;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(ref e) if e.is_retryable() && attempt < backoff.attempts => {
+                thread::sleep(delay);
+                delay = cmp::min(delay * 2, backoff.max);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Backoff, retry};
+    use {Error, ErrorKind};
+
+    #[test]
+    fn test_retry() {
+        let backoff = Backoff {
+            initial: Duration::from_millis(1),
+            max: Duration::from_millis(2),
+            attempts: 3,
+        };
+        let busy = || Error::with_kind(ErrorKind::Busy, "busy".to_owned());
+
+        let mut calls = 0;
+        let result = retry(&backoff, || {
+            calls += 1;
+            if calls < 3 { Err(busy()) } else { Ok(calls) }
+        });
+        assert_eq!(result, Ok(3));
+
+        calls = 0;
+        let result: Result<(), Error> = retry(&backoff, || {
+            calls += 1;
+            Err(busy())
+        });
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Busy);
+        assert_eq!(calls, 3);
+
+        calls = 0;
+        let result: Result<(), Error> = retry(&backoff, || {
+            calls += 1;
+            Err(Error::with_kind(ErrorKind::Corruption, "corrupt".to_owned()))
+        });
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::Corruption);
+        assert_eq!(calls, 1);
+    }
+}
@@ -14,28 +14,18 @@
 
 use std::ffi::CString;
 use std::mem;
-use std::ptr;
 use std::slice;
 
-use libc::{self, c_char, c_void, size_t};
+use libc::{c_char, c_void, size_t};
 
 use ffi;
-
-/// A SliceTranform is a generic pluggable way of transforming one string
-/// to another. Its primary use-case is in configuring rocksdb
-/// to store prefix blooms by setting prefix_extractor in
-/// ColumnFamilyOptions.
-pub struct SliceTransform {
-    pub inner: *mut ffi::rocksdb_slicetransform_t,
-}
-
-// NB we intentionally don't implement a Drop that passes
-// through to rocksdb_slicetransform_destroy because
-// this is currently only used (to my knowledge)
-// by people passing it as a prefix extractor when
-// opening a DB. 
+use ffi_util::{abort_on_callback_panic, catch_callback_panic};
+use {Error, ErrorKind, SliceTransform};
 
 impl SliceTransform {
+    /// Creates a transform from `transform_fn`, which must return a prefix
+    /// of the key it is given. The process aborts if it returns anything
+    /// else, as it does when it panics.
     pub fn create(
         name: &str,
         transform_fn: TransformFn,
@@ -81,6 +71,66 @@ impl SliceTransform {
         }
     }
 
+    /// Creates a transform from a `SliceTransformFns` implementation. The
+    /// callbacks are monomorphized for `T`, so no dynamic dispatch or
+    /// allocation happens when rocksdb extracts a prefix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rocksdb::{Options, SliceTransform, SliceTransformFns};
+    ///
+    /// // Keys look like `user:<id>:<field>`; the prefix is `user:<id>:`.
+    /// struct UserPrefix;
+    ///
+    /// impl SliceTransformFns for UserPrefix {
+    ///     fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+    ///         match key.iter().skip(5).position(|&b| b == b':') {
+    ///             Some(i) => &key[..i + 6],
+    ///             None => key,
+    ///         }
+    ///     }
+    ///
+    ///     fn in_domain(&self, key: &[u8]) -> bool {
+    ///         key.starts_with(b"user:")
+    ///     }
+    /// }
+    ///
+    /// let mut opts = Options::default();
+    /// opts.set_prefix_extractor(SliceTransform::create_from("user_prefix", UserPrefix));
+    /// ```
+    pub fn create_from<T: SliceTransformFns>(name: &str, fns: T) -> SliceTransform {
+        let cb = Box::new(TransformFnsCallback {
+            name: CString::new(name.as_bytes()).unwrap(),
+            fns: fns,
+        });
+
+        let st = unsafe {
+            ffi::rocksdb_slicetransform_create(
+                Box::into_raw(cb) as *mut c_void,
+                Some(fns_destructor_callback::<T>),
+                Some(fns_transform_callback::<T>),
+                Some(fns_in_domain_callback::<T>),
+                None,
+                Some(fns_name_callback::<T>),
+            )
+        };
+
+        SliceTransform {
+            inner: st
+        }
+    }
+
+    /// Creates a transform returning the first `len` bytes of a key, or the
+    /// whole key if it is shorter. Unlike `create_fixed_prefix`, every key is
+    /// in the domain, so keys shorter than `len` still use the prefix bloom.
+    pub fn create_capped_prefix(len: size_t) -> SliceTransform {
+        SliceTransform::create_from(
+            &format!("rocksdb.CappedPrefix.{}", len),
+            CappedPrefix { len: len },
+        )
+    }
+
     pub fn create_noop() -> SliceTransform {
         SliceTransform {
             inner: unsafe {
@@ -90,6 +140,14 @@ impl SliceTransform {
     }
 }
 
+impl Drop for SliceTransform {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::rocksdb_slicetransform_destroy(self.inner);
+        }
+    }
+}
+
 pub type TransformFn = fn(&[u8]) -> Vec<u8>;
 pub type InDomainFn = fn(&[u8]) -> bool;
 
@@ -109,7 +167,7 @@ pub unsafe extern "C" fn slice_transform_destructor_callback(
 pub unsafe extern "C" fn slice_transform_name_callback(
     raw_cb: *mut c_void
 ) -> *const c_char {
-	let cb = &mut *(raw_cb as *mut TransformCallback);
+	let cb = &*(raw_cb as *const TransformCallback);
 	cb.name.as_ptr()
 }
 
@@ -119,18 +177,22 @@ pub unsafe extern "C" fn transform_callback(
 	key_len: size_t,
 	dst_length: *mut size_t,
 ) -> *mut c_char {
-	let cb = &mut *(raw_cb as *mut TransformCallback);
-	let key = slice::from_raw_parts(raw_key as *const u8, key_len as usize);
-	let mut result = (cb.transform_fn)(key);
-    result.shrink_to_fit();
-
-    // copy the result into a C++ destroyable buffer
-    let buf = libc::malloc(result.len() as size_t);
-    assert!(!buf.is_null());
-    ptr::copy(result.as_ptr() as *mut c_void, &mut *buf, result.len());
-
-    *dst_length = result.len() as size_t;
-    buf as *mut c_char
+    let cb = &*(raw_cb as *const TransformCallback);
+    let key = slice::from_raw_parts(raw_key as *const u8, key_len as usize);
+    // rocksdb never frees the returned buffer, so it has to point into the
+    // key; a result that is not a prefix of it cannot be returned.
+    let len = abort_on_callback_panic("slice transform", || {
+        let result = (cb.transform_fn)(key);
+        assert!(
+            key.starts_with(&result),
+            "slice transform result {:?} is not a prefix of key {:?}",
+            result,
+            key
+        );
+        result.len()
+    });
+    *dst_length = len as size_t;
+    raw_key as *mut c_char
 }
 
 pub unsafe extern "C" fn in_domain_callback(
@@ -138,12 +200,160 @@ pub unsafe extern "C" fn in_domain_callback(
 	raw_key: *const c_char,
 	key_len: size_t,
 ) -> u8 {
-	let cb = &mut *(raw_cb as *mut TransformCallback);
-	let key = slice::from_raw_parts(raw_key as *const u8, key_len as usize);
+    let cb = &*(raw_cb as *const TransformCallback);
+    let key = slice::from_raw_parts(raw_key as *const u8, key_len as usize);
 
-    if (cb.in_domain_fn.unwrap())(key) {
+    if abort_on_callback_panic("slice transform in_domain", || (cb.in_domain_fn.unwrap())(key)) {
         1
     } else {
         0
     }
 }
+
+/// The callbacks of a custom prefix extractor, used with
+/// `SliceTransform::create_from`.
+///
+/// rocksdb calls the prefix extractor concurrently from foreground and
+/// background threads, so the methods take `&self` and any state must be
+/// `Send + Sync`; use atomics or locks for state that changes.
+///
+/// A panic in either method aborts the process, since no other prefix
+/// could be returned without breaking prefix seeks.
+pub trait SliceTransformFns: Send + Sync + 'static {
+    /// Returns the prefix of a key in the domain. The result must be a
+    /// prefix of `key` itself.
+    fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8];
+
+    /// Returns whether `key` has a prefix. Keys outside the domain are not
+    /// added to prefix blooms. Every key is in the domain by default.
+    fn in_domain(&self, _key: &[u8]) -> bool {
+        true
+    }
+}
+
+/// Checks that `fns` behaves as rocksdb expects of a prefix extractor for
+/// each of the sample `keys`, returning an `InvalidArgument` error
+/// describing the first violation. For every key in the domain:
+///
+/// - the prefix is a prefix of the key, is itself in the domain and is its
+///   own prefix;
+/// - keys made by appending bytes are in the domain and, unless the key is
+///   its own prefix, have the same prefix;
+/// - prefixes sort in the same order as their keys.
+///
+/// A prefix extractor that breaks these makes prefix seeks and prefix
+/// blooms silently skip keys, so this is meant to be run from tests.
+///
+/// # Example
+///
+/// ```
+/// use rocksdb::{SliceTransformFns, validate_slice_transform};
+///
+/// struct LastByte;
+///
+/// impl SliceTransformFns for LastByte {
+///     fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+///         &key[key.len() - 1..]
+///     }
+/// }
+///
+/// assert!(validate_slice_transform(&LastByte, &[b"ab"]).is_err());
+/// ```
+pub fn validate_slice_transform<T: SliceTransformFns>(
+    fns: &T,
+    keys: &[&[u8]],
+) -> Result<(), Error> {
+    let invalid = |msg: &str, key: &[u8]| {
+        Err(Error::with_kind(ErrorKind::InvalidArgument, format!("{} for key {:?}", msg, key)))
+    };
+
+    let mut prefixes = Vec::new();
+    for &key in keys {
+        if !fns.in_domain(key) {
+            continue;
+        }
+        let prefix = fns.transform(key);
+        if !key.starts_with(prefix) {
+            return invalid("transform result is not a prefix of the key", key);
+        }
+        if !fns.in_domain(prefix) {
+            return invalid("prefix is not in the domain", key);
+        }
+        if fns.transform(prefix) != prefix {
+            return invalid("prefix is not its own prefix", key);
+        }
+        for suffix in &[0u8, 0xff] {
+            let mut extended = key.to_vec();
+            extended.push(*suffix);
+            if !fns.in_domain(&extended) {
+                return invalid("appending to the key leaves the domain", key);
+            }
+            if prefix.len() < key.len() && fns.transform(&extended) != prefix {
+                return invalid("appending to the key changes its prefix", key);
+            }
+        }
+        prefixes.push((key, prefix));
+    }
+
+    prefixes.sort();
+    for pair in prefixes.windows(2) {
+        if pair[0].1 > pair[1].1 {
+            return invalid("prefix sorts after the prefix of a larger key", pair[0].0);
+        }
+    }
+    Ok(())
+}
+
+struct TransformFnsCallback<T: SliceTransformFns> {
+    name: CString,
+    fns: T,
+}
+
+unsafe extern "C" fn fns_destructor_callback<T: SliceTransformFns>(raw_cb: *mut c_void) {
+    let cb = Box::from_raw(raw_cb as *mut TransformFnsCallback<T>);
+    catch_callback_panic("slice transform destructor", || drop(cb));
+}
+
+unsafe extern "C" fn fns_name_callback<T: SliceTransformFns>(raw_cb: *mut c_void) -> *const c_char {
+    let cb = &*(raw_cb as *const TransformFnsCallback<T>);
+    cb.name.as_ptr()
+}
+
+// The prefix points into the key itself, as rocksdb expects, so nothing is
+// allocated per call.
+unsafe extern "C" fn fns_transform_callback<T: SliceTransformFns>(
+    raw_cb: *mut c_void,
+    raw_key: *const c_char,
+    key_len: size_t,
+    dst_length: *mut size_t,
+) -> *mut c_char {
+    let cb = &*(raw_cb as *const TransformFnsCallback<T>);
+    let key = slice::from_raw_parts(raw_key as *const u8, key_len as usize);
+    let prefix = abort_on_callback_panic("slice transform", || cb.fns.transform(key));
+    *dst_length = prefix.len() as size_t;
+    prefix.as_ptr() as *mut c_char
+}
+
+unsafe extern "C" fn fns_in_domain_callback<T: SliceTransformFns>(
+    raw_cb: *mut c_void,
+    raw_key: *const c_char,
+    key_len: size_t,
+) -> u8 {
+    let cb = &*(raw_cb as *const TransformFnsCallback<T>);
+    let key = slice::from_raw_parts(raw_key as *const u8, key_len as usize);
+    abort_on_callback_panic("slice transform in_domain", || cb.fns.in_domain(key)) as u8
+}
+
+struct CappedPrefix {
+    len: usize,
+}
+
+impl SliceTransformFns for CappedPrefix {
+    fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+        if key.len() < self.len {
+            key
+        } else {
+            &key[..self.len]
+        }
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structured access to the statistics collected after
+//! `Options::enable_statistics`, and to the sizes and counts of a column
+//! family.
+
+use std::collections::BTreeMap;
+
+use {ColumnFamily, DB, Error};
+
+/// Percentiles and totals of one RocksDB histogram, such as
+/// `rocksdb.db.get.micros`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+    /// The largest recorded value, if reported by this RocksDB version.
+    pub max: Option<f64>,
+    pub count: u64,
+    /// The sum of all recorded values, if reported by this RocksDB version.
+    pub sum: Option<u64>,
+}
+
+/// A point-in-time copy of every ticker and histogram of a statistics
+/// object.
+///
+/// # Examples
+///
+/// ```
+/// use rocksdb::{DB, Options};
+///
+/// let mut opts = Options::default();
+/// opts.create_if_missing(true);
+/// opts.enable_statistics();
+/// let db = DB::open(&opts, "path/for/rocksdb/storage_statistics").unwrap();
+/// db.put(b"k", b"v").unwrap();
+///
+/// let stats = opts.get_statistics_snapshot().unwrap();
+/// assert!(stats.tickers.contains_key("rocksdb.number.keys.written"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statistics {
+    /// Monotonic counters, keyed by ticker name.
+    pub tickers: BTreeMap<String, u64>,
+    /// Distributions, keyed by histogram name.
+    pub histograms: BTreeMap<String, Histogram>,
+}
+
+impl Statistics {
+    /// Parses the text returned by `Options::get_statistics`. Lines that
+    /// are not in the expected `name KEY : value ...` form are skipped.
+    pub fn parse(s: &str) -> Statistics {
+        let mut stats = Statistics::default();
+        for line in s.lines() {
+            let mut words = line.split_whitespace();
+            let name = match words.next() {
+                Some(name) => name,
+                None => continue,
+            };
+            let words: Vec<&str> = words.collect();
+            let mut fields = BTreeMap::new();
+            for field in words.chunks(3) {
+                if field.len() == 3 && field[1] == ":" {
+                    fields.insert(field[0], field[2]);
+                }
+            }
+
+            let count = match fields.get("COUNT").and_then(|v| v.parse().ok()) {
+                Some(count) => count,
+                None => continue,
+            };
+            let percentile = |key| fields.get(key).and_then(|v: &&str| v.parse().ok());
+            match (percentile("P50"), percentile("P95"), percentile("P99")) {
+                (Some(p50), Some(p95), Some(p99)) => {
+                    stats.histograms.insert(
+                        name.to_owned(),
+                        Histogram {
+                            p50: p50,
+                            p95: p95,
+                            p99: p99,
+                            max: percentile("P100"),
+                            count: count,
+                            sum: fields.get("SUM").and_then(|v| v.parse().ok()),
+                        },
+                    );
+                }
+                _ => {
+                    stats.tickers.insert(name.to_owned(), count);
+                }
+            }
+        }
+        stats
+    }
+}
+
+/// The cumulative compaction I/O of one level of a column family, as
+/// reported in the `Compaction Stats` table of `rocksdb.cfstats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelCompactionStats {
+    pub level: usize,
+    /// The number of files at the level.
+    pub files: u64,
+    /// Bytes read by compactions into the level, in GB rounded to 0.1.
+    pub read_gb: f64,
+    /// Bytes written by compactions and flushes into the level, in GB
+    /// rounded to 0.1.
+    pub write_gb: f64,
+    pub compaction_secs: f64,
+    pub compaction_count: u64,
+}
+
+/// Parses the `Compaction Stats` table of the text of `rocksdb.cfstats`.
+/// Rows other than those of single levels, such as `Sum`, are skipped, as
+/// are rows that are not in the expected form.
+pub fn parse_compaction_stats(s: &str) -> Vec<LevelCompactionStats> {
+    let mut header: Vec<&str> = Vec::new();
+    let mut levels = Vec::new();
+    for line in s.lines() {
+        let mut row: Vec<&str> = line.split_whitespace().collect();
+        if row.first() == Some(&"Level") {
+            header = row;
+            continue;
+        }
+        let level = match row.first() {
+            Some(name) if name.starts_with('L') => name[1..].parse().ok(),
+            _ => None,
+        };
+        let level = match level {
+            Some(level) => level,
+            None => continue,
+        };
+        // The size column is printed with its unit, e.g. `1.77 KB`.
+        if row.len() == header.len() + 1 {
+            row.remove(3);
+        }
+        if row.len() != header.len() {
+            continue;
+        }
+        let column = |name| header.iter().position(|&column| column == name).map(|i| row[i]);
+        let number = |name| column(name).and_then(|v| v.parse().ok());
+        let files = column("Files").and_then(|v| v.split('/').next()).and_then(|v| v.parse().ok());
+        let count = column("Comp(cnt)").and_then(|v| v.parse().ok());
+        match (files, number("Read(GB)"), number("Write(GB)"), number("Comp(sec)"), count) {
+            (Some(files), Some(read_gb), Some(write_gb), Some(secs), Some(count)) => {
+                levels.push(LevelCompactionStats {
+                    level: level,
+                    files: files,
+                    read_gb: read_gb,
+                    write_gb: write_gb,
+                    compaction_secs: secs,
+                    compaction_count: count,
+                });
+            }
+            _ => continue,
+        }
+    }
+    levels
+}
+
+/// Sizes and counts of one column family, read from its properties by
+/// `DB::cf_stats`. A field is `None` if this RocksDB version does not
+/// report it.
+///
+/// Statistics objects are shared by every column family of a database, so
+/// these are the way to tell column families apart, e.g. the tenants of a
+/// multi-tenant deployment: the I/O of each tenant's flushes and
+/// compactions is in `compaction_stats`. Block cache hits and misses are
+/// only counted per database.
+///
+/// # Examples
+///
+/// ```
+/// use rocksdb::{DB, Options};
+///
+/// let mut opts = Options::default();
+/// opts.create_if_missing(true);
+/// opts.create_missing_column_families(true);
+/// let db = DB::open_cf(&opts, "path/for/rocksdb/storage_cf_stats", &["tenant1"]).unwrap();
+/// let tenant1 = db.cf_handle("tenant1").unwrap();
+/// db.put_cf(tenant1, b"k", b"v").unwrap();
+///
+/// let stats = db.cf_stats(Some(tenant1)).unwrap();
+/// assert_eq!(stats.num_entries_active_mem_table, Some(1));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnFamilyStats {
+    /// `rocksdb.estimate-num-keys`
+    pub estimate_num_keys: Option<u64>,
+    /// `rocksdb.estimate-live-data-size`
+    pub estimate_live_data_size: Option<u64>,
+    /// `rocksdb.total-sst-files-size`, including obsolete files still
+    /// referenced by iterators or snapshots.
+    pub total_sst_files_size: Option<u64>,
+    /// `rocksdb.live-sst-files-size`
+    pub live_sst_files_size: Option<u64>,
+    /// `rocksdb.estimate-pending-compaction-bytes`
+    pub estimate_pending_compaction_bytes: Option<u64>,
+    /// `rocksdb.estimate-table-readers-mem`, the memory used by indexes and
+    /// filters outside of the block cache.
+    pub estimate_table_readers_mem: Option<u64>,
+    /// `rocksdb.cur-size-all-mem-tables`, the size of the active and
+    /// unflushed immutable memtables.
+    pub cur_size_all_mem_tables: Option<u64>,
+    /// `rocksdb.size-all-mem-tables`, including the memtables still pinned
+    /// by iterators.
+    pub size_all_mem_tables: Option<u64>,
+    /// `rocksdb.num-immutable-mem-table`
+    pub num_immutable_mem_table: Option<u64>,
+    /// `rocksdb.num-entries-active-mem-table`
+    pub num_entries_active_mem_table: Option<u64>,
+    /// `rocksdb.num-deletes-active-mem-table`
+    pub num_deletes_active_mem_table: Option<u64>,
+    /// `rocksdb.num-files-at-level<N>`, for every level.
+    pub num_files_at_level: Vec<u64>,
+    /// The compaction I/O of every level listed in `rocksdb.cfstats`, i.e.
+    /// those that have files or were compacted into.
+    pub compaction_stats: Vec<LevelCompactionStats>,
+}
+
+impl DB {
+    /// Reads the sizes and counts of `cf`, or of the default column family.
+    pub fn cf_stats(&self, cf: Option<&ColumnFamily>) -> Result<ColumnFamilyStats, Error> {
+        let property = |name: &str| match cf {
+            Some(cf) => self.property_int_value_cf(cf, name),
+            None => self.property_int_value(name),
+        };
+        let mut num_files_at_level = Vec::new();
+        while let Some(files) =
+            try!(property(&format!("rocksdb.num-files-at-level{}", num_files_at_level.len())))
+        {
+            num_files_at_level.push(files);
+        }
+        let cfstats = try!(match cf {
+            Some(cf) => self.property_value_cf(cf, "rocksdb.cfstats"),
+            None => self.property_value("rocksdb.cfstats"),
+        });
+        Ok(ColumnFamilyStats {
+            estimate_num_keys: try!(property("rocksdb.estimate-num-keys")),
+            estimate_live_data_size: try!(property("rocksdb.estimate-live-data-size")),
+            total_sst_files_size: try!(property("rocksdb.total-sst-files-size")),
+            live_sst_files_size: try!(property("rocksdb.live-sst-files-size")),
+            estimate_pending_compaction_bytes: try!(
+                property("rocksdb.estimate-pending-compaction-bytes")
+            ),
+            estimate_table_readers_mem: try!(property("rocksdb.estimate-table-readers-mem")),
+            cur_size_all_mem_tables: try!(property("rocksdb.cur-size-all-mem-tables")),
+            size_all_mem_tables: try!(property("rocksdb.size-all-mem-tables")),
+            num_immutable_mem_table: try!(property("rocksdb.num-immutable-mem-table")),
+            num_entries_active_mem_table: try!(property("rocksdb.num-entries-active-mem-table")),
+            num_deletes_active_mem_table: try!(property("rocksdb.num-deletes-active-mem-table")),
+            num_files_at_level: num_files_at_level,
+            compaction_stats: cfstats.map_or_else(Vec::new, |s| parse_compaction_stats(&s)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_compaction_stats, Histogram, LevelCompactionStats, Statistics};
+
+    #[test]
+    fn test_parse() {
+        let stats = Statistics::parse(
+            "rocksdb.block.cache.miss COUNT : 12\n\
+             rocksdb.db.get.micros P50 : 1.500000 P95 : 3.000000 P99 : 4.250000 \
+             P100 : 9.000000 COUNT : 7 SUM : 20\n\
+             rocksdb.db.write.micros P50 : 2.000000 P95 : 2.000000 P99 : 2.000000 COUNT : 1\n\
+             garbage\n",
+        );
+        assert_eq!(stats.tickers.len(), 1);
+        assert_eq!(stats.tickers["rocksdb.block.cache.miss"], 12);
+        assert_eq!(
+            stats.histograms["rocksdb.db.get.micros"],
+            Histogram {
+                p50: 1.5,
+                p95: 3.0,
+                p99: 4.25,
+                max: Some(9.0),
+                count: 7,
+                sum: Some(20),
+            }
+        );
+        assert_eq!(stats.histograms["rocksdb.db.write.micros"].max, None);
+    }
+
+    #[test]
+    fn test_parse_compaction_stats() {
+        let levels = parse_compaction_stats(
+            "\n** Compaction Stats [cf1] **\n\
+             Level    Files   Size     Score Read(GB)  Rn(GB) Rnp1(GB) Write(GB) Wnew(GB) \
+             Moved(GB) W-Amp Rd(MB/s) Wr(MB/s) Comp(sec) Comp(cnt) Avg(sec) KeyIn KeyDrop\n\
+             ----------------------------------------------------------------------\n\
+             \x20 L0      2/0    1.77 KB   0.5      0.0     0.0      0.0       0.3      0.3  \
+             \x20    0.0   1.0      0.0      0.4         1         2    0.002       0      0\n\
+             \x20 L1      1/0   10.00 MB   0.1      1.5     0.3      1.2       1.4      0.2  \
+             \x20    0.0   1.0     40.0     38.0        12         3    4.000     10K    100\n\
+             \x20Sum      3/0   10.00 MB   0.0      1.5     0.3      1.2       1.7      0.5  \
+             \x20    0.0   1.0     40.0     38.0        13         5    2.600     10K    100\n\
+             Uptime(secs): 1.0 total, 1.0 interval\n",
+        );
+        assert_eq!(
+            levels,
+            vec![
+                LevelCompactionStats {
+                    level: 0,
+                    files: 2,
+                    read_gb: 0.0,
+                    write_gb: 0.3,
+                    compaction_secs: 1.0,
+                    compaction_count: 2,
+                },
+                LevelCompactionStats {
+                    level: 1,
+                    files: 1,
+                    read_gb: 1.5,
+                    write_gb: 1.4,
+                    compaction_secs: 12.0,
+                    compaction_count: 3,
+                },
+            ]
+        );
+    }
+}
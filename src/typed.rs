@@ -0,0 +1,221 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed access to a `DB`, available with the `typed` feature.
+//!
+//! `TypedDb<K, V>` stores keys with the order-preserving encoding of the
+//! `keys` module, so iteration follows the natural order of `K`, and values
+//! serialized with `bincode`.
+//!
+//! # Examples
+//!
+//! ```
+//! use rocksdb::{DB, Options};
+//! use rocksdb::typed::TypedDb;
+//!
+//! let mut opts = Options::default();
+//! opts.create_if_missing(true);
+//! opts.create_missing_column_families(true);
+//! let db = DB::open_cf(&opts, "path/for/rocksdb/storage_typed", &["ages"]).unwrap();
+//!
+//! let names: TypedDb<u32, String> = TypedDb::new(db);
+//! names.put(&2, &"grace".to_owned()).unwrap();
+//! names.put(&1, &"ada".to_owned()).unwrap();
+//! assert_eq!(names.get(&1).unwrap(), Some("ada".to_owned()));
+//!
+//! let ages: TypedDb<String, u32> = names.column_family("ages").unwrap();
+//! ages.put(&"ada".to_owned(), &36).unwrap();
+//!
+//! let all: Vec<(u32, String)> = names.iter().map(|r| r.unwrap()).collect();
+//! assert_eq!(all, vec![(1, "ada".to_owned()), (2, "grace".to_owned())]);
+//! ```
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use bincode;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use {ColumnFamily, DB, DBIterator, Direction, Error, ErrorKind, IteratorMode, WriteBatch};
+use keys::{self, KeyDecode, KeyEncode};
+
+/// A `DB`, or one of its column families, storing values of type `V`
+/// under keys of type `K`.
+pub struct TypedDb<K, V> {
+    db: Arc<DB>,
+    cf: Option<String>,
+    types: PhantomData<fn(K, V) -> (K, V)>,
+}
+
+impl<K, V> Clone for TypedDb<K, V> {
+    fn clone(&self) -> TypedDb<K, V> {
+        TypedDb {
+            db: self.db.clone(),
+            cf: self.cf.clone(),
+            types: PhantomData,
+        }
+    }
+}
+
+fn encode_value<V: Serialize>(value: &V) -> Result<Vec<u8>, Error> {
+    bincode::serialize(value).map_err(|e| {
+        Error::with_kind(ErrorKind::InvalidArgument, format!("cannot serialize value: {}", e))
+    })
+}
+
+fn decode_value<V: DeserializeOwned>(bytes: &[u8]) -> Result<V, Error> {
+    bincode::deserialize(bytes).map_err(|e| {
+        Error::with_kind(ErrorKind::Corruption, format!("cannot deserialize value: {}", e))
+    })
+}
+
+impl<K, V> TypedDb<K, V>
+where
+    K: KeyEncode + KeyDecode,
+    V: Serialize + DeserializeOwned,
+{
+    /// Stores the values in the default column family of `db`.
+    pub fn new(db: DB) -> TypedDb<K, V> {
+        TypedDb::from_arc(Arc::new(db))
+    }
+
+    /// Wraps a database that is also used elsewhere.
+    pub fn from_arc(db: Arc<DB>) -> TypedDb<K, V> {
+        TypedDb {
+            db: db,
+            cf: None,
+            types: PhantomData,
+        }
+    }
+
+    /// Returns a handle storing values of other types in the column family
+    /// `name` of the same database.
+    pub fn column_family<K2, V2>(&self, name: &str) -> Result<TypedDb<K2, V2>, Error>
+    where
+        K2: KeyEncode + KeyDecode,
+        V2: Serialize + DeserializeOwned,
+    {
+        if self.db.cf_handle(name).is_none() {
+            return Err(Error::with_kind(ErrorKind::InvalidArgument,
+                                        format!("no column family {}", name)));
+        }
+        Ok(TypedDb {
+            db: self.db.clone(),
+            cf: Some(name.to_owned()),
+            types: PhantomData,
+        })
+    }
+
+    /// Returns the underlying database.
+    pub fn db(&self) -> &Arc<DB> {
+        &self.db
+    }
+
+    fn cf(&self) -> Option<&ColumnFamily> {
+        // Column families can only be dropped through `&mut DB`, which
+        // cannot be obtained while the `Arc` is shared with this handle.
+        self.cf.as_ref().map(|name| self.db.cf_handle(name).unwrap())
+    }
+
+    pub fn get(&self, key: &K) -> Result<Option<V>, Error> {
+        let key = keys::encode(key);
+        let value = match self.cf() {
+            Some(cf) => try!(self.db.get_cf(cf, &key)),
+            None => try!(self.db.get(&key)),
+        };
+        match value {
+            Some(value) => Ok(Some(try!(decode_value(&value)))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put(&self, key: &K, value: &V) -> Result<(), Error> {
+        let key = keys::encode(key);
+        let value = try!(encode_value(value));
+        match self.cf() {
+            Some(cf) => self.db.put_cf(cf, &key, &value),
+            None => self.db.put(&key, &value),
+        }
+    }
+
+    pub fn delete(&self, key: &K) -> Result<(), Error> {
+        let key = keys::encode(key);
+        match self.cf() {
+            Some(cf) => self.db.delete_cf(cf, &key),
+            None => self.db.delete(&key),
+        }
+    }
+
+    /// Writes several entries in one atomic batch.
+    pub fn put_all<'a, I>(&self, entries: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = (&'a K, &'a V)>,
+        K: 'a,
+        V: 'a,
+    {
+        let mut batch = WriteBatch::default();
+        for (key, value) in entries {
+            let key = keys::encode(key);
+            let value = try!(encode_value(value));
+            match self.cf() {
+                Some(cf) => try!(batch.put_cf(cf, &key, &value)),
+                None => try!(batch.put(&key, &value)),
+            }
+        }
+        self.db.write(batch)
+    }
+
+    fn iter_mode(&self, mode: IteratorMode) -> TypedIterator<K, V> {
+        let inner = match self.cf() {
+            // Creating a column family iterator cannot fail.
+            Some(cf) => self.db.iterator_cf(cf, mode).unwrap(),
+            None => self.db.iterator(mode),
+        };
+        TypedIterator {
+            inner: inner,
+            types: PhantomData,
+        }
+    }
+
+    /// Iterates over all entries in key order.
+    pub fn iter(&self) -> TypedIterator<K, V> {
+        self.iter_mode(IteratorMode::Start)
+    }
+
+    /// Iterates over the entries from `key`, or the entry closest to it, in
+    /// `direction`.
+    pub fn iter_from(&self, key: &K, direction: Direction) -> TypedIterator<K, V> {
+        self.iter_mode(IteratorMode::From(&keys::encode(key), direction))
+    }
+}
+
+/// An iterator over the entries of a `TypedDb`. Entries that fail to decode
+/// are returned as `ErrorKind::Corruption` errors.
+pub struct TypedIterator<K, V> {
+    inner: DBIterator,
+    types: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K: KeyDecode, V: DeserializeOwned> Iterator for TypedIterator<K, V> {
+    type Item = Result<(K, V), Error>;
+
+    fn next(&mut self) -> Option<Result<(K, V), Error>> {
+        self.inner.next().map(|(key, value)| {
+            let key = try!(keys::decode(&key));
+            let value = try!(decode_value(&value));
+            Ok((key, value))
+        })
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use rocksdb::{DB, Options};
+use rocksdb::admin::Admin;
+
+#[test]
+pub fn test_admin() {
+    let path = "_rust_rocksdb_admintest";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let mut db = DB::open(&opts, path).unwrap();
+        db.create_cf("cf1", &Options::default()).unwrap();
+        db.put(b"a", b"1").unwrap();
+        db.put(b"b", b"2").unwrap();
+    }
+    {
+        let admin = Admin::open(path).unwrap();
+        assert_eq!(admin.column_families(), vec!["default".to_owned(), "cf1".to_owned()]);
+
+        admin.put(Some("cf1"), b"c", b"3").unwrap();
+        assert_eq!(admin.get(Some("cf1"), b"c").unwrap(), Some(b"3".to_vec()));
+        assert_eq!(admin.get(None, b"c").unwrap(), None);
+        assert!(admin.get(Some("missing"), b"c").is_err());
+
+        let keys: Vec<_> = admin.scan(None, Some(b"b"), 10)
+            .unwrap()
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, vec![b"b".to_vec().into_boxed_slice()]);
+
+        admin.delete(None, b"a").unwrap();
+        assert_eq!(admin.get(None, b"a").unwrap(), None);
+        assert!(admin.property(Some("cf1"), "rocksdb.stats").unwrap().is_some());
+
+        admin.db().compact_range(None, None);
+        assert!(admin.manifest(None).unwrap().contains("'b'"));
+        assert!(!admin.manifest(Some("cf1")).unwrap().contains("'b'"));
+        assert!(admin.manifest(Some("missing")).is_err());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
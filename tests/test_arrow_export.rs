@@ -0,0 +1,108 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+#![cfg(feature = "arrow")]
+
+extern crate arrow;
+extern crate rocksdb;
+
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BinaryArray, UInt64Array, UInt64Builder};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use rocksdb::{DB, ErrorKind, Options};
+use rocksdb::arrow_export::{BinaryDecoder, Decoder, RecordBatches};
+
+// Decodes big-endian `u64` keys and values into `id` and `count` columns.
+struct CountDecoder {
+    ids: UInt64Builder,
+    counts: UInt64Builder,
+}
+
+fn be_u64(bytes: &[u8]) -> Result<u64, ArrowError> {
+    if bytes.len() != 8 {
+        return Err(ArrowError::ParseError("expected 8 bytes".to_owned()));
+    }
+    Ok(bytes.iter().fold(0, |n, &b| n << 8 | b as u64))
+}
+
+impl Decoder for CountDecoder {
+    fn schema(&self) -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::UInt64, false),
+            Field::new("count", DataType::UInt64, false),
+        ]))
+    }
+
+    fn append(&mut self, key: &[u8], value: &[u8]) -> Result<(), ArrowError> {
+        self.ids.append_value(try!(be_u64(key)));
+        self.counts.append_value(try!(be_u64(value)));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<Vec<ArrayRef>, ArrowError> {
+        Ok(vec![Arc::new(self.ids.finish()), Arc::new(self.counts.finish())])
+    }
+}
+
+fn be(n: u64) -> [u8; 8] {
+    let mut bytes = [0; 8];
+    for i in 0..8 {
+        bytes[i] = (n >> (56 - 8 * i)) as u8;
+    }
+    bytes
+}
+
+#[test]
+pub fn test_arrow_export() {
+    let path = "_rust_rocksdb_arrow_export";
+    {
+        let db = DB::open_default(path).unwrap();
+        for i in 0..10 {
+            db.put(&be(i), &be(i * 10)).unwrap();
+        }
+
+        let batches: Vec<_> = RecordBatches::new(&db, None, None, None, 4, BinaryDecoder::new())
+            .unwrap()
+            .map(|batch| batch.unwrap())
+            .collect();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(), vec![4, 4, 2]);
+        let keys = batches[1].column(0).as_any().downcast_ref::<BinaryArray>().unwrap();
+        assert_eq!(keys.value(0), &be(4)[..]);
+
+        let decoder = CountDecoder {
+            ids: UInt64Builder::new(),
+            counts: UInt64Builder::new(),
+        };
+        let batches = RecordBatches::new(&db, None, Some(&be(3)), Some(&be(7)), 100, decoder)
+            .unwrap();
+        assert_eq!(batches.schema().fields()[1].name(), "count");
+        let batches: Vec<_> = batches.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 4);
+        let counts = batches[0].column(1).as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(counts.value(3), 60);
+
+        db.put(b"bad", b"value").unwrap();
+        let decoder = CountDecoder {
+            ids: UInt64Builder::new(),
+            counts: UInt64Builder::new(),
+        };
+        let mut batches = RecordBatches::new(&db, None, None, None, 100, decoder).unwrap();
+        assert_eq!(batches.next().unwrap().unwrap_err().kind(), ErrorKind::Corruption);
+        assert!(batches.next().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
@@ -0,0 +1,66 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+#![cfg(feature = "tokio")]
+
+extern crate rocksdb;
+extern crate tokio;
+
+use rocksdb::{DB, Direction, Options, WriteBatch};
+use rocksdb::async_db::AsyncDB;
+
+#[test]
+pub fn test_async_db() {
+    let path = "_rust_rocksdb_asynctest";
+    {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let db = AsyncDB::new(DB::open_default(path).unwrap());
+
+        runtime.block_on(db.put(b"k1", b"v1")).unwrap();
+        let mut batch = WriteBatch::default();
+        batch.put(b"k2", b"v2").unwrap();
+        batch.put(b"k3", b"v3").unwrap();
+        runtime.block_on(db.write(batch)).unwrap();
+        runtime.block_on(db.delete(b"k3")).unwrap();
+
+        assert_eq!(runtime.block_on(db.get(b"k1")).unwrap(), Some(b"v1".to_vec()));
+        assert_eq!(
+            runtime.block_on(db.multi_get(&[b"k1", b"k2", b"k3"])).unwrap(),
+            vec![Some(b"v1".to_vec()), Some(b"v2".to_vec()), None]
+        );
+
+        let keys: Vec<_> = runtime
+            .block_on(db.scan(None, Direction::Reverse, 10))
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(keys, vec![b"k2".to_vec().into_boxed_slice(), b"k1".to_vec().into_boxed_slice()]);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_future_created_outside_runtime() {
+    let path = "_rust_rocksdb_async_outside_runtime_test";
+    {
+        let db = AsyncDB::new(DB::open_default(path).unwrap());
+        let put = db.put(b"k1", b"v1");
+        let get = db.get(b"k1");
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(put).unwrap();
+        assert_eq!(runtime.block_on(get).unwrap(), Some(b"v1".to_vec()));
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
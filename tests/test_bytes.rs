@@ -0,0 +1,61 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+#![cfg(feature = "bytes")]
+
+extern crate bytes;
+extern crate rocksdb;
+
+use bytes::{Bytes, BytesMut};
+use rocksdb::{DB, IteratorMode, Options};
+
+#[test]
+pub fn test_bytes() {
+    let path = "_rust_rocksdb_bytes";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open_cf(&opts, path, &["cf1"]).unwrap();
+        let cf1 = db.cf_handle("cf1").unwrap();
+
+        db.put(b"k1", &Bytes::from_static(b"v1")).unwrap();
+        let mut value = BytesMut::new();
+        value.extend_from_slice(b"v2");
+        db.put(b"k2", &value).unwrap();
+        db.put_cf(cf1, b"k3", b"v3").unwrap();
+
+        assert_eq!(db.get_bytes(b"k1").unwrap(), Some(Bytes::from_static(b"v1")));
+        assert_eq!(db.get_bytes(b"missing").unwrap(), None);
+        assert_eq!(db.get_bytes_cf(cf1, b"k3").unwrap(), Some(Bytes::from_static(b"v3")));
+        let value: Bytes = db.get(b"k2").unwrap().unwrap().into();
+        assert_eq!(value, Bytes::from_static(b"v2"));
+
+        let mut buf = BytesMut::new();
+        assert_eq!(db.get_into_bytes(b"k2", &mut buf).unwrap(), Some(2));
+        assert_eq!(&buf[..], b"v2");
+        assert_eq!(db.get_into_bytes(b"missing", &mut buf).unwrap(), None);
+        assert_eq!(buf.len(), 0);
+
+        let entries: Vec<(Bytes, Bytes)> = db.iterator(IteratorMode::Start).into_bytes().collect();
+        assert_eq!(
+            entries,
+            vec![
+                (Bytes::from_static(b"k1"), Bytes::from_static(b"v1")),
+                (Bytes::from_static(b"k2"), Bytes::from_static(b"v2")),
+            ]
+        );
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
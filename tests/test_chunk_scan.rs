@@ -0,0 +1,59 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use rocksdb::{DB, Options};
+
+#[test]
+pub fn test_scan_chunks() {
+    let path = "_rust_rocksdb_scan_chunks";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open_cf(&opts, path, &["cf1"]).unwrap();
+        let cf1 = db.cf_handle("cf1").unwrap();
+        for i in 0..100u8 {
+            db.put(&[i], &[i, i]).unwrap();
+            db.put_cf(cf1, &[i], &[i]).unwrap();
+        }
+
+        let mut scan = db.scan_chunks(Some(&[10]), Some(&[60]), 16);
+        let mut keys = Vec::new();
+        let mut sizes = Vec::new();
+        while let Some(chunk) = scan.next_chunk().unwrap() {
+            assert_eq!(chunk.size(), chunk.len() * 3);
+            for (key, value) in chunk {
+                assert_eq!(value, &[key[0], key[0]][..]);
+                keys.push(key[0]);
+            }
+            sizes.push(chunk.len());
+        }
+        assert_eq!(keys, (10..60).collect::<Vec<u8>>());
+        assert_eq!(sizes, vec![16, 16, 16, 2]);
+        assert!(scan.next_chunk().unwrap().is_none());
+
+        let mut scan = db.scan_chunks_cf(cf1, None, None, 0).unwrap();
+        let chunk = scan.next_chunk().unwrap().unwrap();
+        assert_eq!(chunk.len(), 1);
+        assert_eq!(chunk.get(0), Some((&[0][..], &[0][..])));
+        assert!(chunk.get(1).is_none());
+
+        let mut scan = db.scan_chunks_cf(cf1, None, None, 1000).unwrap();
+        assert_eq!(scan.next_chunk().unwrap().unwrap().len(), 100);
+        assert!(scan.next_chunk().unwrap().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
@@ -15,6 +15,8 @@
 extern crate rocksdb;
 
 use rocksdb::{DB, MergeOperands, Options, ColumnFamilyDescriptor};
+use std::path::Path;
+use std::time::Duration;
 
 #[test]
 pub fn test_column_family() {
@@ -216,4 +218,64 @@ pub fn test_column_family_with_options() {
     }
 
     assert!(DB::destroy(&Options::default(), path).is_ok());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_drop_cf_and_wait() {
+    let path = "_rust_rocksdb_drop_cf_wait_test";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let mut db = DB::open(&opts, path).unwrap();
+        db.create_cf("cf1", &Options::default()).unwrap();
+        {
+            let cf1 = db.cf_handle("cf1").unwrap();
+            db.put_cf(cf1, b"k1", b"v1").unwrap();
+            db.compact_range_cf(cf1, None, None);
+        }
+        let files = db.get_live_files(false).unwrap();
+        assert_eq!(files.len(), 1);
+
+        db.drop_cf_and_wait("cf1", Duration::from_secs(10)).unwrap();
+        assert!(db.cf_handle("cf1").is_none());
+        assert!(db.get_live_files(false).unwrap().is_empty());
+        let name = files[0].name.trim_start_matches('/');
+        assert!(!Path::new(path).join(name).exists());
+        assert!(db.drop_cf("cf1").is_err());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_cf_stats() {
+    let path = "_rust_rocksdb_cf_stats_test";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let mut cf_opts = Options::default();
+        cf_opts.set_num_levels(4);
+        let cf = ColumnFamilyDescriptor::new("cf1", cf_opts);
+        let db = DB::open_cf_descriptors(&opts, path, vec![cf]).unwrap();
+        let cf1 = db.cf_handle("cf1").unwrap();
+        db.put_cf(cf1, b"k1", b"v1").unwrap();
+        db.put_cf(cf1, b"k2", b"v2").unwrap();
+        db.delete_cf(cf1, b"k1").unwrap();
+        db.put(b"k1", b"v1").unwrap();
+
+        let stats = db.cf_stats(Some(cf1)).unwrap();
+        assert_eq!(stats.num_entries_active_mem_table, Some(3));
+        assert_eq!(stats.num_deletes_active_mem_table, Some(1));
+        assert_eq!(stats.num_files_at_level, vec![0; 4]);
+        assert_eq!(db.cf_stats(None).unwrap().num_entries_active_mem_table, Some(1));
+
+        db.compact_range_cf(cf1, None, None);
+        let stats = db.cf_stats(Some(cf1)).unwrap();
+        assert_eq!(stats.num_entries_active_mem_table, Some(0));
+        assert_eq!(stats.num_files_at_level.iter().sum::<u64>(), 1);
+        assert_eq!(stats.compaction_stats.iter().map(|level| level.files).sum::<u64>(), 1);
+        assert!(stats.total_sst_files_size.unwrap() > 0);
+        assert_eq!(db.cf_stats(None).unwrap().total_sst_files_size, Some(0));
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
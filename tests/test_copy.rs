@@ -0,0 +1,67 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use rocksdb::{DB, Options};
+use rocksdb::copy::SnapshotCopy;
+
+#[test]
+pub fn test_snapshot_copy() {
+    let src_path = "_rust_rocksdb_copy_src";
+    let dst_path = "_rust_rocksdb_copy_dst";
+    let all_path = "_rust_rocksdb_copy_all";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open_cf(&opts, src_path, &["cf1", "cf2"]).unwrap();
+        let cf1 = db.cf_handle("cf1").unwrap();
+        for i in 0..100u32 {
+            db.put_cf(cf1, format!("k{:03}", i).as_bytes(), b"value").unwrap();
+        }
+        db.put(b"default", b"value").unwrap();
+        db.put_cf(db.cf_handle("cf2").unwrap(), b"cf2", b"value").unwrap();
+
+        let mut reports = Vec::new();
+        let copied = SnapshotCopy::new(&db)
+            .column_family("cf1")
+            .column_family("default")
+            .batch_bytes(100)
+            .on_progress(|progress| reports.push(progress.clone()))
+            .run(&Options::default(), dst_path)
+            .unwrap();
+        assert_eq!(copied.keys, 101);
+        assert_eq!(copied.bytes, 100 * 9 + 12);
+        assert!(reports.len() > 2);
+        assert_eq!(reports.last(), Some(&copied));
+
+        // The destination must be new.
+        assert!(SnapshotCopy::new(&db).run(&Options::default(), dst_path).is_err());
+        let missing = SnapshotCopy::new(&db).column_family("missing");
+        assert!(missing.run(&Options::default(), all_path).is_err());
+
+        let dst = DB::open_cf(&Options::default(), dst_path, &["cf1"]).unwrap();
+        let dst_cf1 = dst.cf_handle("cf1").unwrap();
+        assert_eq!(&*dst.get_cf(dst_cf1, b"k042").unwrap().unwrap(), b"value");
+        assert_eq!(&*dst.get(b"default").unwrap().unwrap(), b"value");
+        assert!(dst.cf_handle("cf2").is_none());
+
+        let copied = SnapshotCopy::new(&db).run(&Options::default(), all_path).unwrap();
+        assert_eq!(copied.keys, 102);
+    }
+    assert!(DB::destroy(&Options::default(), src_path).is_ok());
+    assert!(DB::destroy(&Options::default(), dst_path).is_ok());
+    assert!(DB::destroy(&Options::default(), all_path).is_ok());
+}
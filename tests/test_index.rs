@@ -0,0 +1,83 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use rocksdb::{DB, Options};
+use rocksdb::index::IndexedDB;
+
+// Records are comma-separated tags.
+fn tags(_: &[u8], value: &[u8]) -> Vec<Vec<u8>> {
+    value.split(|&b| b == b',').map(|tag| tag.to_vec()).collect()
+}
+
+#[test]
+pub fn test_index() {
+    let path = "_rust_rocksdb_indextest";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let mut db = IndexedDB::new(DB::open_cf(&opts, path, &["tags"]).unwrap());
+        assert!(db.add_index("missing", tags).is_err());
+        db.add_index("tags", tags).unwrap();
+        assert!(db.add_index("tags", tags).is_err());
+
+        db.put(b"1", b"a,ab").unwrap();
+        db.put(b"2", b"ab").unwrap();
+        db.put(b"10", b"a").unwrap();
+        db.put(b"b1", b"a").unwrap();
+        assert_eq!(
+            db.lookup("tags", b"a").unwrap(),
+            vec![b"1".to_vec(), b"10".to_vec(), b"b1".to_vec()]
+        );
+        // "ab" + "1" and "a" + "b1" are distinct entries.
+        assert_eq!(db.lookup("tags", b"ab").unwrap(), vec![b"1".to_vec(), b"2".to_vec()]);
+        assert!(db.lookup("tags", b"b").unwrap().is_empty());
+        assert!(db.lookup("missing", b"a").is_err());
+
+        db.put(b"1", b"b").unwrap();
+        db.delete(b"10").unwrap();
+        assert_eq!(db.lookup("tags", b"a").unwrap(), vec![b"b1".to_vec()]);
+        assert_eq!(
+            db.lookup_records("tags", b"b").unwrap(),
+            vec![(b"1".to_vec(), b"b".to_vec())]
+        );
+        assert_eq!(db.get(b"10").unwrap(), None);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_rebuild_index() {
+    let path = "_rust_rocksdb_rebuildindextest";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open_cf(&opts, path, &["tags"]).unwrap();
+        db.put(b"1", b"a").unwrap();
+        db.put_cf(db.cf_handle("tags").unwrap(), b"stale1\0\0\0\x05", b"").unwrap();
+
+        let mut db = IndexedDB::new(db);
+        db.add_index("tags", tags).unwrap();
+        db.put(b"2", b"a,b").unwrap();
+        assert_eq!(db.lookup("tags", b"a").unwrap(), vec![b"2".to_vec()]);
+
+        db.rebuild_index("tags").unwrap();
+        assert_eq!(db.lookup("tags", b"a").unwrap(), vec![b"1".to_vec(), b"2".to_vec()]);
+        assert!(db.lookup("tags", b"stale").unwrap().is_empty());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
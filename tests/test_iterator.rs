@@ -14,7 +14,7 @@
 //
 extern crate rocksdb;
 
-use rocksdb::{DB, Direction, IteratorMode, Options};
+use rocksdb::{DB, Direction, ErrorKind, IteratorMode, Options, ReadOptions};
 
 fn cba(input: &Box<[u8]>) -> Box<[u8]> {
     input.iter().cloned().collect::<Vec<_>>().into_boxed_slice()
@@ -194,3 +194,53 @@ pub fn test_prefix_iterator() {
         }
     }
 }
+
+#[test]
+pub fn test_close_with_live_iterators() {
+    let path = "_rust_rocksdb_close_live_iterators_test";
+    {
+        let db = DB::open_default(path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        let iter = db.iterator(IteratorMode::Start);
+        let raw = db.raw_iterator();
+
+        let (db, err) = db.close().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Busy);
+        assert!(err.to_string().contains("2 iterators"));
+        assert_eq!(iter.count(), 1);
+
+        let (db, _) = db.close().unwrap_err();
+        drop(raw);
+        assert!(db.close().is_ok());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_max_skippable_internal_keys() {
+    let path = "_rust_rocksdb_max_skippable_internal_keys_test";
+    {
+        let db = DB::open_default(path).unwrap();
+        for i in 0..100u8 {
+            db.put(&[i], b"v").unwrap();
+        }
+        for i in 0..99u8 {
+            db.delete(&[i]).unwrap();
+        }
+
+        let iter = db.iterator(IteratorMode::Start);
+        assert_eq!(iter.count(), 1);
+
+        let mut readopts = ReadOptions::default();
+        readopts.set_max_skippable_internal_keys(10);
+        let mut iter = db.iterator_opt(IteratorMode::Start, readopts);
+        assert!(iter.next().is_none());
+        assert_eq!(iter.status().unwrap_err().kind(), ErrorKind::Incomplete);
+
+        let mut iter = db.iterator(IteratorMode::Start);
+        assert!(iter.next().is_some());
+        assert!(iter.next().is_none());
+        assert!(iter.status().is_ok());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
@@ -14,7 +14,7 @@
 //
 extern crate rocksdb;
 
-use rocksdb::{DB, Options};
+use rocksdb::{ColumnFamily, DB, Options, WriteBatch, WriteOptions};
 use std::thread;
 use std::sync::Arc;
 
@@ -65,3 +65,49 @@ pub fn test_multithreaded() {
     }
     assert!(DB::destroy(&Options::default(), path).is_ok());
 }
+
+#[test]
+pub fn test_shared_types_are_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_send<T: Send>() {}
+
+    assert_send_sync::<DB>();
+    assert_send_sync::<ColumnFamily>();
+    assert_send_sync::<WriteOptions>();
+    assert_send::<Options>();
+    assert_send::<WriteBatch>();
+}
+
+#[test]
+pub fn test_multithreaded_cf() {
+    let path = "_rust_rocksdb_multithreadtest_cf";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        let mut db = DB::open(&opts, path).unwrap();
+        db.create_cf("cf1", &Options::default()).unwrap();
+        let db = Arc::new(db);
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let db = db.clone();
+                thread::spawn(move || {
+                    let cf = db.cf_handle("cf1").unwrap();
+                    let mut batch = WriteBatch::default();
+                    batch.put_cf(cf, format!("key{}", i).as_bytes(), b"value").unwrap();
+                    db.write(batch).unwrap();
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let cf = db.cf_handle("cf1").unwrap();
+        for i in 0..4 {
+            let key = format!("key{}", i);
+            assert_eq!(&*db.get_cf(cf, key.as_bytes()).unwrap().unwrap(), b"value");
+        }
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
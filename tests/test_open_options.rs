@@ -0,0 +1,86 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use rocksdb::{ColumnFamilyDescriptor, DB, ErrorKind, OpenOptions, Options};
+use std::time::Duration;
+
+#[test]
+fn test_open_options_cfs() {
+    let path = "_rust_rocksdb_open_options_cfs_test";
+    {
+        assert!(OpenOptions::new().open(path).is_err());
+
+        let db = OpenOptions::new()
+            .create_if_missing(true)
+            .create_missing_column_families(true)
+            .column_families(vec![
+                ColumnFamilyDescriptor::new("cf1", Options::default()),
+                ColumnFamilyDescriptor::new("cf2", Options::default()),
+            ])
+            .open(path)
+            .unwrap();
+        db.put_cf(db.cf_handle("cf1").unwrap(), b"k1", b"v1").unwrap();
+    }
+    {
+        let err = OpenOptions::new()
+            .create_if_missing(true)
+            .error_if_exists(true)
+            .open(path)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_open_options_read_only() {
+    let path = "_rust_rocksdb_open_options_read_only_test";
+    {
+        assert!(OpenOptions::new().read_only(false).open(path).is_err());
+
+        let db = DB::open_default(path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.flush().unwrap();
+    }
+    {
+        let db = OpenOptions::new().read_only(true).open(path).unwrap();
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+        assert_eq!(db.put(b"k2", b"v2").unwrap_err().kind(), ErrorKind::NotSupported);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_open_options_ttl() {
+    let path = "_rust_rocksdb_open_options_ttl_test";
+    {
+        let db = OpenOptions::new()
+            .create_if_missing(true)
+            .ttl(Duration::from_secs(3600))
+            .open(path)
+            .unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    {
+        let cfs = OpenOptions::new()
+            .ttl(Duration::from_secs(3600))
+            .column_family(ColumnFamilyDescriptor::new("cf1", Options::default()))
+            .open(path);
+        assert_eq!(cfs.unwrap_err().kind(), ErrorKind::NotSupported);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
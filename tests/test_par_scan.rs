@@ -0,0 +1,70 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+#![cfg(feature = "rayon")]
+
+extern crate rocksdb;
+
+use rocksdb::{DB, Options};
+use rocksdb::par_scan::{self, KeyRange};
+
+fn key(i: u32) -> Vec<u8> {
+    format!("k{:05}", i).into_bytes()
+}
+
+#[test]
+pub fn test_par_scan() {
+    let path = "_rust_rocksdb_par_scan";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_target_file_size_base(64 << 10);
+        let mut db = DB::open(&opts, path).unwrap();
+        for i in 0..10000 {
+            db.put(&key(i), &[b'v'; 100]).unwrap();
+        }
+        db.compact_range(None, None);
+
+        let all = KeyRange::all();
+        let partitions = par_scan::split_range(&db, None, &all, 4).unwrap();
+        assert!(partitions.len() > 1 && partitions.len() <= 4);
+        assert_eq!(partitions[0].start, None);
+        assert_eq!(partitions.last().unwrap().end, None);
+        for pair in partitions.windows(2) {
+            assert!(pair[0].end.is_some());
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+
+        let range = KeyRange::new(&key(100), &key(9000));
+        let partitions = par_scan::split_range(&db, None, &range, 4).unwrap();
+        assert_eq!(partitions[0].start, range.start);
+        assert_eq!(partitions.last().unwrap().end, range.end);
+
+        let ranges = [range, KeyRange::new(&key(9500), &key(9600))];
+        let counts = par_scan::par_scan(&db, None, &ranges, || 0, |n, _, _| n + 1).unwrap();
+        assert_eq!(counts.iter().sum::<usize>(), 8900 + 100);
+
+        let ranges = [KeyRange::all()];
+        let first = par_scan::par_scan(&db, None, &ranges, || None, |first, key, _| {
+            first.or_else(|| Some(key.to_vec()))
+        }).unwrap();
+        assert_eq!(first[0], Some(key(0)));
+
+        // The files of other column families give no split points.
+        db.create_cf("empty", &Options::default()).unwrap();
+        let empty = db.cf_handle("empty").unwrap();
+        assert_eq!(par_scan::split_range(&db, Some(empty), &all, 4).unwrap(), vec![all.clone()]);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
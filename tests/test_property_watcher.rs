@@ -0,0 +1,66 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use rocksdb::{DB, Options};
+use rocksdb::property_watcher::{self, PropertyWatcher};
+
+#[test]
+pub fn test_property_watcher() {
+    let path = "_rust_rocksdb_property_watcher";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.enable_statistics();
+        let db = Arc::new(DB::open(&opts, path).unwrap());
+        db.put(b"k1", b"v1").unwrap();
+
+        let properties = vec!["rocksdb.num-entries-active-mem-table".to_owned()];
+        let sample = property_watcher::sample(&db, &properties, None);
+        assert_eq!(sample.properties.get(&properties[0]), Some(&1));
+        assert!(sample.statistics.is_none());
+
+        let (samples, received) = mpsc::channel();
+        let watcher = PropertyWatcher::new(Duration::from_millis(10))
+            .property("rocksdb.num-entries-active-mem-table")
+            .property("rocksdb.not-a-property")
+            .statistics(opts)
+            .spawn(&db, move |sample| {
+                let _ = samples.send(sample.clone());
+            });
+        let first = received.recv().unwrap();
+        assert_eq!(first.properties.len(), 1);
+        let stats = first.statistics.unwrap();
+        assert!(stats.tickers.contains_key("rocksdb.number.keys.written"));
+
+        db.put(b"k2", b"v2").unwrap();
+        loop {
+            let sample = received.recv().unwrap();
+            if sample.properties.get("rocksdb.num-entries-active-mem-table") == Some(&2) {
+                break;
+            }
+        }
+
+        // The watcher stops once the database is dropped.
+        drop(db);
+        while received.recv().is_ok() {}
+        drop(watcher);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
@@ -152,3 +152,24 @@ pub fn test_seek_for_prev() {
     assert_eq!(iter.key(), Some(b"k2".to_vec()));
     assert_eq!(iter.value(), Some(b"v2".to_vec()));
 }
+
+#[test]
+pub fn test_pinned() {
+    let db = setup_test_db("pinned");
+    db.put(b"k1", b"v1").unwrap();
+    db.put(b"k2", b"v2").unwrap();
+
+    let mut iter = db.raw_iterator();
+    iter.seek_to_first();
+    assert_eq!(iter.key_pinned(), Some(&b"k1"[..]));
+    assert_eq!(iter.value_pinned(), Some(&b"v1"[..]));
+
+    let key = iter.key_pinned().map(|key| key.to_vec());
+    iter.next();
+    assert_eq!(key, Some(b"k1".to_vec()));
+    assert_eq!(iter.key_pinned(), Some(&b"k2"[..]));
+
+    iter.next();
+    assert!(iter.key_pinned().is_none());
+    assert!(iter.value_pinned().is_none());
+}
@@ -0,0 +1,176 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use rocksdb::{DB, Error, ErrorKind, Options};
+use rocksdb::backup::RestoreOptions;
+use rocksdb::remote_backup::{ObjectStore, RemoteBackup};
+
+#[derive(Default)]
+struct MemoryStore {
+    objects: Mutex<BTreeMap<String, Vec<u8>>>,
+    uploads: Mutex<Vec<(String, usize)>>,
+}
+
+impl ObjectStore for MemoryStore {
+    fn put(&self, name: &str, data: &[u8]) -> Result<(), Error> {
+        self.objects.lock().unwrap().insert(name.to_owned(), data.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.objects.lock().unwrap().get(name).cloned())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let objects = self.objects.lock().unwrap();
+        Ok(objects.keys().filter(|name| name.starts_with(prefix)).cloned().collect())
+    }
+
+    fn put_multipart(
+        &self,
+        name: &str,
+        parts: &mut dyn Iterator<Item = Result<Vec<u8>, Error>>,
+    ) -> Result<(), Error> {
+        let mut data = Vec::new();
+        let mut count = 0;
+        for part in parts {
+            data.extend_from_slice(&try!(part));
+            count += 1;
+        }
+        self.uploads.lock().unwrap().push((name.to_owned(), count));
+        self.put(name, &data)
+    }
+}
+
+#[test]
+pub fn test_remote_backup() {
+    let path = "_rust_rocksdb_remote_backup";
+    let staging = "_rust_rocksdb_remote_backup_staging";
+    let restore_staging = "_rust_rocksdb_remote_backup_restore_staging";
+    let restored = "_rust_rocksdb_remote_backup_restored";
+    let restored_first = "_rust_rocksdb_remote_backup_restored_first";
+    {
+        let mut backup = RemoteBackup::new(MemoryStore::default(), staging);
+        backup.set_prefix("db/");
+        backup.set_part_size(64);
+        let err = backup.restore(restored, restored, &RestoreOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        let db = DB::open_default(path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        backup.create(&db).unwrap();
+        {
+            let uploads = backup.store().uploads.lock().unwrap();
+            assert!(uploads.iter().all(|&(ref name, _)| name.starts_with("db/")));
+            assert!(uploads.iter().any(|&(_, parts)| parts > 1));
+            assert!(uploads.last().unwrap().0.starts_with("db/meta/"));
+        }
+
+        // Only the new files and metadata of the second backup are uploaded.
+        let first = backup.store().list("").unwrap();
+        backup.store().uploads.lock().unwrap().clear();
+        db.put(b"k2", b"v2").unwrap();
+        backup.create(&db).unwrap();
+        let uploads = backup.store().uploads.lock().unwrap().clone();
+        assert!(!uploads.is_empty());
+        assert!(uploads.iter().all(|&(ref name, _)| !first.contains(name)));
+
+        let mut remote = RemoteBackup::new(MemoryStore::default(), restore_staging);
+        for name in backup.store().list("").unwrap() {
+            let data = backup.store().get(&name).unwrap().unwrap();
+            remote.store().put(&name, &data).unwrap();
+        }
+        remote.set_prefix("db/");
+        remote.restore(restored, restored, &RestoreOptions::default()).unwrap();
+        assert!(Path::new(restore_staging).join("db/restore/meta").is_dir());
+        {
+            let db = DB::open_default(restored).unwrap();
+            assert_eq!(&*db.get(b"k2").unwrap().unwrap(), b"v2");
+        }
+
+        // Restoring the first backup downloads again a file corrupted in the
+        // staging directory.
+        let ids = remote.backup_ids().unwrap();
+        assert_eq!(ids.len(), 2);
+        let shared = Path::new(restore_staging).join("db/restore/shared");
+        for entry in fs::read_dir(&shared).unwrap() {
+            fs::write(entry.unwrap().path(), b"corrupted").unwrap();
+        }
+        let err = remote
+            .restore_backup(ids[1] + 1, restored_first, restored_first, &RestoreOptions::default())
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        remote.restore_backup(ids[0], restored_first, restored_first, &RestoreOptions::default())
+            .unwrap();
+        let db = DB::open_default(restored_first).unwrap();
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+        assert!(db.get(b"k2").unwrap().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+    assert!(DB::destroy(&Options::default(), restored).is_ok());
+    assert!(DB::destroy(&Options::default(), restored_first).is_ok());
+    let _ = fs::remove_dir_all(staging);
+    let _ = fs::remove_dir_all(restore_staging);
+}
+
+#[test]
+pub fn test_create_after_restore() {
+    let path = "_rust_rocksdb_remote_backup_create_after_restore";
+    let staging = "_rust_rocksdb_remote_backup_create_after_restore_staging";
+    let restored = "_rust_rocksdb_remote_backup_create_after_restore_restored";
+    {
+        let mut backup = RemoteBackup::new(MemoryStore::default(), staging);
+        let db = DB::open_default(path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        backup.create(&db).unwrap();
+        db.put(b"k2", b"v2").unwrap();
+        backup.create(&db).unwrap();
+        let ids = backup.backup_ids().unwrap();
+        assert_eq!(ids.len(), 2);
+
+        // Restoring an older backup from the same staging directory does not
+        // make the next backup reuse the id of a newer one.
+        backup.restore_backup(ids[0], restored, restored, &RestoreOptions::default()).unwrap();
+        db.put(b"k3", b"v3").unwrap();
+        backup.create(&db).unwrap();
+        let new_ids = backup.backup_ids().unwrap();
+        assert_eq!(new_ids.len(), 3);
+        assert!(new_ids[2] > ids[1]);
+
+        backup.restore(restored, restored, &RestoreOptions::default()).unwrap();
+        let restored_db = DB::open_default(restored).unwrap();
+        assert_eq!(&*restored_db.get(b"k3").unwrap().unwrap(), b"v3");
+    }
+    {
+        // A backup reusing the id of one already in the store is refused.
+        let store = MemoryStore::default();
+        let _ = fs::remove_dir_all(staging);
+        let mut backup = RemoteBackup::new(store, staging);
+        let db = DB::open_default(path).unwrap();
+        backup.create(&db).unwrap();
+        let _ = fs::remove_dir_all(staging);
+        let err = backup.create(&db).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+    assert!(DB::destroy(&Options::default(), restored).is_ok());
+    let _ = fs::remove_dir_all(staging);
+}
@@ -0,0 +1,146 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use std::sync::Arc;
+
+use rocksdb::{DB, Options, WriteBatch};
+use rocksdb::replication::{self, Follower, ReplicationStream, Update};
+
+fn open_follower(path: &str) -> Follower {
+    Follower::new(DB::open_default(path).unwrap())
+}
+
+#[test]
+pub fn test_replication() {
+    let leader_path = "_rust_rocksdb_replication_leader";
+    let follower_path = "_rust_rocksdb_replication_follower";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        replication::retain_wal(&mut opts, 3600, 0);
+        let leader = Arc::new(DB::open(&opts, leader_path).unwrap());
+
+        leader.put(b"k1", b"v1").unwrap();
+        let mut batch = WriteBatch::default();
+        batch.put(b"k2", b"v2").unwrap();
+        batch.put(b"k3", b"v3").unwrap();
+        leader.write(batch).unwrap();
+        leader.delete(b"k1").unwrap();
+
+        {
+            let mut follower = open_follower(follower_path);
+            assert_eq!(follower.last_applied().unwrap(), 0);
+
+            let mut stream = ReplicationStream::new(leader.clone(), 0);
+            let updates = stream.poll(2).unwrap();
+            assert_eq!(updates.len(), 2);
+            assert_eq!(updates[1].sequence(), 2);
+            assert_eq!(updates[1].last_sequence(), 3);
+            assert_eq!(stream.last_sequence(), 3);
+            follower.apply_all(&updates).unwrap();
+            assert_eq!(follower.last_applied().unwrap(), 3);
+            assert_eq!(&*follower.db().get(b"k1").unwrap().unwrap(), b"v1");
+
+            // Applying an update again is a no-op.
+            assert!(!follower.apply(&updates[0]).unwrap());
+        }
+
+        // Resume from the recorded progress after a restart.
+        let mut follower = open_follower(follower_path);
+        let mut stream = ReplicationStream::new(leader.clone(), follower.last_applied().unwrap());
+        let updates = stream.poll(10).unwrap();
+        assert_eq!(updates.len(), 1);
+        let update = Update::from_parts(updates[0].sequence(), updates[0].data().to_vec());
+        assert_eq!(update, updates[0]);
+        assert!(follower.apply(&update).unwrap());
+        assert!(follower.db().get(b"k1").unwrap().is_none());
+        assert_eq!(&*follower.db().get(b"k3").unwrap().unwrap(), b"v3");
+        assert!(stream.poll(10).unwrap().is_empty());
+
+        leader.put(b"k4", b"v4").unwrap();
+        follower.apply_all(&stream.poll(10).unwrap()).unwrap();
+        assert_eq!(follower.last_applied().unwrap(), leader.latest_sequence_number());
+        assert_eq!(&*follower.db().get(b"k4").unwrap().unwrap(), b"v4");
+    }
+    assert!(DB::destroy(&Options::default(), leader_path).is_ok());
+    assert!(DB::destroy(&Options::default(), follower_path).is_ok());
+}
+
+#[test]
+pub fn test_leader_column_family() {
+    let leader_path = "_rust_rocksdb_replication_cf_leader";
+    let follower_path = "_rust_rocksdb_replication_cf_follower";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        replication::retain_wal(&mut opts, 3600, 0);
+        let mut leader = DB::open(&opts, leader_path).unwrap();
+        leader.create_cf("cf1", &Options::default()).unwrap();
+        let leader = Arc::new(leader);
+        {
+            let cf1 = leader.cf_handle("cf1").unwrap();
+            leader.put_cf(cf1, b"k1", b"v1").unwrap();
+        }
+
+        // The follower has the column families of the leader and nothing
+        // else, so the update lands in its cf1 and not in the progress.
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open_cf(&opts, follower_path, &["cf1"]).unwrap();
+        let mut follower = Follower::new(db);
+        let mut stream = ReplicationStream::new(leader.clone(), 0);
+        follower.apply_all(&stream.poll(10).unwrap()).unwrap();
+        assert_eq!(follower.last_applied().unwrap(), 1);
+        let cf1 = follower.db().cf_handle("cf1").unwrap();
+        assert_eq!(&*follower.db().get_cf(cf1, b"k1").unwrap().unwrap(), b"v1");
+        assert!(follower.db().get(b"k1").unwrap().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), leader_path).is_ok());
+    assert!(DB::destroy(&Options::default(), follower_path).is_ok());
+}
+
+#[test]
+pub fn test_follower_new_at() {
+    let leader_path = "_rust_rocksdb_replication_new_at_leader";
+    let follower_path = "_rust_rocksdb_replication_new_at_follower";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        replication::retain_wal(&mut opts, 3600, 0);
+        let leader = Arc::new(DB::open(&opts, leader_path).unwrap());
+        leader.put(b"k1", b"v1").unwrap();
+        leader.put(b"k2", b"v2").unwrap();
+
+        let mut stream = ReplicationStream::from_oldest(leader.clone());
+        assert_eq!(stream.poll(10).unwrap().len(), 2);
+        assert_eq!(stream.last_sequence(), 2);
+
+        // A follower that already has the first update only gets the second.
+        let db = DB::open_default(follower_path).unwrap();
+        let mut follower = Follower::new_at(db, 1).unwrap();
+        assert_eq!(follower.last_applied().unwrap(), 1);
+        let mut stream = ReplicationStream::new(leader.clone(), follower.last_applied().unwrap());
+        let updates = stream.poll(10).unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].sequence(), 2);
+        follower.apply_all(&updates).unwrap();
+        assert!(follower.db().get(b"k1").unwrap().is_none());
+        assert_eq!(&*follower.db().get(b"k2").unwrap().unwrap(), b"v2");
+    }
+    assert!(DB::destroy(&Options::default(), leader_path).is_ok());
+    assert!(DB::destroy(&Options::default(), follower_path).is_ok());
+}
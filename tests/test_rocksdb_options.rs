@@ -14,7 +14,14 @@
 //
 extern crate rocksdb;
 
-use rocksdb::{DB, Options};
+use rocksdb::{BlockBasedIndexType, BlockBasedOptions, ColumnFamilyDescriptor, DB, DBCompactionStyle,
+              DBCompressionType, Env, FifoCompactOptions, IteratorMode, Options, StorageClass,
+              ThreadLimiter, UniversalCompactOptions, UniversalCompactionStopStyle,
+              get_approximate_memory_usage};
+
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
 
 #[test]
 fn test_set_num_levels() {
@@ -25,3 +32,450 @@ fn test_set_num_levels() {
     let db = DB::open(&opts, path).unwrap();
     drop(db);
 }
+
+#[test]
+fn test_clone_options() {
+    let path = "_rust_rocksdb_test_clone_options";
+    {
+        let mut base = Options::default();
+        base.create_if_missing(true);
+        base.create_missing_column_families(true);
+
+        let mut cf_opts = base.try_clone().unwrap();
+        cf_opts.set_write_buffer_size(8 * 1024 * 1024);
+        let mut strict = base.try_clone().unwrap();
+        strict.set_error_if_exists(true);
+
+        let cfs = vec![ColumnFamilyDescriptor::new("cf1", cf_opts)];
+        drop(DB::open_cf_descriptors(&base, path, cfs).unwrap());
+        assert!(DB::open(&strict, path).is_err());
+        assert!(DB::open_cf(&base, path, &["cf1"]).is_ok());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_two_level_index() {
+    let path = "_rust_rocksdb_test_two_level_index";
+    {
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_bloom_filter(10, false);
+        block_opts.set_index_type(BlockBasedIndexType::TwoLevelIndexSearch);
+        block_opts.set_cache_index_and_filter_blocks(true);
+        block_opts.set_pin_l0_filter_and_index_blocks_in_cache(true);
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_block_based_table_factory(&block_opts);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_bloom_filter() {
+    let path = "_rust_rocksdb_test_bloom_filter";
+    {
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_bloom_filter(10, false);
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_block_based_table_factory(&block_opts);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+        assert!(db.get(b"k2").unwrap().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_compression_per_level() {
+    let path = "_rust_rocksdb_test_compression_per_level";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_num_levels(3);
+        opts.set_compression_per_level(&[
+            DBCompressionType::None,
+            DBCompressionType::None,
+            DBCompressionType::Snappy,
+        ]);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_universal_compaction_options() {
+    let path = "_rust_rocksdb_test_universal_compaction_options";
+    {
+        let mut uco = UniversalCompactOptions::default();
+        uco.set_size_ratio(2);
+        uco.set_min_merge_width(2);
+        uco.set_max_merge_width(10);
+        uco.set_max_size_amplification_percent(150);
+        uco.set_compression_size_percent(-1);
+        uco.set_stop_style(UniversalCompactionStopStyle::Similar);
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_compaction_style(DBCompactionStyle::Universal);
+        opts.set_universal_compaction_options(&uco);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_fifo_compaction_options() {
+    let path = "_rust_rocksdb_test_fifo_compaction_options";
+    {
+        let mut fifo_opts = FifoCompactOptions::default();
+        fifo_opts.set_max_table_files_size(1024 * 1024);
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_compaction_style(DBCompactionStyle::Fifo);
+        opts.set_fifo_compaction_options(&fifo_opts);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_level_compaction_tuning() {
+    let path = "_rust_rocksdb_test_level_compaction_tuning";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_level_compaction_dynamic_level_bytes(true);
+        opts.set_max_bytes_for_level_base(64 * 1024 * 1024);
+        opts.set_max_bytes_for_level_multiplier(8.0);
+        opts.set_target_file_size_base(8 * 1024 * 1024);
+        opts.set_target_file_size_multiplier(2);
+        opts.set_max_compaction_bytes(256 * 1024 * 1024);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_subcompactions() {
+    let path = "_rust_rocksdb_test_subcompactions";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_max_subcompactions(4);
+        opts.set_compaction_readahead_size(2 * 1024 * 1024);
+
+        let db = DB::open(&opts, path).unwrap();
+        for i in 0..100u32 {
+            db.put(format!("k{}", i).as_bytes(), b"v").unwrap();
+        }
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k42").unwrap().unwrap(), b"v");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_auto_tuned() {
+    let path = "_rust_rocksdb_test_auto_tuned";
+    for &storage in &[StorageClass::Ssd, StorageClass::Hdd] {
+        {
+            let mut opts = Options::auto_tuned(256 * 1024 * 1024, 2, storage);
+            opts.create_if_missing(true);
+            let db = DB::open(&opts, path).unwrap();
+            db.put(b"k1", b"v1").unwrap();
+            db.compact_range(None, None);
+            assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+        }
+        assert!(DB::destroy(&Options::default(), path).is_ok());
+    }
+}
+
+#[test]
+fn test_bulk_load() {
+    let path = "_rust_rocksdb_test_bulk_load";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        opts.prepare_for_bulk_load();
+        let db = DB::open_cf(&opts, path, &["cf1"]).unwrap();
+        for i in 0..1000u32 {
+            let key = format!("k{}", i);
+            db.put(key.as_bytes(), b"v").unwrap();
+            db.put_cf(db.cf_handle("cf1").unwrap(), key.as_bytes(), b"v").unwrap();
+        }
+        db.finish_bulk_load();
+        assert_eq!(db.property_int_value("rocksdb.num-files-at-level0").unwrap(), Some(0));
+    }
+    {
+        let db = DB::open_cf(&Options::default(), path, &["cf1"]).unwrap();
+        let cf1 = db.cf_handle("cf1").unwrap();
+        assert_eq!(&*db.get_cf(cf1, b"k42").unwrap().unwrap(), b"v");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_memory_budget() {
+    let path = "_rust_rocksdb_test_memory_budget";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        opts.set_db_write_buffer_size(1024 * 1024);
+        opts.set_max_total_wal_size(4 * 1024 * 1024);
+        opts.set_max_open_files(64);
+
+        let db = DB::open_cf(&opts, path, &["cf1", "cf2"]).unwrap();
+        let value = vec![b'x'; 1024];
+        for i in 0..2048u32 {
+            let key = format!("k{}", i);
+            db.put_cf(db.cf_handle("cf1").unwrap(), key.as_bytes(), &value).unwrap();
+            db.put_cf(db.cf_handle("cf2").unwrap(), key.as_bytes(), &value).unwrap();
+        }
+        let cf1 = db.cf_handle("cf1").unwrap();
+        assert_eq!(&*db.get_cf(cf1, b"k42").unwrap().unwrap(), &value[..]);
+
+        // About 4MB were written, so hitting the limits has switched the
+        // memtables of both column families before their 2048 writes.
+        for name in &["cf1", "cf2"] {
+            let cf = db.cf_handle(name).unwrap();
+            let entries = db.property_int_value_cf(cf, "rocksdb.num-entries-active-mem-table");
+            assert!(entries.unwrap().unwrap() < 2048);
+        }
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_background_threads() {
+    let path = "_rust_rocksdb_test_background_threads";
+    {
+        let mut env = Env::default();
+        env.set_background_threads(4);
+        env.set_high_priority_background_threads(2);
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.increase_parallelism(4);
+        opts.set_max_background_jobs(6);
+        opts.set_env(&env);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_thread_limiter() {
+    let path = "_rust_rocksdb_test_thread_limiter";
+    {
+        assert!(ThreadLimiter::new(Env::mem_env(), 4, 5, 1).is_err());
+        let limiter = ThreadLimiter::new(Env::mem_env(), 4, 2, 1).unwrap();
+        assert_eq!(limiter.compaction_threads(), 2);
+        assert_eq!(limiter.flush_threads(), 1);
+        assert!(limiter.set_compaction_threads(0).is_err());
+        assert!(limiter.set_flush_threads(5).is_err());
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        limiter.apply(&mut opts);
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+
+        let shared = limiter.clone();
+        shared.set_compaction_threads(3).unwrap();
+        shared.set_flush_threads(2).unwrap();
+        assert_eq!(limiter.compaction_threads(), 3);
+        assert_eq!(limiter.flush_threads(), 2);
+
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+}
+
+#[test]
+fn test_concurrent_writes() {
+    let path = "_rust_rocksdb_test_concurrent_writes";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_allow_concurrent_memtable_write(true);
+        opts.set_enable_write_thread_adaptive_yield(true);
+
+        let db = Arc::new(DB::open(&opts, path).unwrap());
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let db = db.clone();
+                thread::spawn(move || for j in 0..100 {
+                    db.put(format!("k{}-{}", i, j).as_bytes(), b"v").unwrap();
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        assert_eq!(&*db.get(b"k3-99").unwrap().unwrap(), b"v");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_write_thread_adaptive_yield() {
+    let path = "_rust_rocksdb_test_write_thread_adaptive_yield";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_enable_write_thread_adaptive_yield(false);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_wal_options() {
+    let path = "_rust_rocksdb_test_wal_options";
+    let wal_path = "_rust_rocksdb_test_wal_options_wal";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_wal_dir(wal_path);
+        opts.set_wal_ttl_seconds(60);
+        opts.set_wal_size_limit_mb(16);
+        opts.set_recycle_log_file_num(2);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        assert!(Path::new(wal_path).read_dir().unwrap().next().is_some());
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    let mut opts = Options::default();
+    opts.set_wal_dir(wal_path);
+    assert!(DB::destroy(&opts, path).is_ok());
+}
+
+#[test]
+fn test_mmap_reads() {
+    let path = "_rust_rocksdb_test_mmap_reads";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_allow_mmap_reads(true);
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_mem_env() {
+    let path = "_rust_rocksdb_test_mem_env";
+    let env = Env::mem_env();
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.set_env(&env);
+    {
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+    }
+    {
+        let db = DB::open(&opts, path).unwrap();
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(!Path::new(path).exists());
+    assert!(DB::destroy(&opts, path).is_ok());
+}
+
+#[test]
+fn test_options_from_string() {
+    let path = "_rust_rocksdb_test_options_from_string";
+    {
+        let opts = Options::from_string(
+            &Options::default(),
+            "create_if_missing=true;write_buffer_size=64m;max_write_buffer_number=4",
+        ).unwrap();
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+
+    assert!(Options::from_string(&Options::default(), "no_such_option=1").is_err());
+    assert!(Options::from_string(&Options::default(), "write_buffer_size=lots").is_err());
+}
+
+#[test]
+fn test_approximate_memory_usage() {
+    let path = "_rust_rocksdb_test_approximate_memory_usage";
+    {
+        let db = DB::open_default(path).unwrap();
+        let before = get_approximate_memory_usage(&[&db]).unwrap();
+        for i in 0..1000u32 {
+            db.put(format!("key{}", i).as_bytes(), &[0u8; 100]).unwrap();
+        }
+        let after = get_approximate_memory_usage(&[&db]).unwrap();
+        assert!(after.mem_table_total > before.mem_table_total);
+        assert!(after.mem_table_unflushed <= after.mem_table_total);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_closure_comparator() {
+    let path = "_rust_rocksdb_closure_comparator_test";
+    {
+        let reverse = true;
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_comparator("reverse", move |a: &[u8], b: &[u8]| if reverse {
+            b.cmp(a)
+        } else {
+            a.cmp(b)
+        });
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.put(b"k2", b"v2").unwrap();
+        db.put(b"k3", b"v3").unwrap();
+        let keys: Vec<_> = db.iterator(IteratorMode::Start).map(|(k, _)| k.to_vec()).collect();
+        assert_eq!(keys, vec![b"k3".to_vec(), b"k2".to_vec(), b"k1".to_vec()]);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
@@ -1,6 +1,11 @@
 extern crate rocksdb;
 
-use rocksdb::{DB, Options, SliceTransform};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use rocksdb::{BlockBasedOptions, DB, ErrorKind, IteratorMode, MemtableFactory, Options, ReadOptions,
+              SliceTransform, SliceTransformFns, validate_slice_transform};
 
 #[test]
 pub fn test_slice_transform() {
@@ -46,3 +51,258 @@ pub fn test_slice_transform() {
         assert_eq!(b_iterator.collect::<Vec<_>>(), expected)
     }
 }
+
+#[test]
+pub fn test_memtable_prefix_bloom() {
+    let path = "_rust_rocksdb_memtable_prefix_bloom_test";
+    {
+        let mut block_opts = BlockBasedOptions::default();
+        block_opts.set_bloom_filter(10, false);
+        block_opts.set_whole_key_filtering(false);
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(3));
+        opts.set_memtable_prefix_bloom_ratio(0.1);
+        opts.set_block_based_table_factory(&block_opts);
+
+        let db = DB::open(&opts, path).unwrap();
+        assert!(db.put(b"aaa1", b"a1").is_ok());
+        assert!(db.put(b"bbb1", b"b1").is_ok());
+
+        let keys: Vec<_> = db.prefix_iterator(b"aaa").map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![b"aaa1".to_vec().into_boxed_slice()]);
+        assert!(db.prefix_iterator(b"ccc").next().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_hash_skiplist_memtable() {
+    let path = "_rust_rocksdb_hash_skiplist_memtable_test";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(3));
+        opts.set_allow_concurrent_memtable_write(false);
+        opts.set_memtable_factory(MemtableFactory::HashSkipList {
+            bucket_count: 1000,
+            height: 4,
+            branching_factor: 4,
+        });
+
+        let db = DB::open(&opts, path).unwrap();
+        assert!(db.put(b"aaa1", b"a1").is_ok());
+        assert!(db.put(b"aaa2", b"a2").is_ok());
+        assert!(db.put(b"bbb1", b"b1").is_ok());
+
+        let keys: Vec<_> = db.prefix_iterator(b"aaa").map(|(k, _)| k).collect();
+        assert_eq!(keys,
+                   vec![b"aaa1".to_vec().into_boxed_slice(), b"aaa2".to_vec().into_boxed_slice()]);
+        assert_eq!(&*db.get(b"bbb1").unwrap().unwrap(), b"b1");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_total_order_seek() {
+    let path = "_rust_rocksdb_total_order_seek_test";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(3));
+        opts.set_allow_concurrent_memtable_write(false);
+        opts.set_memtable_factory(MemtableFactory::HashSkipList {
+            bucket_count: 1000,
+            height: 4,
+            branching_factor: 4,
+        });
+
+        let db = DB::open(&opts, path).unwrap();
+        assert!(db.put(b"bbb1", b"b1").is_ok());
+        assert!(db.put(b"aaa1", b"a1").is_ok());
+        assert!(db.put(b"ccc1", b"c1").is_ok());
+
+        let mut readopts = ReadOptions::default();
+        readopts.set_total_order_seek(true);
+        let keys: Vec<_> = db.iterator_opt(IteratorMode::Start, readopts)
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                b"aaa1".to_vec().into_boxed_slice(),
+                b"bbb1".to_vec().into_boxed_slice(),
+                b"ccc1".to_vec().into_boxed_slice(),
+            ]
+        );
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_capped_prefix() {
+    let path = "_rust_rocksdb_capped_prefix_test";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(SliceTransform::create_capped_prefix(3));
+        opts.set_memtable_prefix_bloom_ratio(0.1);
+
+        let db = DB::open(&opts, path).unwrap();
+        assert!(db.put(b"a", b"a").is_ok());
+        assert!(db.put(b"aaa1", b"a1").is_ok());
+        assert!(db.put(b"aaa2", b"a2").is_ok());
+        assert!(db.put(b"bbb1", b"b1").is_ok());
+
+        let keys: Vec<_> = db.prefix_iterator(b"aaa").map(|(k, _)| k).collect();
+        assert_eq!(
+            keys,
+            vec![b"aaa1".to_vec().into_boxed_slice(), b"aaa2".to_vec().into_boxed_slice()]
+        );
+        assert_eq!(db.get(b"a").unwrap().unwrap().to_utf8(), Some("a"));
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_slice_transform_fns() {
+    struct UntilDot;
+
+    impl SliceTransformFns for UntilDot {
+        fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+            let end = key.iter().position(|&b| b == b'.').unwrap();
+            &key[..end + 1]
+        }
+
+        fn in_domain(&self, key: &[u8]) -> bool {
+            key.contains(&b'.')
+        }
+    }
+
+    let path = "_rust_rocksdb_slice_transform_fns_test";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(SliceTransform::create_from("until_dot", UntilDot));
+        opts.set_memtable_prefix_bloom_ratio(0.1);
+
+        let db = DB::open(&opts, path).unwrap();
+        assert!(db.put(b"a.1", b"a1").is_ok());
+        assert!(db.put(b"ab.1", b"ab1").is_ok());
+        assert!(db.put(b"nodot", b"nodot").is_ok());
+
+        let keys: Vec<_> = db.prefix_iterator(b"a.").map(|(k, _)| k).collect();
+        assert_eq!(keys, vec![b"a.1".to_vec().into_boxed_slice()]);
+        assert_eq!(db.get(b"nodot").unwrap().unwrap().to_utf8(), Some("nodot"));
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_slice_transform_fns_concurrent() {
+    struct Counting {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl SliceTransformFns for Counting {
+        fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            &key[..1]
+        }
+
+        fn in_domain(&self, key: &[u8]) -> bool {
+            !key.is_empty()
+        }
+    }
+
+    let path = "_rust_rocksdb_slice_transform_fns_concurrent_test";
+    {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(
+            SliceTransform::create_from("counting", Counting { calls: calls.clone() }),
+        );
+        opts.set_memtable_prefix_bloom_ratio(0.1);
+
+        let db = Arc::new(DB::open(&opts, path).unwrap());
+        let threads: Vec<_> = (0..4u8)
+            .map(|i| {
+                let db = db.clone();
+                thread::spawn(move || for j in 0..100u8 {
+                    db.put(&[b'a' + i, j], b"v").unwrap();
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        assert_eq!(db.prefix_iterator(b"b").count(), 100);
+        assert!(calls.load(Ordering::SeqCst) >= 400);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+pub fn test_validate_slice_transform() {
+    // The first byte of keys longer than one byte: appending to a one byte
+    // key brings it into the domain.
+    struct LongKeys;
+
+    impl SliceTransformFns for LongKeys {
+        fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+            &key[..1]
+        }
+
+        fn in_domain(&self, key: &[u8]) -> bool {
+            key.len() > 1
+        }
+    }
+
+    // Everything up to the last byte, so appending changes the prefix.
+    struct AllButLast;
+
+    impl SliceTransformFns for AllButLast {
+        fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+            &key[..key.len() - 1]
+        }
+
+        fn in_domain(&self, key: &[u8]) -> bool {
+            !key.is_empty()
+        }
+    }
+
+    struct FirstTwo;
+
+    impl SliceTransformFns for FirstTwo {
+        fn transform<'a>(&self, key: &'a [u8]) -> &'a [u8] {
+            &key[..2]
+        }
+
+        fn in_domain(&self, key: &[u8]) -> bool {
+            key.len() >= 2
+        }
+    }
+
+    let keys: &[&[u8]] = &[b"", b"a", b"ab", b"abc", b"b\xff"];
+    assert!(validate_slice_transform(&FirstTwo, keys).is_ok());
+
+    let err = validate_slice_transform(&LongKeys, keys).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidArgument);
+    assert!(validate_slice_transform(&AllButLast, keys).is_err());
+}
+
+#[test]
+pub fn test_unused_slice_transform() {
+    fn first_three(k: &[u8]) -> Vec<u8> {
+        k.iter().take(3).cloned().collect()
+    }
+
+    // Transforms that are never handed to an `Options` are freed on drop.
+    drop(SliceTransform::create("first_three", first_three, None));
+    drop(SliceTransform::create_fixed_prefix(3));
+    drop(SliceTransform::create_capped_prefix(3));
+    drop(SliceTransform::create_noop());
+}
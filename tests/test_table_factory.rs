@@ -0,0 +1,64 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use rocksdb::{CuckooTableOptions, DB, Options, PlainTableOptions, SliceTransform};
+
+#[test]
+fn test_plain_table() {
+    let path = "_rust_rocksdb_test_plain_table";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(4));
+        opts.set_plain_table_factory(&PlainTableOptions {
+            user_key_length: 0,
+            bloom_bits_per_key: 10,
+            hash_table_ratio: 0.75,
+            index_sparseness: 16,
+        });
+
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"user1", b"v1").unwrap();
+        db.put(b"user2", b"v2").unwrap();
+        db.compact_range(None, None);
+        assert_eq!(&*db.get(b"user1").unwrap().unwrap(), b"v1");
+        assert_eq!(&*db.get(b"user2").unwrap().unwrap(), b"v2");
+        assert!(db.get(b"user3").unwrap().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
+
+#[test]
+fn test_cuckoo_table() {
+    let path = "_rust_rocksdb_test_cuckoo_table";
+    {
+        let mut cuckoo_opts = CuckooTableOptions::default();
+        cuckoo_opts.set_hash_ratio(0.75);
+        cuckoo_opts.set_max_search_depth(50);
+
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_cuckoo_table_factory(&cuckoo_opts);
+
+        // Reading cuckoo table files needs mmap reads, so this only goes
+        // through the memtable.
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        assert_eq!(&*db.get(b"k1").unwrap().unwrap(), b"v1");
+        assert!(db.get(b"k2").unwrap().is_none());
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
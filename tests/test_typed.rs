@@ -0,0 +1,56 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+#![cfg(feature = "typed")]
+
+extern crate rocksdb;
+
+use rocksdb::{DB, Direction, ErrorKind, Options};
+use rocksdb::typed::TypedDb;
+
+#[test]
+pub fn test_typed_db() {
+    let path = "_rust_rocksdb_typedtest";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = DB::open_cf(&opts, path, &["scores"]).unwrap();
+
+        let names: TypedDb<u32, String> = TypedDb::new(db);
+        let (ada, grace) = ("ada".to_owned(), "grace".to_owned());
+        names.put_all(vec![(&256, &grace), (&1, &ada)]).unwrap();
+        assert_eq!(names.get(&1).unwrap(), Some(ada.clone()));
+        assert_eq!(names.get(&2).unwrap(), None);
+
+        // Keys are ordered numerically, not by their little-endian bytes.
+        let all: Vec<_> = names.iter().map(|r| r.unwrap()).collect();
+        assert_eq!(all, vec![(1, ada.clone()), (256, grace.clone())]);
+        let from: Vec<_> = names.iter_from(&2, Direction::Forward).map(|r| r.unwrap()).collect();
+        assert_eq!(from, vec![(256, grace.clone())]);
+
+        let scores: TypedDb<(String, u32), Vec<u32>> = names.column_family("scores").unwrap();
+        scores.put(&(ada.clone(), 2018), &vec![1, 2]).unwrap();
+        assert_eq!(scores.get(&(ada.clone(), 2018)).unwrap(), Some(vec![1, 2]));
+        assert!(names.column_family::<u32, u32>("missing").is_err());
+
+        names.delete(&1).unwrap();
+        assert_eq!(names.get(&1).unwrap(), None);
+
+        names.db().put(b"not a key", b"").unwrap();
+        let err = names.iter().last().unwrap().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::Corruption);
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}
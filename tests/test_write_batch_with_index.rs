@@ -0,0 +1,93 @@
+// Copyright 2018 Tyler Neely
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+extern crate rocksdb;
+
+use rocksdb::{DB, ErrorKind, IteratorMode, Options, WriteBatchWithIndex};
+
+#[test]
+pub fn test_write_batch_with_index() {
+    let path = "_rust_rocksdb_write_batch_with_index";
+    {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.set_append_merge_operator(b",");
+        let db = DB::open(&opts, path).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+        db.put(b"k2", b"v2").unwrap();
+        db.put(b"k4", b"v4").unwrap();
+        let snapshot = db.snapshot();
+        db.put(b"k2", b"v2-after-snapshot").unwrap();
+
+        let mut batch = WriteBatchWithIndex::default();
+        batch.put(b"k3", b"v3").unwrap();
+        batch.delete(b"k1").unwrap();
+        batch.put(b"k4", b"old").unwrap();
+        batch.put(b"k4", b"new").unwrap();
+        assert_eq!(batch.len(), 4);
+
+        // Reads from the batch alone.
+        assert_eq!(&*batch.get_from_batch(&opts, b"k3").unwrap().unwrap(), b"v3");
+        assert!(batch.get_from_batch(&opts, b"k1").unwrap().is_none());
+        assert!(batch.get_from_batch(&opts, b"k2").unwrap().is_none());
+
+        // Reads merged over the database and a snapshot of it.
+        assert!(batch.get_from_batch_and_db(&db, b"k1").unwrap().is_none());
+        let value = batch.get_from_batch_and_db(&db, b"k2").unwrap().unwrap();
+        assert_eq!(&*value, b"v2-after-snapshot");
+        assert_eq!(&*snapshot.get_with_batch(&batch, b"k2").unwrap().unwrap(), b"v2");
+        assert_eq!(&*snapshot.get_with_batch(&batch, b"k4").unwrap().unwrap(), b"new");
+
+        {
+            let entries: Vec<(Vec<u8>, Vec<u8>)> = batch
+                .iterator_with_base(&db, IteratorMode::Start)
+                .map(|(k, v)| (k.to_vec(), v.to_vec()))
+                .collect();
+            let expected: Vec<(Vec<u8>, Vec<u8>)> = vec![
+                (b"k2".to_vec(), b"v2-after-snapshot".to_vec()),
+                (b"k3".to_vec(), b"v3".to_vec()),
+                (b"k4".to_vec(), b"new".to_vec()),
+            ];
+            assert_eq!(entries, expected);
+
+            let mut iter = snapshot.iterator_with_batch(&batch, IteratorMode::End);
+            let (key, value) = iter.next().unwrap();
+            assert_eq!((&*key, &*value), (&b"k4"[..], &b"new"[..]));
+            let (key, value) = iter.next().unwrap();
+            assert_eq!((&*key, &*value), (&b"k3"[..], &b"v3"[..]));
+            let (key, value) = iter.next().unwrap();
+            assert_eq!((&*key, &*value), (&b"k2"[..], &b"v2"[..]));
+            assert!(iter.next().is_none());
+        }
+
+        // Merge operands are applied on top of the database.
+        let mut merges = WriteBatchWithIndex::default();
+        merges.merge(b"k2", b"m").unwrap();
+        assert_eq!(
+            &*merges.get_from_batch_and_db(&db, b"k2").unwrap().unwrap(),
+            b"v2-after-snapshot,m"
+        );
+        assert_eq!(
+            merges.get_from_batch(&opts, b"k2").err().unwrap().kind(),
+            ErrorKind::MergeInProgress
+        );
+
+        drop(snapshot);
+        db.write_with_index(batch).unwrap();
+        assert!(db.get(b"k1").unwrap().is_none());
+        assert_eq!(&*db.get(b"k3").unwrap().unwrap(), b"v3");
+        assert_eq!(&*db.get(b"k4").unwrap().unwrap(), b"new");
+    }
+    assert!(DB::destroy(&Options::default(), path).is_ok());
+}